@@ -1,16 +1,30 @@
 #[cfg(feature = "raspberry_pi")]
 pub mod raspberrypi;
+pub mod render;
+pub mod storage;
 
 use crate::app;
 use crate::app::ApplicationHandlerCallResult;
-use crate::config::{Config, Environment};
-use crate::domain::time::Duration;
-use crate::domain::{Message, TimingConfig, servos};
+use crate::config::{Config, Environment, Operators};
+use crate::domain::analysis;
+use crate::domain::clock_mode::{ClockMode, ClockSchedule};
+use crate::domain::injection::{InjectionStrategy, MessageToInject};
+use crate::domain::presets::EncodingPreset;
+use crate::domain::quiet_hours::{QuietHours, QuietHoursSubmissions};
+use crate::domain::recurring::{CatchUp, RecurringMessage, Schedule};
+use crate::domain::templates::MessageTemplate;
+use crate::domain::time::{Duration, TimeZone};
+use crate::domain::windows::{TimeOfDay, TimeWindow, TimeWindows, Weekday};
+use crate::domain::{
+    Encoding, EncodingPlane, Message, Shift, ShiftScope, ShutterLocation, ShutterPosition,
+    ShutterPositions, TimingConfig, servos,
+};
 use crate::errors::Result;
 use anyhow::anyhow;
 use log::debug;
 use prometheus::{CounterVec, HistogramOpts, HistogramVec, Opts, Registry, labels};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 use tokio::sync::broadcast;
@@ -37,27 +51,323 @@ struct TomlConfig {
     address: String,
     queue_size: usize,
     environment: String,
-    messages_to_inject: Vec<String>,
+    messages_to_inject: Vec<TomlMessageToInject>,
     timing: TomlTimingConfig,
+    encoding: Option<TomlEncodingConfig>,
+    transliterations: Option<HashMap<String, String>>,
+    queue_path: Option<String>,
+    operators: Option<HashMap<String, String>>,
+    park_position: Option<Vec<String>>,
+    time_zone: Option<String>,
+    recurring_messages: Option<Vec<TomlRecurringMessage>>,
+    recurring_messages_path: Option<String>,
+    quiet_hours: Option<TomlQuietHours>,
+    injection: Option<TomlInjectionConfig>,
+    clock: Option<TomlClockMode>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlMessageToInject {
+    Template(String),
+    Message {
+        message: String,
+        weight: Option<u32>,
+        min_interval: Option<u64>,
+    },
+}
+
+impl TryFrom<TomlMessageToInject> for MessageToInject {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: TomlMessageToInject) -> std::result::Result<Self, Self::Error> {
+        match value {
+            TomlMessageToInject::Template(template) => {
+                Ok(MessageToInject::new(MessageTemplate::new(template)?))
+            }
+            TomlMessageToInject::Message {
+                message,
+                weight,
+                min_interval,
+            } => {
+                let message_to_inject = MessageToInject::new(MessageTemplate::new(message)?);
+                let message_to_inject = match weight {
+                    Some(weight) => message_to_inject.with_weight(weight)?,
+                    None => message_to_inject,
+                };
+                Ok(match min_interval {
+                    Some(min_interval) => message_to_inject
+                        .with_min_interval(Duration::new_from_seconds(min_interval)),
+                    None => message_to_inject,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlInjectionConfig {
+    strategy: Option<String>,
+    seed: Option<u64>,
+    state_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TomlClockMode {
+    interval: Option<u64>,
+    on_quarter_hour: Option<bool>,
+    timing: Option<TomlClockTiming>,
+    windows: Option<Vec<TomlTimeWindow>>,
+}
+
+/// Durations which aren't given are taken from the timing of regular messages.
+#[derive(Deserialize)]
+struct TomlClockTiming {
+    show_character_for: Option<u64>,
+    pause_between_characters_for: Option<u64>,
+    pause_between_messages_for: Option<u64>,
+}
+
+impl TomlClockMode {
+    fn try_into_clock_mode(self, timing: &TimingConfig, time_zone: TimeZone) -> Result<ClockMode> {
+        let schedule = match (self.interval, self.on_quarter_hour) {
+            (Some(interval), None | Some(false)) => {
+                ClockSchedule::Interval(Duration::new_from_seconds(interval))
+            }
+            (None, Some(true)) => ClockSchedule::QuarterHour,
+            _ => {
+                return Err(
+                    anyhow!("clock needs either an interval or on_quarter_hour = true").into(),
+                );
+            }
+        };
+        let clock_mode = ClockMode::new(schedule)?;
+
+        let clock_mode = match self.timing {
+            Some(clock_timing) => {
+                let duration = |seconds: Option<u64>, default: &Duration| match seconds {
+                    Some(seconds) => Duration::new_from_seconds(seconds),
+                    None => default.clone(),
+                };
+                clock_mode.with_timing(TimingConfig::new(
+                    duration(clock_timing.show_character_for, timing.show_character_for()),
+                    duration(
+                        clock_timing.pause_between_characters_for,
+                        timing.pause_between_characters_for(),
+                    ),
+                    duration(
+                        clock_timing.pause_between_messages_for,
+                        timing.pause_between_messages_for(),
+                    ),
+                    timing
+                        .inject_message_if_no_next_message_after_pausing_between_messages_for()
+                        .clone(),
+                ))
+            }
+            None => clock_mode,
+        };
+
+        Ok(match self.windows {
+            Some(windows) => clock_mode.with_windows(toml_time_windows(windows, time_zone)?),
+            None => clock_mode,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlQuietHours {
+    submissions: Option<String>,
+    windows: Vec<TomlTimeWindow>,
+}
+
+#[derive(Deserialize)]
+struct TomlTimeWindow {
+    days: Vec<String>,
+    start: String,
+    end: String,
+}
+
+impl TomlQuietHours {
+    fn try_into_quiet_hours(self, time_zone: TimeZone) -> Result<QuietHours> {
+        let submissions = match self.submissions.as_deref() {
+            None | Some("reject") => QuietHoursSubmissions::Reject,
+            Some("hold") => QuietHoursSubmissions::Hold,
+            Some(other) => {
+                return Err(anyhow!("invalid quiet hours submissions: {}", other).into());
+            }
+        };
+        QuietHours::new(toml_time_windows(self.windows, time_zone)?, submissions)
+    }
+}
+
+fn toml_time_windows(windows: Vec<TomlTimeWindow>, time_zone: TimeZone) -> Result<TimeWindows> {
+    Ok(TimeWindows::new(
+        time_zone,
+        windows
+            .into_iter()
+            .map(TimeWindow::try_from)
+            .collect::<Result<Vec<_>>>()?,
+    ))
+}
+
+impl TryFrom<TomlTimeWindow> for TimeWindow {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: TomlTimeWindow) -> std::result::Result<Self, Self::Error> {
+        let days = value
+            .days
+            .iter()
+            .map(|day| match day.as_str() {
+                "mon" => Ok(Weekday::Monday),
+                "tue" => Ok(Weekday::Tuesday),
+                "wed" => Ok(Weekday::Wednesday),
+                "thu" => Ok(Weekday::Thursday),
+                "fri" => Ok(Weekday::Friday),
+                "sat" => Ok(Weekday::Saturday),
+                "sun" => Ok(Weekday::Sunday),
+                other => Err(anyhow!("invalid day: {}", other).into()),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        TimeWindow::new(
+            &days,
+            toml_time_of_day(&value.start)?,
+            toml_time_of_day(&value.end)?,
+        )
+    }
+}
+
+fn toml_time_of_day(value: &str) -> Result<TimeOfDay> {
+    let invalid = || anyhow!("invalid time of day, expected HH:MM: {}", value);
+    let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+    TimeOfDay::new(
+        hour.parse().map_err(|_| invalid())?,
+        minute.parse().map_err(|_| invalid())?,
+    )
+}
+
+#[derive(Deserialize)]
+struct TomlRecurringMessage {
+    name: String,
+    schedule: String,
+    message: String,
+    catch_up: Option<String>,
+}
+
+impl TryFrom<TomlRecurringMessage> for RecurringMessage {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: TomlRecurringMessage) -> std::result::Result<Self, Self::Error> {
+        let catch_up = match value.catch_up.as_deref() {
+            None | Some("skip") => CatchUp::Skip,
+            Some("once") => CatchUp::Once,
+            Some("all") => CatchUp::All,
+            Some(other) => return Err(anyhow!("invalid catch up policy: {}", other).into()),
+        };
+        RecurringMessage::new(
+            value.name,
+            Schedule::new(value.schedule)?,
+            Message::new(value.message)?,
+            catch_up,
+        )
+    }
 }
 
 impl TryFrom<TomlConfig> for Config {
     type Error = crate::errors::Error;
 
     fn try_from(value: TomlConfig) -> std::result::Result<Self, Self::Error> {
-        let messages_to_inject: Vec<Message> = value
+        let messages_to_inject: Vec<MessageToInject> = value
             .messages_to_inject
             .into_iter()
-            .map(Message::new)
+            .map(MessageToInject::try_from)
             .collect::<Result<Vec<_>>>()?;
 
-        Config::new(
+        let config = Config::new(
             value.address,
             value.queue_size,
             value.environment.try_into()?,
             messages_to_inject,
             value.timing.try_into()?,
-        )
+            match value.encoding {
+                Some(encoding) => encoding.try_into()?,
+                None => Encoding::default(),
+            },
+            value.transliterations.unwrap_or_default(),
+        )?;
+
+        let config = match value.queue_path {
+            Some(queue_path) => config.with_queue_path(queue_path),
+            None => config,
+        };
+
+        let config = match value.operators {
+            Some(operators) => config.with_operators(Operators::new(operators)?),
+            None => config,
+        };
+
+        let config = match value.park_position {
+            Some(park_position) => {
+                config.with_park_position(toml_shutter_positions(park_position)?)
+            }
+            None => config,
+        };
+
+        let config = match value.time_zone {
+            Some(time_zone) => config.with_time_zone(TimeZone::new_from_name(&time_zone)?),
+            None => config,
+        };
+
+        let config = match value.recurring_messages {
+            Some(recurring_messages) => config.with_recurring_messages(
+                recurring_messages
+                    .into_iter()
+                    .map(RecurringMessage::try_from)
+                    .collect::<Result<Vec<_>>>()?,
+            )?,
+            None => config,
+        };
+
+        let config = match value.recurring_messages_path {
+            Some(recurring_messages_path) => {
+                config.with_recurring_messages_path(recurring_messages_path)
+            }
+            None => config,
+        };
+
+        let config = match value.quiet_hours {
+            Some(quiet_hours) => {
+                let quiet_hours = quiet_hours.try_into_quiet_hours(config.time_zone().clone())?;
+                config.with_quiet_hours(quiet_hours)
+            }
+            None => config,
+        };
+
+        let config = match value.clock {
+            Some(clock) => {
+                let clock_mode =
+                    clock.try_into_clock_mode(config.timing(), config.time_zone().clone())?;
+                config.with_clock_mode(clock_mode)
+            }
+            None => config,
+        };
+
+        let Some(injection) = value.injection else {
+            return Ok(config);
+        };
+        let config = match injection.strategy.as_deref() {
+            None | Some("weighted") => config.with_injection_strategy(InjectionStrategy::Weighted),
+            Some("round_robin") => config.with_injection_strategy(InjectionStrategy::RoundRobin),
+            Some("shuffle_bag") => config.with_injection_strategy(InjectionStrategy::ShuffleBag),
+            Some(other) => return Err(anyhow!("invalid injection strategy: {}", other).into()),
+        };
+        let config = match injection.seed {
+            Some(seed) => config.with_injection_seed(seed),
+            None => config,
+        };
+        Ok(match injection.state_path {
+            Some(state_path) => config.with_injection_state_path(state_path),
+            None => config,
+        })
     }
 }
 
@@ -79,20 +389,228 @@ struct TomlTimingConfig {
     pause_between_characters_for: u64,
     pause_between_messages_for: u64,
     inject_message_if_no_next_message_after_pausing_between_messages_for: u64,
+    expire_messages_after: Option<u64>,
 }
 
 impl TryFrom<TomlTimingConfig> for TimingConfig {
     type Error = crate::errors::Error;
 
     fn try_from(value: TomlTimingConfig) -> std::result::Result<Self, Self::Error> {
-        Ok(TimingConfig::new(
+        let timing = TimingConfig::new(
             Duration::new_from_seconds(value.show_character_for),
             Duration::new_from_seconds(value.pause_between_characters_for),
             Duration::new_from_seconds(value.pause_between_messages_for),
             Duration::new_from_seconds(
                 value.inject_message_if_no_next_message_after_pausing_between_messages_for,
             ),
-        ))
+        );
+        Ok(match value.expire_messages_after {
+            Some(seconds) => timing.with_message_expiry(Duration::new_from_seconds(seconds)),
+            None => timing,
+        })
+    }
+}
+
+/// Returns a configuration file fragment which defines the encoding.
+pub fn encoding_to_toml(encoding: &Encoding) -> Result<String> {
+    let table = TomlEncodingTable {
+        encoding: encoding.into(),
+    };
+    toml::to_string(&table).map_err(|err| anyhow!(err).into())
+}
+
+#[derive(Serialize)]
+struct TomlEncodingTable {
+    encoding: TomlEncodingConfig,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TomlEncodingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum_distance: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_end: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cancel: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    planes: Option<Vec<TomlEncodingPlaneConfig>>,
+}
+
+impl From<&Encoding> for TomlEncodingConfig {
+    fn from(value: &Encoding) -> Self {
+        Self {
+            name: Some(value.name().to_string()),
+            preset: None,
+            checksum: value.checksum().then_some(true),
+            minimum_distance: analysis::minimum_distance(value)
+                .map(|(distance, _, _)| distance)
+                .filter(|distance| *distance > 1),
+            message_end: Some(toml_open_shutters(value.message_end())),
+            cancel: value.cancel().map(toml_open_shutters),
+            planes: Some(value.planes().iter().map(|v| v.into()).collect()),
+        }
+    }
+}
+
+impl TryFrom<TomlEncodingConfig> for Encoding {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: TomlEncodingConfig) -> std::result::Result<Self, Self::Error> {
+        let encoding = match (value.preset, value.message_end, value.planes) {
+            (Some(preset), None, None) => EncodingPreset::try_from(preset.as_str())?.encoding(),
+            (None, Some(message_end), Some(planes)) => {
+                let planes = planes
+                    .into_iter()
+                    .map(EncodingPlane::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+
+                Encoding::new(planes, toml_shutter_positions(message_end)?)?
+            }
+            _ => {
+                return Err(anyhow!(
+                    "encoding must either specify a preset or the message end and planes"
+                )
+                .into());
+            }
+        };
+
+        let encoding = match value.cancel {
+            Some(cancel) => encoding.with_cancel(toml_shutter_positions(cancel)?)?,
+            None => encoding,
+        };
+
+        if let Some(minimum_distance) = value.minimum_distance {
+            encoding.check_minimum_distance(minimum_distance)?;
+        }
+
+        let encoding = match value.name {
+            Some(name) => encoding.with_name(name),
+            None => encoding,
+        };
+
+        if value.checksum.unwrap_or(false) {
+            return Ok(encoding.with_checksum());
+        }
+        Ok(encoding)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct TomlEncodingPlaneConfig {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shift: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shift_scope: Option<String>,
+    characters: BTreeMap<String, Vec<String>>,
+}
+
+impl From<&EncodingPlane> for TomlEncodingPlaneConfig {
+    fn from(value: &EncodingPlane) -> Self {
+        Self {
+            name: value.name().to_string(),
+            shift: value
+                .shift()
+                .map(|v| toml_open_shutters(v.shutter_positions())),
+            shift_scope: value.shift_scope().map(|v| v.into()),
+            characters: value
+                .characters()
+                .iter()
+                .map(|(character, positions)| (character.clone(), toml_open_shutters(positions)))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<TomlEncodingPlaneConfig> for EncodingPlane {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: TomlEncodingPlaneConfig) -> std::result::Result<Self, Self::Error> {
+        let shift = match (value.shift, value.shift_scope) {
+            (Some(shift), scope) => Some(Shift::new(
+                toml_shutter_positions(shift)?,
+                match scope {
+                    Some(scope) => scope.try_into()?,
+                    None => ShiftScope::Locking,
+                },
+            )?),
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(
+                    anyhow!("plane '{}' has a shift scope but no shift", value.name).into(),
+                );
+            }
+        };
+        let characters = value
+            .characters
+            .into_iter()
+            .map(|(character, open_shutters)| {
+                Ok((character, toml_shutter_positions(open_shutters)?))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        EncodingPlane::new(value.name, shift, characters)
+    }
+}
+
+fn toml_shutter_positions(open_shutters: Vec<String>) -> Result<ShutterPositions> {
+    let open_shutters = open_shutters
+        .into_iter()
+        .map(ShutterLocation::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    ShutterPositions::new(&open_shutters)
+}
+
+fn toml_open_shutters(positions: &ShutterPositions) -> Vec<String> {
+    ShutterLocation::iter()
+        .filter(|location| matches!(positions.get_position(location), ShutterPosition::Open))
+        .map(|location| location.into())
+        .collect()
+}
+
+impl TryFrom<String> for ShiftScope {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "locking" => Ok(ShiftScope::Locking),
+            "character" => Ok(ShiftScope::Character),
+            "run" => Ok(ShiftScope::Run),
+            other => Err(anyhow!("invalid shift scope: {}", other).into()),
+        }
+    }
+}
+
+impl From<&ShiftScope> for String {
+    fn from(value: &ShiftScope) -> Self {
+        match value {
+            ShiftScope::Locking => "locking",
+            ShiftScope::Character => "character",
+            ShiftScope::Run => "run",
+        }
+        .to_string()
+    }
+}
+
+impl TryFrom<String> for ShutterLocation {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "TOP_LEFT" => Ok(ShutterLocation::TopLeft),
+            "TOP_RIGHT" => Ok(ShutterLocation::TopRight),
+            "MIDDLE_LEFT" => Ok(ShutterLocation::MiddleLeft),
+            "MIDDLE_RIGHT" => Ok(ShutterLocation::MiddleRight),
+            "BOTTOM_LEFT" => Ok(ShutterLocation::BottomLeft),
+            "BOTTOM_RIGHT" => Ok(ShutterLocation::BottomRight),
+            other => Err(anyhow!("invalid shutter location: {}", other).into()),
+        }
     }
 }
 
@@ -168,6 +686,7 @@ impl app::Metrics for Metrics {
 pub struct PubSub {
     clacks_updated: broadcast::Sender<()>,
     message_added_to_queue: broadcast::Sender<()>,
+    queue_changed: broadcast::Sender<()>,
 }
 
 impl Default for PubSub {
@@ -180,10 +699,12 @@ impl PubSub {
     pub fn new() -> Self {
         let (clacks_updated, _) = broadcast::channel(1);
         let (message_added_to_queue, _) = broadcast::channel(1);
+        let (queue_changed, _) = broadcast::channel(1);
 
         Self {
             clacks_updated,
             message_added_to_queue,
+            queue_changed,
         }
     }
 }
@@ -204,6 +725,14 @@ impl app::EventPublisher for PubSub {
         }
         Ok(())
     }
+
+    fn publish_queue_changed(&self) -> Result<()> {
+        // if there are no receivers next line will return an error
+        if let Err(err) = self.queue_changed.send(()) {
+            debug!("publish queue changed failed: {:?}", err);
+        }
+        Ok(())
+    }
 }
 
 impl PubSub {
@@ -214,6 +743,10 @@ impl PubSub {
     pub fn subscribe_to_message_added_to_queue(&self) -> Receiver<()> {
         self.message_added_to_queue.subscribe()
     }
+
+    pub fn subscribe_to_queue_changed(&self) -> Receiver<()> {
+        self.queue_changed.subscribe()
+    }
 }
 
 pub struct MockServoController {}
@@ -252,17 +785,84 @@ mod tests {
             10,
             Environment::Development,
             vec![
-                Message::new("freeside")?,
-                Message::new("milliways")?,
-                Message::new("GNU TERRY PRATCHETT")?,
+                MessageToInject::new(MessageTemplate::new("freeside")?),
+                MessageToInject::new(MessageTemplate::new("milliways")?),
+                MessageToInject::new(MessageTemplate::new("GNU TERRY PRATCHETT")?),
+                MessageToInject::new(MessageTemplate::new("TIME {time}")?)
+                    .with_weight(2)?
+                    .with_min_interval(Duration::new_from_seconds(900)),
             ],
             TimingConfig::new(
                 Duration::new_from_seconds(1),
                 Duration::new_from_seconds(2),
                 Duration::new_from_seconds(3),
                 Duration::new_from_seconds(4),
+            )
+            .with_message_expiry(Duration::new_from_seconds(3600)),
+            Encoding::default(),
+            HashMap::from([("🙂".to_string(), "SMILE".to_string())]),
+        )?
+        .with_queue_path("/var/lib/clacks/queue.json")
+        .with_operators(Operators::new(HashMap::from([(
+            "alice".to_string(),
+            "secret-token".to_string(),
+        )]))?)
+        .with_park_position(ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+        ])?)
+        .with_time_zone(TimeZone::new_from_name("Europe/London")?)
+        .with_recurring_messages(vec![RecurringMessage::new(
+            "closing-time",
+            Schedule::new("0 18 * * 1-5")?,
+            Message::new("CLOSING TIME")?,
+            CatchUp::Once,
+        )?])?
+        .with_recurring_messages_path("/var/lib/clacks/recurring.json")
+        .with_quiet_hours(QuietHours::new(
+            TimeWindows::new(
+                TimeZone::new_from_name("Europe/London")?,
+                vec![
+                    TimeWindow::new(
+                        &[
+                            Weekday::Monday,
+                            Weekday::Tuesday,
+                            Weekday::Wednesday,
+                            Weekday::Thursday,
+                            Weekday::Sunday,
+                        ],
+                        TimeOfDay::new(22, 0)?,
+                        TimeOfDay::new(7, 30)?,
+                    )?,
+                    TimeWindow::new(
+                        &[Weekday::Friday, Weekday::Saturday],
+                        TimeOfDay::new(23, 30)?,
+                        TimeOfDay::new(9, 0)?,
+                    )?,
+                ],
             ),
-        )?;
+            QuietHoursSubmissions::Hold,
+        )?)
+        .with_injection_strategy(InjectionStrategy::ShuffleBag)
+        .with_injection_seed(42)
+        .with_injection_state_path("/var/lib/clacks/injection.json")
+        .with_clock_mode(
+            ClockMode::new(ClockSchedule::QuarterHour)?
+                .with_timing(TimingConfig::new(
+                    Duration::new_from_seconds(1),
+                    Duration::new_from_seconds(1),
+                    Duration::new_from_seconds(3),
+                    Duration::new_from_seconds(4),
+                ))
+                .with_windows(TimeWindows::new(
+                    TimeZone::new_from_name("Europe/London")?,
+                    vec![TimeWindow::new(
+                        &[Weekday::Saturday, Weekday::Sunday],
+                        TimeOfDay::new(10, 0)?,
+                        TimeOfDay::new(18, 0)?,
+                    )?],
+                )),
+        );
         let loader = ConfigLoader::new(fixtures::test_file_path(
             "src/adapters/testdata/config.toml",
         ));
@@ -270,4 +870,64 @@ mod tests {
         assert_eq!(expected_config, config);
         Ok(())
     }
+
+    #[test]
+    fn loads_config_with_custom_encoding_from_file_successfully() -> Result<()> {
+        let expected_encoding = Encoding::new(
+            vec![
+                EncodingPlane::new(
+                    "letters",
+                    Some(Shift::new(
+                        ShutterPositions::new(&[ShutterLocation::BottomRight])?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([
+                        (
+                            "A".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::TopLeft])?,
+                        ),
+                        (
+                            "B".to_string(),
+                            ShutterPositions::new(&[
+                                ShutterLocation::TopRight,
+                                ShutterLocation::BottomLeft,
+                            ])?,
+                        ),
+                        (
+                            " ".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::MiddleLeft])?,
+                        ),
+                    ]),
+                )?,
+                EncodingPlane::new(
+                    "figures",
+                    Some(Shift::new(
+                        ShutterPositions::new(&[
+                            ShutterLocation::MiddleRight,
+                            ShutterLocation::BottomRight,
+                        ])?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([
+                        (
+                            "1".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::TopLeft])?,
+                        ),
+                        (
+                            " ".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::MiddleLeft])?,
+                        ),
+                    ]),
+                )?,
+            ],
+            ShutterPositions::new_with_all_open(),
+        )?
+        .with_checksum();
+        let loader = ConfigLoader::new(fixtures::test_file_path(
+            "src/adapters/testdata/config_with_encoding.toml",
+        ));
+        let config = loader.load()?;
+        assert_eq!(&expected_encoding, config.encoding());
+        Ok(())
+    }
 }
@@ -0,0 +1,392 @@
+use crate::app;
+use crate::app::ImageFormat;
+use crate::domain::time::Duration;
+use crate::domain::{
+    EncodedMessage, EncodingSymbol, MessageComponent, ShutterLocation, ShutterPosition,
+    ShutterPositions, TimingConfig,
+};
+use crate::errors::{Error, Result};
+use std::borrow::Cow;
+
+const SHUTTER_SIZE: u32 = 40;
+const SHUTTER_GAP: u32 = 8;
+const PADDING: u32 = 12;
+const FRAME_WIDTH: u32 = 2 * PADDING + 2 * SHUTTER_SIZE + SHUTTER_GAP;
+const FRAME_HEIGHT: u32 = 2 * PADDING + 3 * SHUTTER_SIZE + 2 * SHUTTER_GAP;
+const FRAME_GAP: u32 = 8;
+const LABEL_HEIGHT: u32 = 40;
+const TITLE_HEIGHT: u32 = 40;
+const CHART_COLUMNS: usize = 8;
+
+const BACKGROUND: u8 = 0;
+const CLOSED: u8 = 1;
+const OPEN: u8 = 2;
+const PALETTE: [[u8; 3]; 3] = [[0x00, 0x00, 0x00], [0x33, 0x33, 0x33], [0xff, 0xff, 0x00]];
+
+/// Draws the tower as seen from the front. Raster images only contain the shutters, labels are
+/// only drawn in SVG images as that doesn't require bundling a font.
+#[derive(Clone, Default)]
+pub struct Renderer {}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl app::Renderer for Renderer {
+    fn render_frame(
+        &self,
+        shutter_positions: &ShutterPositions,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>> {
+        render_strip(&[(shutter_positions.clone(), None)], format)
+    }
+
+    fn render_filmstrip(&self, message: &EncodedMessage, format: ImageFormat) -> Result<Vec<u8>> {
+        let frames: Vec<(ShutterPositions, Option<String>)> = message
+            .parts()
+            .iter()
+            .map(|part| {
+                (
+                    part.shutter_positions().clone(),
+                    Some(label(part.element()).to_string()),
+                )
+            })
+            .collect();
+        render_strip(&frames, format)
+    }
+
+    fn render_animation(
+        &self,
+        message: &EncodedMessage,
+        timing: &TimingConfig,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>> {
+        let timeline = message.timeline(timing);
+        match format {
+            ImageFormat::Svg => Ok(animated_svg(&timeline).into_bytes()),
+            ImageFormat::Gif => {
+                let frames: Vec<(Canvas, &Duration)> = timeline
+                    .iter()
+                    .map(|(positions, duration)| {
+                        let mut canvas = Canvas::new(FRAME_WIDTH, FRAME_HEIGHT);
+                        canvas.draw_frame(0, 0, positions);
+                        (canvas, duration)
+                    })
+                    .collect();
+                encode_gif(&frames)
+            }
+            ImageFormat::Png => Err(Error::UnsupportedImageFormat(
+                "png can't be animated".to_string(),
+            )),
+        }
+    }
+
+    fn render_chart(
+        &self,
+        name: &str,
+        version: &str,
+        symbols: &[EncodingSymbol],
+        format: ImageFormat,
+    ) -> Result<Vec<u8>> {
+        if format != ImageFormat::Svg {
+            return Err(Error::UnsupportedImageFormat(format!(
+                "charts can't be rendered as {}",
+                format.name()
+            )));
+        }
+
+        let columns = symbols.len().clamp(1, CHART_COLUMNS) as u32;
+        let rows = symbols.len().div_ceil(CHART_COLUMNS) as u32;
+        let width = columns * FRAME_WIDTH + (columns - 1) * FRAME_GAP;
+        let height = TITLE_HEIGHT + rows * (FRAME_HEIGHT + LABEL_HEIGHT);
+
+        let mut body = svg_text(
+            width / 2,
+            TITLE_HEIGHT / 2,
+            20,
+            &format!("{} ({})", name, version),
+        );
+        for (i, symbol) in symbols.iter().enumerate() {
+            let x = (i as u32 % columns) * (FRAME_WIDTH + FRAME_GAP);
+            let y = TITLE_HEIGHT + (i as u32 / columns) * (FRAME_HEIGHT + LABEL_HEIGHT);
+            body.push_str(&svg_frame(x, y, symbol.shutter_positions()));
+            body.push_str(&svg_text(
+                x + FRAME_WIDTH / 2,
+                y + FRAME_HEIGHT + LABEL_HEIGHT / 3,
+                14,
+                label(symbol.element()),
+            ));
+            if let Some(plane) = symbol.plane() {
+                body.push_str(&svg_text(
+                    x + FRAME_WIDTH / 2,
+                    y + FRAME_HEIGHT + 2 * LABEL_HEIGHT / 3,
+                    10,
+                    plane,
+                ));
+            }
+        }
+        Ok(svg_document(width, height, &body).into_bytes())
+    }
+}
+
+fn render_strip(
+    frames: &[(ShutterPositions, Option<String>)],
+    format: ImageFormat,
+) -> Result<Vec<u8>> {
+    let count = frames.len() as u32;
+    let width = count * FRAME_WIDTH + count.saturating_sub(1) * FRAME_GAP;
+    let has_labels = frames.iter().any(|(_, label)| label.is_some());
+
+    match format {
+        ImageFormat::Svg => {
+            let mut body = String::new();
+            for (i, (positions, label)) in frames.iter().enumerate() {
+                let x = i as u32 * (FRAME_WIDTH + FRAME_GAP);
+                body.push_str(&svg_frame(x, 0, positions));
+                if let Some(label) = label {
+                    body.push_str(&svg_text(
+                        x + FRAME_WIDTH / 2,
+                        FRAME_HEIGHT + LABEL_HEIGHT / 2,
+                        14,
+                        label,
+                    ));
+                }
+            }
+            let height = match has_labels {
+                true => FRAME_HEIGHT + LABEL_HEIGHT,
+                false => FRAME_HEIGHT,
+            };
+            Ok(svg_document(width, height, &body).into_bytes())
+        }
+        ImageFormat::Png | ImageFormat::Gif => {
+            let mut canvas = Canvas::new(width, FRAME_HEIGHT);
+            for (i, (positions, _)) in frames.iter().enumerate() {
+                canvas.draw_frame(i as u32 * (FRAME_WIDTH + FRAME_GAP), 0, positions);
+            }
+            match format {
+                ImageFormat::Png => encode_png(&canvas),
+                _ => encode_gif(&[(canvas, &Duration::new_from_seconds(0))]),
+            }
+        }
+    }
+}
+
+fn label(element: &MessageComponent) -> &str {
+    match element {
+        MessageComponent::Character(character) if character == " " => "SPACE",
+        MessageComponent::Character(character) => character,
+        MessageComponent::Shift => "SHIFT",
+        MessageComponent::Checksum => "CHECKSUM",
+        MessageComponent::End => "END",
+        MessageComponent::Cancel => "CANCEL",
+    }
+}
+
+/// Returns the position of the top left corner of the shutter relative to the frame.
+fn shutter_origin(location: &ShutterLocation) -> (u32, u32) {
+    let (column, row) = match location {
+        ShutterLocation::TopLeft => (0, 0),
+        ShutterLocation::TopRight => (1, 0),
+        ShutterLocation::MiddleLeft => (0, 1),
+        ShutterLocation::MiddleRight => (1, 1),
+        ShutterLocation::BottomLeft => (0, 2),
+        ShutterLocation::BottomRight => (1, 2),
+    };
+    (
+        PADDING + column * (SHUTTER_SIZE + SHUTTER_GAP),
+        PADDING + row * (SHUTTER_SIZE + SHUTTER_GAP),
+    )
+}
+
+fn color(positions: &ShutterPositions, location: &ShutterLocation) -> u8 {
+    match positions.get_position(location) {
+        ShutterPosition::Open => OPEN,
+        ShutterPosition::Closed => CLOSED,
+    }
+}
+
+fn svg_color(color: u8) -> String {
+    let [r, g, b] = PALETTE[color as usize];
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn svg_document(width: u32, height: u32, body: &str) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="{width}" height="{height}" fill="{}"/>{body}</svg>"#,
+        svg_color(BACKGROUND)
+    )
+}
+
+fn svg_frame(x: u32, y: u32, positions: &ShutterPositions) -> String {
+    ShutterLocation::iter()
+        .map(|location| {
+            let (shutter_x, shutter_y) = shutter_origin(location);
+            format!(
+                r#"<rect x="{}" y="{}" width="{SHUTTER_SIZE}" height="{SHUTTER_SIZE}" fill="{}"/>"#,
+                x + shutter_x,
+                y + shutter_y,
+                svg_color(color(positions, location))
+            )
+        })
+        .collect()
+}
+
+fn svg_text(x: u32, y: u32, size: u32, text: &str) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+    format!(
+        r#"<text x="{x}" y="{y}" fill="{}" font-family="sans-serif" font-size="{size}" text-anchor="middle" dominant-baseline="middle">{text}</text>"#,
+        svg_color(OPEN)
+    )
+}
+
+fn animated_svg(timeline: &[(ShutterPositions, Duration)]) -> String {
+    let total: f64 = timeline
+        .iter()
+        .map(|(_, duration)| duration.as_seconds())
+        .sum();
+
+    let mut key_times = vec![];
+    let mut elapsed = 0.0;
+    for (_, duration) in timeline {
+        key_times.push(match total > 0.0 {
+            true => format!("{:.4}", elapsed / total),
+            false => "0".to_string(),
+        });
+        elapsed += duration.as_seconds();
+    }
+    let key_times = key_times.join(";");
+
+    let body: String = ShutterLocation::iter()
+        .map(|location| {
+            let (x, y) = shutter_origin(location);
+            let values: Vec<String> = timeline
+                .iter()
+                .map(|(positions, _)| svg_color(color(positions, location)))
+                .collect();
+            format!(
+                r#"<rect x="{x}" y="{y}" width="{SHUTTER_SIZE}" height="{SHUTTER_SIZE}" fill="{}"><animate attributeName="fill" dur="{total}s" repeatCount="indefinite" calcMode="discrete" keyTimes="{key_times}" values="{}"/></rect>"#,
+                values[0],
+                values.join(";")
+            )
+        })
+        .collect();
+    svg_document(FRAME_WIDTH, FRAME_HEIGHT, &body)
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![BACKGROUND; (width * height) as usize],
+        }
+    }
+
+    fn draw_frame(&mut self, x: u32, y: u32, positions: &ShutterPositions) {
+        for location in ShutterLocation::iter() {
+            let (shutter_x, shutter_y) = shutter_origin(location);
+            self.fill_rect(
+                x + shutter_x,
+                y + shutter_y,
+                SHUTTER_SIZE,
+                SHUTTER_SIZE,
+                color(positions, location),
+            );
+        }
+    }
+
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: u8) {
+        for row in y..y + height {
+            let start = (row * self.width + x) as usize;
+            self.pixels[start..start + width as usize].fill(color);
+        }
+    }
+}
+
+fn encode_png(canvas: &Canvas) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    let mut encoder = png::Encoder::new(&mut data, canvas.width, canvas.height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(PALETTE.concat());
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&canvas.pixels)?;
+    writer.finish()?;
+    Ok(data)
+}
+
+fn encode_gif(frames: &[(Canvas, &Duration)]) -> Result<Vec<u8>> {
+    let (width, height) = match frames.first() {
+        Some((canvas, _)) => (canvas.width as u16, canvas.height as u16),
+        None => (0, 0),
+    };
+
+    let mut data = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut data, width, height, &PALETTE.concat())?;
+        if frames.len() > 1 {
+            encoder.set_repeat(gif::Repeat::Infinite)?;
+        }
+        for (canvas, duration) in frames {
+            let frame = gif::Frame {
+                width,
+                height,
+                buffer: Cow::Borrowed(&canvas.pixels),
+                // delays are in hundredths of a second
+                delay: (duration.as_seconds() * 100.0).round() as u16,
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Renderer as _;
+    use crate::domain::{Encoding, Message};
+
+    #[test]
+    fn renders_messages_in_all_formats() -> Result<()> {
+        let renderer = Renderer::new();
+        let message = Encoding::default().encode(&Message::new("ab")?)?;
+        let timing = TimingConfig::new(
+            Duration::new_from_seconds(1),
+            Duration::new_from_seconds(1),
+            Duration::new_from_seconds(2),
+            Duration::new_from_seconds(3),
+        );
+
+        let svg = String::from_utf8(renderer.render_filmstrip(&message, ImageFormat::Svg)?)
+            .map_err(anyhow::Error::from)?;
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">END</text>"));
+
+        let png = renderer.render_filmstrip(&message, ImageFormat::Png)?;
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let gif = renderer.render_animation(&message, &timing, ImageFormat::Gif)?;
+        assert!(gif.starts_with(b"GIF89a"));
+
+        assert!(
+            renderer
+                .render_animation(&message, &timing, ImageFormat::Png)
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+use crate::domain::injection::{InjectionSnapshot, InjectionStorage};
+use crate::domain::recurring::{
+    CatchUp, RecurringMessage, RecurringMessagesSnapshot, RecurringMessagesStorage, Schedule,
+};
+use crate::domain::time::DateTime;
+use crate::domain::{Encoding, Message, MessageId, Priority, QueueStorage, QueuedMessage};
+use crate::errors::Result;
+use anyhow::anyhow;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const DATE_TIME_FORMAT: &str = "%+";
+
+/// Stores the queue as a JSON snapshot. Messages are stored as text and encoded again when they
+/// are loaded so that they follow changes to the encoding.
+pub struct FileQueueStorage {
+    path: PathBuf,
+    encoding: Encoding,
+}
+
+impl FileQueueStorage {
+    pub fn new<P: Into<PathBuf>>(path: P, encoding: Encoding) -> Self {
+        Self {
+            path: path.into(),
+            encoding,
+        }
+    }
+}
+
+impl QueueStorage for FileQueueStorage {
+    fn load(&self) -> Result<Vec<QueuedMessage>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+        let snapshot: StoredQueue = serde_json::from_str(&content).map_err(|err| anyhow!(err))?;
+        let mut messages = vec![];
+        for message in snapshot.messages {
+            match message.into_queued_message(&self.encoding) {
+                Ok(message) => messages.push(message),
+                Err(err) => warn!("dropping stored message which can't be restored: {}", err),
+            }
+        }
+        Ok(messages)
+    }
+
+    fn save(&self, messages: &[QueuedMessage]) -> Result<()> {
+        let snapshot = StoredQueue {
+            messages: messages.iter().map(|v| v.into()).collect(),
+        };
+        let content = serde_json::to_vec(&snapshot).map_err(|err| anyhow!(err))?;
+        write_atomically(&self.path, &content)
+    }
+}
+
+/// Stores recurring messages added using the API and the time of their last runs as a JSON
+/// snapshot.
+pub struct FileRecurringMessagesStorage {
+    path: PathBuf,
+}
+
+impl FileRecurringMessagesStorage {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl RecurringMessagesStorage for FileRecurringMessagesStorage {
+    fn load(&self) -> Result<RecurringMessagesSnapshot> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Ok(RecurringMessagesSnapshot::default());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let snapshot: StoredRecurringMessages =
+            serde_json::from_str(&content).map_err(|err| anyhow!(err))?;
+        snapshot.try_into()
+    }
+
+    fn save(&self, snapshot: &RecurringMessagesSnapshot) -> Result<()> {
+        let snapshot: StoredRecurringMessages = snapshot.into();
+        let content = serde_json::to_vec(&snapshot).map_err(|err| anyhow!(err))?;
+        write_atomically(&self.path, &content)
+    }
+}
+
+/// Stores the position of the rotation of injected messages as a JSON snapshot.
+pub struct FileInjectionStorage {
+    path: PathBuf,
+}
+
+impl FileInjectionStorage {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl InjectionStorage for FileInjectionStorage {
+    fn load(&self) -> Result<InjectionSnapshot> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Ok(InjectionSnapshot::default());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let snapshot: StoredInjectionSnapshot =
+            serde_json::from_str(&content).map_err(|err| anyhow!(err))?;
+        snapshot.try_into()
+    }
+
+    fn save(&self, snapshot: &InjectionSnapshot) -> Result<()> {
+        let snapshot: StoredInjectionSnapshot = snapshot.into();
+        let content = serde_json::to_vec(&snapshot).map_err(|err| anyhow!(err))?;
+        write_atomically(&self.path, &content)
+    }
+}
+
+/// Writes to a temporary file which then replaces the previous file so that a crash never
+/// leaves a partially written file behind.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let mut temporary_path = path.to_path_buf().into_os_string();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let mut file = fs::File::create(&temporary_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredRecurringMessages {
+    added: Vec<StoredRecurringMessage>,
+    handled_until: HashMap<String, String>,
+}
+
+impl From<&RecurringMessagesSnapshot> for StoredRecurringMessages {
+    fn from(value: &RecurringMessagesSnapshot) -> Self {
+        Self {
+            added: value.added().iter().map(|v| v.into()).collect(),
+            handled_until: value
+                .handled_until()
+                .iter()
+                .map(|(name, v)| (name.clone(), v.format(DATE_TIME_FORMAT)))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredRecurringMessages> for RecurringMessagesSnapshot {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: StoredRecurringMessages) -> std::result::Result<Self, Self::Error> {
+        let added = value
+            .added
+            .into_iter()
+            .map(RecurringMessage::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let handled_until = value
+            .handled_until
+            .into_iter()
+            .map(|(name, v)| Ok((name, DateTime::new_from_str(&v, DATE_TIME_FORMAT)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(RecurringMessagesSnapshot::new(added, handled_until))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredRecurringMessage {
+    name: String,
+    schedule: String,
+    message: String,
+    catch_up: String,
+}
+
+impl From<&RecurringMessage> for StoredRecurringMessage {
+    fn from(value: &RecurringMessage) -> Self {
+        Self {
+            name: value.name().to_string(),
+            schedule: value.schedule().expression().to_string(),
+            message: value.message().text().to_string(),
+            catch_up: value.catch_up().into(),
+        }
+    }
+}
+
+impl TryFrom<StoredRecurringMessage> for RecurringMessage {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: StoredRecurringMessage) -> std::result::Result<Self, Self::Error> {
+        RecurringMessage::new(
+            value.name,
+            Schedule::new(value.schedule)?,
+            Message::new(value.message)?,
+            value.catch_up.try_into()?,
+        )
+    }
+}
+
+impl From<CatchUp> for String {
+    fn from(value: CatchUp) -> Self {
+        match value {
+            CatchUp::Skip => "SKIP",
+            CatchUp::Once => "ONCE",
+            CatchUp::All => "ALL",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<String> for CatchUp {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "SKIP" => Ok(CatchUp::Skip),
+            "ONCE" => Ok(CatchUp::Once),
+            "ALL" => Ok(CatchUp::All),
+            other => Err(anyhow!("invalid catch up policy: {}", other).into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredQueue {
+    messages: Vec<StoredQueuedMessage>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredQueuedMessage {
+    id: String,
+    priority: String,
+    submitted_at: String,
+    #[serde(default)]
+    not_before: Option<String>,
+    text: String,
+}
+
+impl From<&QueuedMessage> for StoredQueuedMessage {
+    fn from(value: &QueuedMessage) -> Self {
+        Self {
+            id: value.id().to_string(),
+            priority: value.priority().into(),
+            submitted_at: value.submitted_at().format(DATE_TIME_FORMAT),
+            not_before: value.not_before().map(|v| v.format(DATE_TIME_FORMAT)),
+            text: value.message().text(),
+        }
+    }
+}
+
+impl StoredQueuedMessage {
+    fn into_queued_message(self, encoding: &Encoding) -> Result<QueuedMessage> {
+        let message = QueuedMessage::new(
+            MessageId::new(self.id)?,
+            encoding.encode(&Message::new(self.text)?)?,
+            self.priority.try_into()?,
+            DateTime::new_from_str(&self.submitted_at, DATE_TIME_FORMAT)?,
+        );
+        Ok(match self.not_before {
+            Some(not_before) => {
+                message.with_not_before(DateTime::new_from_str(&not_before, DATE_TIME_FORMAT)?)
+            }
+            None => message,
+        })
+    }
+}
+
+impl TryFrom<String> for Priority {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "OPERATOR" => Ok(Priority::Operator),
+            "SCHEDULED" => Ok(Priority::Scheduled),
+            "PUBLIC" => Ok(Priority::Public),
+            "INJECTED" => Ok(Priority::Injected),
+            other => Err(anyhow!("invalid priority: {}", other).into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredInjectionSnapshot {
+    last: Option<String>,
+    bag: Vec<String>,
+    draws: u64,
+    last_injected_at: HashMap<String, String>,
+}
+
+impl From<&InjectionSnapshot> for StoredInjectionSnapshot {
+    fn from(value: &InjectionSnapshot) -> Self {
+        Self {
+            last: value.last().map(|v| v.to_string()),
+            bag: value.bag().to_vec(),
+            draws: value.draws(),
+            last_injected_at: value
+                .last_injected_at()
+                .iter()
+                .map(|(text, v)| (text.clone(), v.format(DATE_TIME_FORMAT)))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredInjectionSnapshot> for InjectionSnapshot {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: StoredInjectionSnapshot) -> std::result::Result<Self, Self::Error> {
+        let last_injected_at = value
+            .last_injected_at
+            .into_iter()
+            .map(|(text, v)| Ok((text, DateTime::new_from_str(&v, DATE_TIME_FORMAT)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(InjectionSnapshot::new(
+            value.last,
+            value.bag,
+            value.draws,
+            last_injected_at,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Encoding, Message, Queue};
+
+    #[test]
+    fn restores_saved_queue() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("clacks-queue-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let encoding = Encoding::default().with_checksum();
+        let queue = Queue::new_with_storage(10, FileQueueStorage::new(&path, encoding.clone()))?;
+        for text in ["first", "second"] {
+            queue.add_message(QueuedMessage::new(
+                MessageId::generate(),
+                encoding.encode(&Message::new(text)?)?,
+                Priority::Public,
+                DateTime::new_from_unix_timestamp(1000),
+            ))?;
+        }
+        let popped = queue.pop_message(&DateTime::now())?.unwrap();
+
+        // the encoding changed while the clacks was down
+        let new_encoding = Encoding::default();
+        let restore = || -> Result<Vec<QueuedMessage>> {
+            Queue::new_with_storage(10, FileQueueStorage::new(&path, new_encoding.clone()))?
+                .get_messages(&DateTime::now())
+        };
+
+        let messages = restore()?;
+        assert_eq!(2, messages.len());
+        assert_eq!(popped.id(), messages[0].id());
+        assert_eq!(Priority::Public, messages[1].priority());
+        assert_eq!(
+            &DateTime::new_from_unix_timestamp(1000),
+            messages[1].submitted_at()
+        );
+        assert_eq!(
+            new_encoding.encode(&Message::new("second")?)?.parts(),
+            messages[1].message().parts()
+        );
+
+        queue.finish_message(popped.id())?;
+        let messages = restore()?;
+        fs::remove_file(&path)?;
+        assert_eq!(1, messages.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::app;
+use crate::app::{AbortTransmission, Clacks, Encoding, Metrics};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct AbortTransmissionHandler<C, E, M> {
+    clacks: C,
+    encoding: E,
+    metrics: M,
+}
+
+impl<C, E, M> AbortTransmissionHandler<C, E, M> {
+    pub fn new(clacks: C, encoding: E, metrics: M) -> Self {
+        Self {
+            clacks,
+            encoding,
+            metrics,
+        }
+    }
+}
+
+impl<C, E, M> app::AbortTransmissionHandler for AbortTransmissionHandler<C, E, M>
+where
+    C: Clacks,
+    E: Encoding,
+    M: Metrics,
+{
+    #[application_handler]
+    fn handle(&self, abort_transmission: AbortTransmission) -> Result<()> {
+        let id = self.clacks.abort(self.encoding.encode_cancel())?;
+        match id {
+            Some(id) => info!(
+                "operator `{}` aborted message `{}`",
+                abort_transmission.operator, id
+            ),
+            None => info!(
+                "operator `{}` aborted an injected message",
+                abort_transmission.operator
+            ),
+        }
+        Ok::<(), Error>(())
+    }
+}
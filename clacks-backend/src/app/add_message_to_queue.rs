@@ -1,39 +1,105 @@
 use crate::app;
-use crate::app::{AddMessageToQueue, Encoding, EventPublisher, Metrics, Queue};
+use crate::app::{
+    AddMessageToQueue, AddMessageToQueueResult, Clacks, Encoding, EventPublisher, Metrics, Queue,
+    Transliterator,
+};
+use crate::domain::quiet_hours::QuietHoursSubmissions;
+use crate::domain::time::Clock;
+use crate::domain::{MessageId, Priority, QueuedMessage};
 use crate::errors::{Error, Result};
 use clacks_macros::application_handler;
 
 #[derive(Clone)]
-pub struct AddMessageToQueueHandler<Q, M, E, P> {
+pub struct AddMessageToQueueHandler<Q, M, E, T, P, C, K> {
     queue: Q,
     metrics: M,
     encoding: E,
+    transliterator: T,
     publisher: P,
+    clacks: C,
+    clock: K,
 }
 
-impl<Q, M, E, P> AddMessageToQueueHandler<Q, M, E, P> {
-    pub fn new(queue: Q, metrics: M, encoding: E, publisher: P) -> Self {
+impl<Q, M, E, T, P, C, K> AddMessageToQueueHandler<Q, M, E, T, P, C, K> {
+    pub fn new(
+        queue: Q,
+        metrics: M,
+        encoding: E,
+        transliterator: T,
+        publisher: P,
+        clacks: C,
+        clock: K,
+    ) -> Self {
         Self {
             queue,
             metrics,
             encoding,
+            transliterator,
             publisher,
+            clacks,
+            clock,
         }
     }
 }
 
-impl<Q, M, E, P> app::AddMessageToQueueHandler for AddMessageToQueueHandler<Q, M, E, P>
+impl<Q, M, E, T, P, C, K> app::AddMessageToQueueHandler
+    for AddMessageToQueueHandler<Q, M, E, T, P, C, K>
 where
     Q: Queue,
     M: Metrics,
     E: Encoding,
+    T: Transliterator,
     P: EventPublisher,
+    C: Clacks,
+    K: Clock,
 {
     #[application_handler]
-    fn handle(&self, add_message_to_queue: AddMessageToQueue) -> Result<()> {
-        let encoded_message = self.encoding.encode(&add_message_to_queue.message)?;
-        self.queue.add_message(encoded_message)?;
+    fn handle(&self, add_message_to_queue: AddMessageToQueue) -> Result<AddMessageToQueueResult> {
+        if let Some(pause) = self.clacks.current_pause()
+            && !pause.accept_messages()
+        {
+            return Err(Error::ClacksIsPaused);
+        }
+
+        // operators and scheduled messages are simply held until the quiet hours end
+        let mut held_until = None;
+        if add_message_to_queue.not_before.is_none()
+            && add_message_to_queue.priority == Priority::Public
+            && let Some(quiet_period) = self.clacks.current_quiet_period()
+        {
+            match quiet_period.submissions() {
+                QuietHoursSubmissions::Reject => {
+                    return Err(Error::ClacksIsQuiet(quiet_period.until().clone()));
+                }
+                QuietHoursSubmissions::Hold => held_until = Some(quiet_period.until().clone()),
+            }
+        }
+
+        let transliteration = self
+            .transliterator
+            .transliterate(&add_message_to_queue.message)?;
+        let encoded_message = self.encoding.encode(transliteration.message())?;
+        let id = MessageId::generate();
+        let message = match add_message_to_queue.not_before {
+            Some(not_before) => QueuedMessage::new(
+                id.clone(),
+                encoded_message,
+                Priority::Scheduled,
+                self.clock.now(),
+            )
+            .with_not_before(not_before),
+            None => QueuedMessage::new(
+                id.clone(),
+                encoded_message,
+                add_message_to_queue.priority,
+                self.clock.now(),
+            ),
+        };
+        self.queue.add_message(message)?;
         self.publisher.publish_message_added_to_queue()?;
-        Ok::<(), Error>(())
+        Ok::<AddMessageToQueueResult, Error>(
+            AddMessageToQueueResult::new(id, transliteration.substitutions().to_vec())
+                .with_held_until(held_until),
+        )
     }
 }
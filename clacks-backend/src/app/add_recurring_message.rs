@@ -0,0 +1,60 @@
+use crate::app;
+use crate::app::{AddRecurringMessage, Encoding, Metrics, RecurringMessages, Transliterator};
+use crate::domain::time::Clock;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct AddRecurringMessageHandler<R, E, T, M, K> {
+    recurring_messages: R,
+    encoding: E,
+    transliterator: T,
+    metrics: M,
+    clock: K,
+}
+
+impl<R, E, T, M, K> AddRecurringMessageHandler<R, E, T, M, K> {
+    pub fn new(
+        recurring_messages: R,
+        encoding: E,
+        transliterator: T,
+        metrics: M,
+        clock: K,
+    ) -> Self {
+        Self {
+            recurring_messages,
+            encoding,
+            transliterator,
+            metrics,
+            clock,
+        }
+    }
+}
+
+impl<R, E, T, M, K> app::AddRecurringMessageHandler for AddRecurringMessageHandler<R, E, T, M, K>
+where
+    R: RecurringMessages,
+    E: Encoding,
+    T: Transliterator,
+    M: Metrics,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, add_recurring_message: AddRecurringMessage) -> Result<()> {
+        // rejects messages which can't be encoded now rather than every time they are due
+        let transliteration = self
+            .transliterator
+            .transliterate(add_recurring_message.message.message().text())?;
+        self.encoding.encode(transliteration.message())?;
+
+        let name = add_recurring_message.message.name().to_string();
+        self.recurring_messages
+            .add(add_recurring_message.message, &self.clock.now())?;
+        info!(
+            "operator `{}` added recurring message `{}`",
+            add_recurring_message.operator, name
+        );
+        Ok::<(), Error>(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::app;
+use crate::app::{CancelScheduledMessage, EventPublisher, MessageLog, Metrics, Queue};
+use crate::domain::time::Clock;
+use crate::domain::{MessageRecord, MessageStatus};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct CancelScheduledMessageHandler<Q, L, M, P, K> {
+    queue: Q,
+    message_log: L,
+    metrics: M,
+    publisher: P,
+    clock: K,
+}
+
+impl<Q, L, M, P, K> CancelScheduledMessageHandler<Q, L, M, P, K> {
+    pub fn new(queue: Q, message_log: L, metrics: M, publisher: P, clock: K) -> Self {
+        Self {
+            queue,
+            message_log,
+            metrics,
+            publisher,
+            clock,
+        }
+    }
+}
+
+impl<Q, L, M, P, K> app::CancelScheduledMessageHandler
+    for CancelScheduledMessageHandler<Q, L, M, P, K>
+where
+    Q: Queue,
+    L: MessageLog,
+    M: Metrics,
+    P: EventPublisher,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, cancel_scheduled_message: CancelScheduledMessage) -> Result<()> {
+        // only scheduled messages can be cancelled this way, other messages have to be removed
+        // from the queue
+        let is_scheduled = self
+            .queue
+            .get_scheduled_messages()?
+            .iter()
+            .any(|v| v.id() == &cancel_scheduled_message.id);
+        if !is_scheduled {
+            return Err(Error::MessageNotFound(
+                cancel_scheduled_message.id.to_string(),
+            ));
+        }
+
+        let message = self.queue.remove_message(&cancel_scheduled_message.id)?;
+        info!(
+            "operator `{}` cancelled scheduled message `{}`",
+            cancel_scheduled_message.operator,
+            message.id()
+        );
+        self.message_log.record(
+            MessageRecord::new(&message, MessageStatus::Rejected)
+                .with_finished_at(self.clock.now()),
+        );
+        self.publisher.publish_queue_changed()?;
+        Ok::<(), Error>(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::app;
+use crate::app::{ClearQueue, EventPublisher, MessageLog, Metrics, Queue};
+use crate::domain::time::Clock;
+use crate::domain::{MessageRecord, MessageStatus};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct ClearQueueHandler<Q, L, M, P, K> {
+    queue: Q,
+    message_log: L,
+    metrics: M,
+    publisher: P,
+    clock: K,
+}
+
+impl<Q, L, M, P, K> ClearQueueHandler<Q, L, M, P, K> {
+    pub fn new(queue: Q, message_log: L, metrics: M, publisher: P, clock: K) -> Self {
+        Self {
+            queue,
+            message_log,
+            metrics,
+            publisher,
+            clock,
+        }
+    }
+}
+
+impl<Q, L, M, P, K> app::ClearQueueHandler for ClearQueueHandler<Q, L, M, P, K>
+where
+    Q: Queue,
+    L: MessageLog,
+    M: Metrics,
+    P: EventPublisher,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, clear_queue: ClearQueue) -> Result<()> {
+        let messages = self.queue.clear()?;
+        info!(
+            "operator `{}` cleared {} messages from the queue",
+            clear_queue.operator,
+            messages.len()
+        );
+        let now = self.clock.now();
+        for message in &messages {
+            self.message_log.record(
+                MessageRecord::new(message, MessageStatus::Rejected).with_finished_at(now.clone()),
+            );
+        }
+        self.publisher.publish_queue_changed()?;
+        Ok::<(), Error>(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::app;
+use crate::app::{Encoding, EventPublisher, Metrics, Queue, RecurringMessages, Transliterator};
+use crate::domain::time::Clock;
+use crate::domain::{MessageId, Priority, QueuedMessage};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::{error, info};
+
+#[derive(Clone)]
+pub struct EnqueueRecurringMessagesHandler<R, Q, E, T, M, P, K> {
+    recurring_messages: R,
+    queue: Q,
+    encoding: E,
+    transliterator: T,
+    metrics: M,
+    publisher: P,
+    clock: K,
+}
+
+impl<R, Q, E, T, M, P, K> EnqueueRecurringMessagesHandler<R, Q, E, T, M, P, K> {
+    pub fn new(
+        recurring_messages: R,
+        queue: Q,
+        encoding: E,
+        transliterator: T,
+        metrics: M,
+        publisher: P,
+        clock: K,
+    ) -> Self {
+        Self {
+            recurring_messages,
+            queue,
+            encoding,
+            transliterator,
+            metrics,
+            publisher,
+            clock,
+        }
+    }
+}
+
+impl<R, Q, E, T, M, P, K> app::EnqueueRecurringMessagesHandler
+    for EnqueueRecurringMessagesHandler<R, Q, E, T, M, P, K>
+where
+    R: RecurringMessages,
+    Q: Queue,
+    E: Encoding,
+    T: Transliterator,
+    M: Metrics,
+    P: EventPublisher,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self) -> Result<()> {
+        let now = self.clock.now();
+        let runs = self.recurring_messages.take_due(&now)?;
+        if runs.is_empty() {
+            return Ok(());
+        }
+
+        for run in &runs {
+            // one broken message shouldn't prevent the others from being transmitted
+            let result = self
+                .transliterator
+                .transliterate(run.message().text())
+                .and_then(|v| self.encoding.encode(v.message()))
+                .and_then(|encoded_message| {
+                    let id = MessageId::generate();
+                    self.queue.add_message(
+                        QueuedMessage::new(
+                            id.clone(),
+                            encoded_message,
+                            Priority::Scheduled,
+                            now.clone(),
+                        )
+                        .with_not_before(now.clone()),
+                    )?;
+                    self.recurring_messages.mark_handled(run)?;
+                    Ok(id)
+                });
+            match result {
+                Ok(id) => info!(
+                    "queued recurring message `{}` scheduled for {} as `{}`",
+                    run.name(),
+                    run.scheduled_for(),
+                    id
+                ),
+                Err(err) => error!("error queueing recurring message `{}`: {}", run.name(), err),
+            }
+        }
+        self.publisher.publish_message_added_to_queue()?;
+        Ok::<(), Error>(())
+    }
+}
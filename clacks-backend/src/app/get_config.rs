@@ -25,6 +25,7 @@ where
     fn get_config(&self) -> Result<Config> {
         Ok::<Config, Error>(Config {
             supported_characters: self.encoding.supported_characters(),
+            phrases: self.encoding.phrases(),
             max_message_len_in_bytes: MAX_MESSAGE_LEN_BYTES,
         })
     }
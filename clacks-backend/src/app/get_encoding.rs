@@ -0,0 +1,31 @@
+use crate::app;
+use crate::app::{Encoding, EncodingTable, Metrics};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+
+#[derive(Clone)]
+pub struct GetEncodingHandler<E, M> {
+    encoding: E,
+    metrics: M,
+}
+
+impl<E, M> GetEncodingHandler<E, M> {
+    pub fn new(encoding: E, metrics: M) -> Self {
+        Self { encoding, metrics }
+    }
+}
+
+impl<E, M> app::GetEncodingHandler for GetEncodingHandler<E, M>
+where
+    E: Encoding,
+    M: Metrics,
+{
+    #[application_handler]
+    fn get_encoding(&self) -> Result<EncodingTable> {
+        Ok::<EncodingTable, Error>(EncodingTable {
+            name: self.encoding.name(),
+            version: self.encoding.version(),
+            symbols: self.encoding.symbols(),
+        })
+    }
+}
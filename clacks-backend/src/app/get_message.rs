@@ -0,0 +1,47 @@
+use crate::app;
+use crate::app::{MessageLog, MessageState, Metrics, Queue};
+use crate::domain::time::Clock;
+use crate::domain::{MessageId, MessageRecord, MessageStatus};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+
+#[derive(Clone)]
+pub struct GetMessageHandler<Q, L, M, K> {
+    queue: Q,
+    message_log: L,
+    metrics: M,
+    clock: K,
+}
+
+impl<Q, L, M, K> GetMessageHandler<Q, L, M, K> {
+    pub fn new(queue: Q, message_log: L, metrics: M, clock: K) -> Self {
+        Self {
+            queue,
+            message_log,
+            metrics,
+            clock,
+        }
+    }
+}
+
+impl<Q, L, M, K> app::GetMessageHandler for GetMessageHandler<Q, L, M, K>
+where
+    Q: Queue,
+    L: MessageLog,
+    M: Metrics,
+    K: Clock,
+{
+    #[application_handler]
+    fn get_message(&self, id: &MessageId) -> Result<MessageState> {
+        let queue = self.queue.get_messages(&self.clock.now())?;
+        if let Some(position) = queue.iter().position(|v| v.id() == id) {
+            let record = MessageRecord::new(&queue[position], MessageStatus::Queued);
+            return Ok(MessageState::new(record, Some(position)));
+        }
+
+        match self.message_log.get(id) {
+            Some(record) => Ok::<MessageState, Error>(MessageState::new(record, None)),
+            None => Err(Error::MessageNotFound(id.to_string())),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::app;
+use crate::app::{Metrics, RecurringMessages};
+use crate::domain::recurring::RecurringMessageEntry;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+
+#[derive(Clone)]
+pub struct GetRecurringMessagesHandler<R, M> {
+    recurring_messages: R,
+    metrics: M,
+}
+
+impl<R, M> GetRecurringMessagesHandler<R, M> {
+    pub fn new(recurring_messages: R, metrics: M) -> Self {
+        Self {
+            recurring_messages,
+            metrics,
+        }
+    }
+}
+
+impl<R, M> app::GetRecurringMessagesHandler for GetRecurringMessagesHandler<R, M>
+where
+    R: RecurringMessages,
+    M: Metrics,
+{
+    #[application_handler]
+    fn get_recurring_messages(&self) -> Result<Vec<RecurringMessageEntry>> {
+        Ok::<Vec<RecurringMessageEntry>, Error>(self.recurring_messages.list())
+    }
+}
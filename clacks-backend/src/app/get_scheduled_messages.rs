@@ -0,0 +1,29 @@
+use crate::app;
+use crate::app::{Metrics, Queue};
+use crate::domain::QueuedMessage;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+
+#[derive(Clone)]
+pub struct GetScheduledMessagesHandler<Q, M> {
+    queue: Q,
+    metrics: M,
+}
+
+impl<Q, M> GetScheduledMessagesHandler<Q, M> {
+    pub fn new(queue: Q, metrics: M) -> Self {
+        Self { queue, metrics }
+    }
+}
+
+impl<Q, M> app::GetScheduledMessagesHandler for GetScheduledMessagesHandler<Q, M>
+where
+    Q: Queue,
+    M: Metrics,
+{
+    #[application_handler]
+    fn get_scheduled_messages(&self) -> Result<Vec<QueuedMessage>> {
+        let messages = self.queue.get_scheduled_messages()?;
+        Ok::<Vec<QueuedMessage>, Error>(messages)
+    }
+}
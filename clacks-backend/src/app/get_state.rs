@@ -1,38 +1,46 @@
 use crate::app;
 use crate::app::{Clacks, Metrics, Queue, State};
+use crate::domain::time::Clock;
 use crate::errors::{Error, Result};
 use clacks_macros::application_handler;
 
 #[derive(Clone)]
-pub struct GetStateHandler<C, Q, M> {
+pub struct GetStateHandler<C, Q, M, K> {
     clacks: C,
     queue: Q,
     metrics: M,
+    clock: K,
 }
 
-impl<C, Q, M> GetStateHandler<C, Q, M> {
-    pub fn new(clacks: C, queue: Q, metrics: M) -> Self {
+impl<C, Q, M, K> GetStateHandler<C, Q, M, K> {
+    pub fn new(clacks: C, queue: Q, metrics: M, clock: K) -> Self {
         Self {
             clacks,
             queue,
             metrics,
+            clock,
         }
     }
 }
 
-impl<C, Q, M> app::GetStateHandler for GetStateHandler<C, Q, M>
+impl<C, Q, M, K> app::GetStateHandler for GetStateHandler<C, Q, M, K>
 where
     C: Clacks,
     Q: Queue,
     M: Metrics,
+    K: Clock,
 {
     #[application_handler]
     fn get_state(&self) -> Result<State> {
         let current_message = self.clacks.current_message();
-        let queue = self.queue.get_messages()?;
+        let queue = self.queue.get_messages(&self.clock.now())?;
+        let pause = self.clacks.current_pause();
+        let quiet_period = self.clacks.current_quiet_period();
         Ok::<State, Error>(State {
             current_message,
             queue,
+            pause,
+            quiet_period,
         })
     }
 }
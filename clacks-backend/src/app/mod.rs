@@ -1,12 +1,36 @@
+pub mod abort_transmission;
 pub mod add_message_to_queue;
+pub mod add_recurring_message;
+pub mod cancel_scheduled_message;
+pub mod clear_queue;
+pub mod enqueue_recurring_messages;
 pub mod get_config;
+pub mod get_encoding;
+pub mod get_message;
+pub mod get_recurring_messages;
+pub mod get_scheduled_messages;
 pub mod get_state;
+pub mod move_message_in_queue;
+pub mod pause_clacks;
+pub mod preempt_transmission;
+pub mod remove_message_from_queue;
+pub mod remove_recurring_message;
+pub mod render;
+pub mod resume_clacks;
 pub mod update_clacks;
 
 use crate::domain;
+use crate::domain::quiet_hours::QuietPeriod;
+use crate::domain::recurring::{RecurringMessage, RecurringMessageEntry, RecurringRun};
 use crate::domain::servos::ServoController;
-use crate::domain::time::Duration;
-use crate::domain::{CurrentMessage, EncodedMessage, Message, ShutterPositions, servos};
+use crate::domain::time::{DateTime, Duration};
+use crate::domain::transliteration::{Substitution, Transliteration};
+use crate::domain::{
+    CurrentMessage, EncodedMessage, EncodedMessagePart, EncodingSymbol, Message, MessageId,
+    MessageRecord, MessageStatus, Pause, PreemptMode, Priority, QueuedMessage, ShutterPositions,
+    TimingConfig, servos,
+};
+use crate::errors::Error;
 use crate::errors::Result;
 
 pub trait UpdateClacksHandler {
@@ -14,45 +38,470 @@ pub trait UpdateClacksHandler {
 }
 
 pub struct AddMessageToQueue {
-    message: Message,
+    message: String,
+    priority: Priority,
+    not_before: Option<DateTime>,
 }
 
 impl AddMessageToQueue {
-    pub fn new(message: Message) -> Self {
-        Self { message }
+    /// The message is validated once it has been transliterated.
+    pub fn new(message: impl Into<String>, priority: Priority) -> Self {
+        Self {
+            message: message.into(),
+            priority,
+            not_before: None,
+        }
+    }
+
+    /// Schedules the message for the given time. Scheduled messages are transmitted ahead of
+    /// public messages once they are due.
+    pub fn with_not_before(self, not_before: DateTime) -> Self {
+        Self {
+            not_before: Some(not_before),
+            ..self
+        }
     }
 }
 
 pub trait AddMessageToQueueHandler {
-    fn handle(&self, add_message_to_queue: AddMessageToQueue) -> Result<()>;
+    fn handle(&self, add_message_to_queue: AddMessageToQueue) -> Result<AddMessageToQueueResult>;
+}
+
+pub trait EnqueueRecurringMessagesHandler {
+    fn handle(&self) -> Result<()>;
+}
+
+pub struct AddRecurringMessage {
+    message: RecurringMessage,
+    operator: String,
+}
+
+impl AddRecurringMessage {
+    pub fn new(message: RecurringMessage, operator: impl Into<String>) -> Self {
+        Self {
+            message,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait AddRecurringMessageHandler {
+    fn handle(&self, add_recurring_message: AddRecurringMessage) -> Result<()>;
+}
+
+pub struct RemoveRecurringMessage {
+    name: String,
+    operator: String,
+}
+
+impl RemoveRecurringMessage {
+    pub fn new(name: impl Into<String>, operator: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait RemoveRecurringMessageHandler {
+    fn handle(&self, remove_recurring_message: RemoveRecurringMessage) -> Result<()>;
+}
+
+pub trait GetRecurringMessagesHandler {
+    fn get_recurring_messages(&self) -> Result<Vec<RecurringMessageEntry>>;
+}
+
+pub struct AddMessageToQueueResult {
+    id: MessageId,
+    substitutions: Vec<Substitution>,
+    held_until: Option<DateTime>,
+}
+
+impl AddMessageToQueueResult {
+    pub fn new(id: MessageId, substitutions: Vec<Substitution>) -> Self {
+        Self {
+            id,
+            substitutions,
+            held_until: None,
+        }
+    }
+
+    /// Set if the message was submitted during quiet hours and is held until they end.
+    pub fn with_held_until(self, held_until: Option<DateTime>) -> Self {
+        Self { held_until, ..self }
+    }
+
+    pub fn held_until(&self) -> Option<&DateTime> {
+        self.held_until.as_ref()
+    }
+
+    pub fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    pub fn substitutions(&self) -> &[Substitution] {
+        &self.substitutions
+    }
+}
+
+/// Operator commands carry the name of the operator so that it's clear who changed the queue.
+pub struct RemoveMessageFromQueue {
+    id: MessageId,
+    operator: String,
+}
+
+impl RemoveMessageFromQueue {
+    pub fn new(id: MessageId, operator: impl Into<String>) -> Self {
+        Self {
+            id,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait RemoveMessageFromQueueHandler {
+    fn handle(&self, remove_message_from_queue: RemoveMessageFromQueue) -> Result<()>;
+}
+
+pub struct MoveMessageInQueue {
+    id: MessageId,
+    position: usize,
+    operator: String,
+}
+
+impl MoveMessageInQueue {
+    pub fn new(id: MessageId, position: usize, operator: impl Into<String>) -> Self {
+        Self {
+            id,
+            position,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait MoveMessageInQueueHandler {
+    fn handle(&self, move_message_in_queue: MoveMessageInQueue) -> Result<()>;
+}
+
+pub struct ClearQueue {
+    operator: String,
+}
+
+impl ClearQueue {
+    pub fn new(operator: impl Into<String>) -> Self {
+        Self {
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait ClearQueueHandler {
+    fn handle(&self, clear_queue: ClearQueue) -> Result<()>;
+}
+
+pub struct CancelScheduledMessage {
+    id: MessageId,
+    operator: String,
+}
+
+impl CancelScheduledMessage {
+    pub fn new(id: MessageId, operator: impl Into<String>) -> Self {
+        Self {
+            id,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait CancelScheduledMessageHandler {
+    fn handle(&self, cancel_scheduled_message: CancelScheduledMessage) -> Result<()>;
+}
+
+pub struct AbortTransmission {
+    operator: String,
+}
+
+impl AbortTransmission {
+    pub fn new(operator: impl Into<String>) -> Self {
+        Self {
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait AbortTransmissionHandler {
+    fn handle(&self, abort_transmission: AbortTransmission) -> Result<()>;
+}
+
+pub struct PreemptTransmission {
+    message: String,
+    mode: PreemptMode,
+    operator: String,
+}
+
+impl PreemptTransmission {
+    /// The message is validated once it has been transliterated.
+    pub fn new(message: impl Into<String>, mode: PreemptMode, operator: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            mode,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait PreemptTransmissionHandler {
+    fn handle(
+        &self,
+        preempt_transmission: PreemptTransmission,
+    ) -> Result<PreemptTransmissionResult>;
+}
+
+pub struct PreemptTransmissionResult {
+    id: MessageId,
+    substitutions: Vec<Substitution>,
+}
+
+impl PreemptTransmissionResult {
+    pub fn new(id: MessageId, substitutions: Vec<Substitution>) -> Self {
+        Self { id, substitutions }
+    }
+
+    pub fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    pub fn substitutions(&self) -> &[Substitution] {
+        &self.substitutions
+    }
+}
+
+pub struct PauseClacks {
+    reason: String,
+    accept_messages: bool,
+    operator: String,
+}
+
+impl PauseClacks {
+    pub fn new(
+        reason: impl Into<String>,
+        accept_messages: bool,
+        operator: impl Into<String>,
+    ) -> Self {
+        Self {
+            reason: reason.into(),
+            accept_messages,
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait PauseClacksHandler {
+    fn handle(&self, pause_clacks: PauseClacks) -> Result<()>;
+}
+
+pub struct ResumeClacks {
+    operator: String,
+}
+
+impl ResumeClacks {
+    pub fn new(operator: impl Into<String>) -> Self {
+        Self {
+            operator: operator.into(),
+        }
+    }
+}
+
+pub trait ResumeClacksHandler {
+    fn handle(&self, resume_clacks: ResumeClacks) -> Result<()>;
 }
 
 pub trait GetStateHandler {
     fn get_state(&self) -> Result<State>;
 }
 
+pub trait GetScheduledMessagesHandler {
+    /// Returns the scheduled messages which are still waiting, the earliest first.
+    fn get_scheduled_messages(&self) -> Result<Vec<QueuedMessage>>;
+}
+
+pub trait GetMessageHandler {
+    fn get_message(&self, id: &MessageId) -> Result<MessageState>;
+}
+
+pub struct MessageState {
+    record: MessageRecord,
+    position: Option<usize>,
+}
+
+impl MessageState {
+    pub fn new(record: MessageRecord, position: Option<usize>) -> Self {
+        Self { record, position }
+    }
+
+    pub fn id(&self) -> &MessageId {
+        self.record.id()
+    }
+
+    pub fn status(&self) -> MessageStatus {
+        self.record.status()
+    }
+
+    /// Number of messages which will be transmitted before this one, only set for queued
+    /// messages.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub fn record(&self) -> &MessageRecord {
+        &self.record
+    }
+}
+
 pub trait GetConfigHandler {
     fn get_config(&self) -> Result<Config>;
 }
 
+pub trait GetEncodingHandler {
+    fn get_encoding(&self) -> Result<EncodingTable>;
+}
+
+pub struct RenderFrame {
+    shutter_positions: ShutterPositions,
+    format: ImageFormat,
+}
+
+impl RenderFrame {
+    pub fn new(shutter_positions: ShutterPositions, format: ImageFormat) -> Self {
+        Self {
+            shutter_positions,
+            format,
+        }
+    }
+}
+
+pub struct RenderMessage {
+    message: String,
+    animated: bool,
+    format: ImageFormat,
+}
+
+impl RenderMessage {
+    /// Animated messages follow the timing of the clacks, otherwise all symbols are rendered
+    /// next to each other. The message is validated once it has been transliterated.
+    pub fn new(message: impl Into<String>, animated: bool, format: ImageFormat) -> Self {
+        Self {
+            message: message.into(),
+            animated,
+            format,
+        }
+    }
+}
+
+pub struct RenderChart {
+    format: ImageFormat,
+}
+
+impl RenderChart {
+    pub fn new(format: ImageFormat) -> Self {
+        Self { format }
+    }
+}
+
+pub trait RenderHandler {
+    fn render_frame(&self, render_frame: RenderFrame) -> Result<Image>;
+    fn render_message(&self, render_message: RenderMessage) -> Result<Image>;
+    fn render_chart(&self, render_chart: RenderChart) -> Result<Image>;
+}
+
+pub struct Image {
+    format: ImageFormat,
+    data: Vec<u8>,
+}
+
+impl Image {
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "image/svg+xml",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Gif => "image/gif",
+        }
+    }
+}
+
+impl TryFrom<&str> for ImageFormat {
+    type Error = Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "svg" => Ok(ImageFormat::Svg),
+            "png" => Ok(ImageFormat::Png),
+            "gif" => Ok(ImageFormat::Gif),
+            other => Err(Error::UnsupportedImageFormat(other.to_string())),
+        }
+    }
+}
+
 pub struct State {
     current_message: Option<CurrentMessage>,
-    queue: Vec<EncodedMessage>,
+    queue: Vec<QueuedMessage>,
+    pause: Option<Pause>,
+    quiet_period: Option<QuietPeriod>,
 }
 
 impl State {
-    pub fn new(current_message: Option<CurrentMessage>, queue: Vec<EncodedMessage>) -> Self {
+    pub fn new(
+        current_message: Option<CurrentMessage>,
+        queue: Vec<QueuedMessage>,
+        pause: Option<Pause>,
+        quiet_period: Option<QuietPeriod>,
+    ) -> Self {
         Self {
             current_message,
             queue,
+            pause,
+            quiet_period,
         }
     }
 
+    pub fn quiet_period(&self) -> Option<&QuietPeriod> {
+        self.quiet_period.as_ref()
+    }
+
     pub fn current_message(&self) -> Option<&CurrentMessage> {
         self.current_message.as_ref()
     }
 
-    pub fn queue(&self) -> &Vec<EncodedMessage> {
+    pub fn pause(&self) -> Option<&Pause> {
+        self.pause.as_ref()
+    }
+
+    pub fn queue(&self) -> &Vec<QueuedMessage> {
         &self.queue
     }
 }
@@ -60,6 +509,7 @@ impl State {
 pub struct Config {
     // yes, it's unclear if that's what we want
     supported_characters: Vec<String>,
+    phrases: Vec<String>,
     max_message_len_in_bytes: usize,
 }
 
@@ -68,13 +518,48 @@ impl Config {
         &self.supported_characters
     }
 
+    pub fn phrases(&self) -> &[String] {
+        &self.phrases
+    }
+
     pub fn max_message_len_in_bytes(&self) -> usize {
         self.max_message_len_in_bytes
     }
 }
 
+pub struct EncodingTable {
+    name: String,
+    version: String,
+    symbols: Vec<EncodingSymbol>,
+}
+
+impl EncodingTable {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn symbols(&self) -> &[EncodingSymbol] {
+        &self.symbols
+    }
+}
+
 pub trait Clacks {
     fn update(&self) -> Result<ClacksUpdateResult>;
+    fn abort(&self, cancel: Option<EncodedMessagePart>) -> Result<Option<MessageId>>;
+    fn preempt(
+        &self,
+        message: QueuedMessage,
+        mode: PreemptMode,
+        encoding: &impl Encoding,
+    ) -> Result<()>;
+    fn pause(&self, pause: Pause) -> Result<()>;
+    fn resume(&self) -> Result<()>;
+    fn current_pause(&self) -> Option<Pause>;
+    fn current_quiet_period(&self) -> Option<QuietPeriod>;
     fn current_message(&self) -> Option<CurrentMessage>;
     fn get_desired_shutter_positions(&self) -> ShutterPositions;
 }
@@ -85,14 +570,64 @@ pub enum ClacksUpdateResult {
 }
 
 pub trait Queue {
-    fn add_message(&self, message: EncodedMessage) -> Result<()>;
-    fn pop_message(&self) -> Option<EncodedMessage>;
-    fn get_messages(&self) -> Result<Vec<EncodedMessage>>;
+    fn add_message(&self, message: QueuedMessage) -> Result<()>;
+    fn pop_message(&self, now: &DateTime) -> Result<Option<QueuedMessage>>;
+    fn get_messages(&self, now: &DateTime) -> Result<Vec<QueuedMessage>>;
+    fn get_scheduled_messages(&self) -> Result<Vec<QueuedMessage>>;
+    fn remove_message(&self, id: &MessageId) -> Result<QueuedMessage>;
+    fn move_message(&self, id: &MessageId, position: usize, now: &DateTime) -> Result<()>;
+    fn clear(&self) -> Result<Vec<QueuedMessage>>;
+}
+
+pub trait RecurringMessages {
+    fn take_due(&self, now: &DateTime) -> Result<Vec<RecurringRun>>;
+    fn mark_handled(&self, run: &RecurringRun) -> Result<()>;
+    fn add(&self, message: RecurringMessage, now: &DateTime) -> Result<()>;
+    fn remove(&self, name: &str) -> Result<()>;
+    fn list(&self) -> Vec<RecurringMessageEntry>;
+}
+
+pub trait MessageLog {
+    fn record(&self, record: MessageRecord);
+    fn get(&self, id: &MessageId) -> Option<MessageRecord>;
 }
 
 pub trait Encoding {
     fn encode(&self, message: &Message) -> Result<EncodedMessage>;
+    fn encode_cancel(&self) -> Option<EncodedMessagePart>;
+    fn encode_resumption(&self, remaining: &[EncodedMessagePart])
+    -> Result<Option<EncodedMessage>>;
     fn supported_characters(&self) -> Vec<String>;
+    fn phrases(&self) -> Vec<String>;
+    fn name(&self) -> String;
+    fn version(&self) -> String;
+    fn symbols(&self) -> Vec<EncodingSymbol>;
+}
+
+pub trait Renderer {
+    fn render_frame(
+        &self,
+        shutter_positions: &ShutterPositions,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>>;
+    fn render_filmstrip(&self, message: &EncodedMessage, format: ImageFormat) -> Result<Vec<u8>>;
+    fn render_animation(
+        &self,
+        message: &EncodedMessage,
+        timing: &TimingConfig,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>>;
+    fn render_chart(
+        &self,
+        name: &str,
+        version: &str,
+        symbols: &[EncodingSymbol],
+        format: ImageFormat,
+    ) -> Result<Vec<u8>>;
+}
+
+pub trait Transliterator {
+    fn transliterate(&self, text: &str) -> Result<Transliteration>;
 }
 
 pub trait Metrics {
@@ -107,6 +642,7 @@ pub trait Metrics {
 pub trait EventPublisher {
     fn publish_clacks_updated(&self) -> Result<()>;
     fn publish_message_added_to_queue(&self) -> Result<()>;
+    fn publish_queue_changed(&self) -> Result<()>;
 }
 
 pub trait ShuttersController {
@@ -123,6 +659,37 @@ impl Clacks for domain::Clacks {
         self.update()
     }
 
+    fn abort(&self, cancel: Option<EncodedMessagePart>) -> Result<Option<MessageId>> {
+        self.abort(cancel)
+    }
+
+    fn preempt(
+        &self,
+        message: QueuedMessage,
+        mode: PreemptMode,
+        encoding: &impl Encoding,
+    ) -> Result<()> {
+        self.preempt(message, mode, encoding.encode_cancel(), |remaining| {
+            encoding.encode_resumption(remaining)
+        })
+    }
+
+    fn pause(&self, pause: Pause) -> Result<()> {
+        self.pause(pause)
+    }
+
+    fn resume(&self) -> Result<()> {
+        self.resume()
+    }
+
+    fn current_pause(&self) -> Option<Pause> {
+        self.current_pause()
+    }
+
+    fn current_quiet_period(&self) -> Option<QuietPeriod> {
+        self.current_quiet_period()
+    }
+
     fn current_message(&self) -> Option<CurrentMessage> {
         self.current_message()
     }
@@ -133,16 +700,64 @@ impl Clacks for domain::Clacks {
 }
 
 impl Queue for domain::Queue {
-    fn add_message(&self, message: EncodedMessage) -> Result<()> {
+    fn add_message(&self, message: QueuedMessage) -> Result<()> {
         self.add_message(message)
     }
 
-    fn pop_message(&self) -> Option<EncodedMessage> {
-        self.pop_message()
+    fn pop_message(&self, now: &DateTime) -> Result<Option<QueuedMessage>> {
+        self.pop_message(now)
+    }
+
+    fn get_messages(&self, now: &DateTime) -> Result<Vec<QueuedMessage>> {
+        self.get_messages(now)
+    }
+
+    fn get_scheduled_messages(&self) -> Result<Vec<QueuedMessage>> {
+        self.get_scheduled_messages()
+    }
+
+    fn remove_message(&self, id: &MessageId) -> Result<QueuedMessage> {
+        self.remove_message(id)
+    }
+
+    fn move_message(&self, id: &MessageId, position: usize, now: &DateTime) -> Result<()> {
+        self.move_message(id, position, now)
+    }
+
+    fn clear(&self) -> Result<Vec<QueuedMessage>> {
+        self.clear()
+    }
+}
+
+impl RecurringMessages for domain::recurring::RecurringMessages {
+    fn take_due(&self, now: &DateTime) -> Result<Vec<RecurringRun>> {
+        self.take_due(now)
+    }
+
+    fn mark_handled(&self, run: &RecurringRun) -> Result<()> {
+        self.mark_handled(run)
+    }
+
+    fn add(&self, message: RecurringMessage, now: &DateTime) -> Result<()> {
+        self.add(message, now)
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        self.remove(name)
+    }
+
+    fn list(&self) -> Vec<RecurringMessageEntry> {
+        self.list()
+    }
+}
+
+impl MessageLog for domain::MessageLog {
+    fn record(&self, record: MessageRecord) {
+        self.record(record)
     }
 
-    fn get_messages(&self) -> Result<Vec<EncodedMessage>> {
-        self.get_messages()
+    fn get(&self, id: &MessageId) -> Option<MessageRecord> {
+        self.get(id)
     }
 }
 
@@ -151,9 +766,42 @@ impl Encoding for domain::Encoding {
         self.encode(message)
     }
 
+    fn encode_cancel(&self) -> Option<EncodedMessagePart> {
+        self.encode_cancel()
+    }
+
+    fn encode_resumption(
+        &self,
+        remaining: &[EncodedMessagePart],
+    ) -> Result<Option<EncodedMessage>> {
+        self.encode_resumption(remaining)
+    }
+
     fn supported_characters(&self) -> Vec<String> {
         self.supported_characters()
     }
+
+    fn phrases(&self) -> Vec<String> {
+        self.phrases()
+    }
+
+    fn name(&self) -> String {
+        self.name().to_string()
+    }
+
+    fn version(&self) -> String {
+        self.version()
+    }
+
+    fn symbols(&self) -> Vec<EncodingSymbol> {
+        self.symbols()
+    }
+}
+
+impl Transliterator for domain::transliteration::Transliterator {
+    fn transliterate(&self, text: &str) -> Result<Transliteration> {
+        self.transliterate(text)
+    }
 }
 
 impl<T> ShuttersController for servos::ShuttersController<T>
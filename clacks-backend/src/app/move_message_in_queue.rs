@@ -0,0 +1,50 @@
+use crate::app;
+use crate::app::{EventPublisher, Metrics, MoveMessageInQueue, Queue};
+use crate::domain::time::Clock;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct MoveMessageInQueueHandler<Q, M, P, K> {
+    queue: Q,
+    metrics: M,
+    publisher: P,
+    clock: K,
+}
+
+impl<Q, M, P, K> MoveMessageInQueueHandler<Q, M, P, K> {
+    pub fn new(queue: Q, metrics: M, publisher: P, clock: K) -> Self {
+        Self {
+            queue,
+            metrics,
+            publisher,
+            clock,
+        }
+    }
+}
+
+impl<Q, M, P, K> app::MoveMessageInQueueHandler for MoveMessageInQueueHandler<Q, M, P, K>
+where
+    Q: Queue,
+    M: Metrics,
+    P: EventPublisher,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, move_message_in_queue: MoveMessageInQueue) -> Result<()> {
+        self.queue.move_message(
+            &move_message_in_queue.id,
+            move_message_in_queue.position,
+            &self.clock.now(),
+        )?;
+        info!(
+            "operator `{}` moved message `{}` to position {}",
+            move_message_in_queue.operator,
+            move_message_in_queue.id,
+            move_message_in_queue.position
+        );
+        self.publisher.publish_queue_changed()?;
+        Ok::<(), Error>(())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::app;
+use crate::app::{Clacks, Metrics, PauseClacks};
+use crate::domain::Pause;
+use crate::domain::time::Clock;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct PauseClacksHandler<C, M, K> {
+    clacks: C,
+    metrics: M,
+    clock: K,
+}
+
+impl<C, M, K> PauseClacksHandler<C, M, K> {
+    pub fn new(clacks: C, metrics: M, clock: K) -> Self {
+        Self {
+            clacks,
+            metrics,
+            clock,
+        }
+    }
+}
+
+impl<C, M, K> app::PauseClacksHandler for PauseClacksHandler<C, M, K>
+where
+    C: Clacks,
+    M: Metrics,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, pause_clacks: PauseClacks) -> Result<()> {
+        self.clacks.pause(Pause::new(
+            pause_clacks.reason.clone(),
+            pause_clacks.operator.clone(),
+            pause_clacks.accept_messages,
+            self.clock.now(),
+        ))?;
+        info!(
+            "operator `{}` paused the clacks: {}",
+            pause_clacks.operator, pause_clacks.reason
+        );
+        Ok::<(), Error>(())
+    }
+}
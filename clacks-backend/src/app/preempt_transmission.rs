@@ -0,0 +1,69 @@
+use crate::app;
+use crate::app::{
+    Clacks, Encoding, Metrics, PreemptTransmission, PreemptTransmissionResult, Transliterator,
+};
+use crate::domain::time::Clock;
+use crate::domain::{MessageId, Priority, QueuedMessage};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct PreemptTransmissionHandler<C, E, T, M, K> {
+    clacks: C,
+    encoding: E,
+    transliterator: T,
+    metrics: M,
+    clock: K,
+}
+
+impl<C, E, T, M, K> PreemptTransmissionHandler<C, E, T, M, K> {
+    pub fn new(clacks: C, encoding: E, transliterator: T, metrics: M, clock: K) -> Self {
+        Self {
+            clacks,
+            encoding,
+            transliterator,
+            metrics,
+            clock,
+        }
+    }
+}
+
+impl<C, E, T, M, K> app::PreemptTransmissionHandler for PreemptTransmissionHandler<C, E, T, M, K>
+where
+    C: Clacks,
+    E: Encoding,
+    T: Transliterator,
+    M: Metrics,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(
+        &self,
+        preempt_transmission: PreemptTransmission,
+    ) -> Result<PreemptTransmissionResult> {
+        let transliteration = self
+            .transliterator
+            .transliterate(&preempt_transmission.message)?;
+        let encoded_message = self.encoding.encode(transliteration.message())?;
+        let id = MessageId::generate();
+        self.clacks.preempt(
+            QueuedMessage::new(
+                id.clone(),
+                encoded_message,
+                Priority::Operator,
+                self.clock.now(),
+            ),
+            preempt_transmission.mode,
+            &self.encoding,
+        )?;
+        info!(
+            "operator `{}` preempted the transmission with message `{}`",
+            preempt_transmission.operator, id
+        );
+        Ok::<PreemptTransmissionResult, Error>(PreemptTransmissionResult::new(
+            id,
+            transliteration.substitutions().to_vec(),
+        ))
+    }
+}
@@ -0,0 +1,54 @@
+use crate::app;
+use crate::app::{EventPublisher, MessageLog, Metrics, Queue, RemoveMessageFromQueue};
+use crate::domain::time::Clock;
+use crate::domain::{MessageRecord, MessageStatus};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct RemoveMessageFromQueueHandler<Q, L, M, P, K> {
+    queue: Q,
+    message_log: L,
+    metrics: M,
+    publisher: P,
+    clock: K,
+}
+
+impl<Q, L, M, P, K> RemoveMessageFromQueueHandler<Q, L, M, P, K> {
+    pub fn new(queue: Q, message_log: L, metrics: M, publisher: P, clock: K) -> Self {
+        Self {
+            queue,
+            message_log,
+            metrics,
+            publisher,
+            clock,
+        }
+    }
+}
+
+impl<Q, L, M, P, K> app::RemoveMessageFromQueueHandler
+    for RemoveMessageFromQueueHandler<Q, L, M, P, K>
+where
+    Q: Queue,
+    L: MessageLog,
+    M: Metrics,
+    P: EventPublisher,
+    K: Clock,
+{
+    #[application_handler]
+    fn handle(&self, remove_message_from_queue: RemoveMessageFromQueue) -> Result<()> {
+        let message = self.queue.remove_message(&remove_message_from_queue.id)?;
+        info!(
+            "operator `{}` removed message `{}` from the queue",
+            remove_message_from_queue.operator,
+            message.id()
+        );
+        self.message_log.record(
+            MessageRecord::new(&message, MessageStatus::Rejected)
+                .with_finished_at(self.clock.now()),
+        );
+        self.publisher.publish_queue_changed()?;
+        Ok::<(), Error>(())
+    }
+}
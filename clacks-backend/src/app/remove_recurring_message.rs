@@ -0,0 +1,37 @@
+use crate::app;
+use crate::app::{Metrics, RecurringMessages, RemoveRecurringMessage};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct RemoveRecurringMessageHandler<R, M> {
+    recurring_messages: R,
+    metrics: M,
+}
+
+impl<R, M> RemoveRecurringMessageHandler<R, M> {
+    pub fn new(recurring_messages: R, metrics: M) -> Self {
+        Self {
+            recurring_messages,
+            metrics,
+        }
+    }
+}
+
+impl<R, M> app::RemoveRecurringMessageHandler for RemoveRecurringMessageHandler<R, M>
+where
+    R: RecurringMessages,
+    M: Metrics,
+{
+    #[application_handler]
+    fn handle(&self, remove_recurring_message: RemoveRecurringMessage) -> Result<()> {
+        self.recurring_messages
+            .remove(&remove_recurring_message.name)?;
+        info!(
+            "operator `{}` removed recurring message `{}`",
+            remove_recurring_message.operator, remove_recurring_message.name
+        );
+        Ok::<(), Error>(())
+    }
+}
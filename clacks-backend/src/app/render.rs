@@ -0,0 +1,87 @@
+use crate::app;
+use crate::app::{
+    Encoding, Image, Metrics, RenderChart, RenderFrame, RenderMessage, Renderer, Transliterator,
+};
+use crate::domain::TimingConfig;
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+
+#[derive(Clone)]
+pub struct RenderHandler<E, T, R, M> {
+    encoding: E,
+    transliterator: T,
+    renderer: R,
+    timing: TimingConfig,
+    metrics: M,
+}
+
+impl<E, T, R, M> RenderHandler<E, T, R, M> {
+    pub fn new(
+        encoding: E,
+        transliterator: T,
+        renderer: R,
+        timing: TimingConfig,
+        metrics: M,
+    ) -> Self {
+        Self {
+            encoding,
+            transliterator,
+            renderer,
+            timing,
+            metrics,
+        }
+    }
+}
+
+impl<E, T, R, M> app::RenderHandler for RenderHandler<E, T, R, M>
+where
+    E: Encoding,
+    T: Transliterator,
+    R: Renderer,
+    M: Metrics,
+{
+    #[application_handler]
+    fn render_frame(&self, render_frame: RenderFrame) -> Result<Image> {
+        let data = self
+            .renderer
+            .render_frame(&render_frame.shutter_positions, render_frame.format)?;
+        Ok::<Image, Error>(Image {
+            format: render_frame.format,
+            data,
+        })
+    }
+
+    #[application_handler]
+    fn render_message(&self, render_message: RenderMessage) -> Result<Image> {
+        let transliteration = self.transliterator.transliterate(&render_message.message)?;
+        let encoded_message = self.encoding.encode(transliteration.message())?;
+        let data = match render_message.animated {
+            true => self.renderer.render_animation(
+                &encoded_message,
+                &self.timing,
+                render_message.format,
+            )?,
+            false => self
+                .renderer
+                .render_filmstrip(&encoded_message, render_message.format)?,
+        };
+        Ok::<Image, Error>(Image {
+            format: render_message.format,
+            data,
+        })
+    }
+
+    #[application_handler]
+    fn render_chart(&self, render_chart: RenderChart) -> Result<Image> {
+        let data = self.renderer.render_chart(
+            &self.encoding.name(),
+            &self.encoding.version(),
+            &self.encoding.symbols(),
+            render_chart.format,
+        )?;
+        Ok::<Image, Error>(Image {
+            format: render_chart.format,
+            data,
+        })
+    }
+}
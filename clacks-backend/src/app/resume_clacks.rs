@@ -0,0 +1,30 @@
+use crate::app;
+use crate::app::{Clacks, Metrics, ResumeClacks};
+use crate::errors::{Error, Result};
+use clacks_macros::application_handler;
+use log::info;
+
+#[derive(Clone)]
+pub struct ResumeClacksHandler<C, M> {
+    clacks: C,
+    metrics: M,
+}
+
+impl<C, M> ResumeClacksHandler<C, M> {
+    pub fn new(clacks: C, metrics: M) -> Self {
+        Self { clacks, metrics }
+    }
+}
+
+impl<C, M> app::ResumeClacksHandler for ResumeClacksHandler<C, M>
+where
+    C: Clacks,
+    M: Metrics,
+{
+    #[application_handler]
+    fn handle(&self, resume_clacks: ResumeClacks) -> Result<()> {
+        self.clacks.resume()?;
+        info!("operator `{}` resumed the clacks", resume_clacks.operator);
+        Ok::<(), Error>(())
+    }
+}
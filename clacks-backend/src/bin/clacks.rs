@@ -1,9 +1,34 @@
+use anyhow::anyhow;
+use clacks_backend::adapters::render::Renderer;
 use clacks_backend::adapters::{ConfigLoader, Metrics, PubSub};
+use clacks_backend::app::abort_transmission::AbortTransmissionHandler;
 use clacks_backend::app::add_message_to_queue::AddMessageToQueueHandler;
+use clacks_backend::app::add_recurring_message::AddRecurringMessageHandler;
+use clacks_backend::app::cancel_scheduled_message::CancelScheduledMessageHandler;
+use clacks_backend::app::clear_queue::ClearQueueHandler;
+use clacks_backend::app::enqueue_recurring_messages::EnqueueRecurringMessagesHandler;
 use clacks_backend::app::get_config::GetConfigHandler;
+use clacks_backend::app::get_encoding::GetEncodingHandler;
+use clacks_backend::app::get_message::GetMessageHandler;
+use clacks_backend::app::get_recurring_messages::GetRecurringMessagesHandler;
+use clacks_backend::app::get_scheduled_messages::GetScheduledMessagesHandler;
 use clacks_backend::app::get_state::GetStateHandler;
+use clacks_backend::app::move_message_in_queue::MoveMessageInQueueHandler;
+use clacks_backend::app::pause_clacks::PauseClacksHandler;
+use clacks_backend::app::preempt_transmission::PreemptTransmissionHandler;
+use clacks_backend::app::remove_message_from_queue::RemoveMessageFromQueueHandler;
+use clacks_backend::app::remove_recurring_message::RemoveRecurringMessageHandler;
+use clacks_backend::app::render::RenderHandler;
+use clacks_backend::app::resume_clacks::ResumeClacksHandler;
 use clacks_backend::app::update_clacks::UpdateClacksHandler;
-use clacks_backend::config::Config;
+use clacks_backend::app::{ImageFormat, Renderer as _};
+use clacks_backend::config::{Config, Operators};
+use clacks_backend::domain::analysis;
+use clacks_backend::domain::decoder::{Decoder, DecoderEvent};
+use clacks_backend::domain::optimizer;
+use clacks_backend::domain::presets::EncodingPreset;
+use clacks_backend::domain::time::Clock as _;
+use clacks_backend::domain::transliteration::{Transliteration, Transliterator};
 use clacks_backend::domain::{
     Encoding, MessageComponent, ShutterLocation, ShutterPosition, ShutterPositions, servos,
 };
@@ -12,10 +37,12 @@ use clacks_backend::ports::http;
 use clacks_backend::ports::http::EventSubscriber;
 use clacks_backend::ports::timers;
 use clacks_backend::{adapters, app, domain};
-use clap::{Command, arg};
+use clap::{Arg, ArgMatches, Command, arg};
 use env_logger::Env;
-use log::error;
+use log::{error, info, warn};
 use prometheus::Registry;
+use std::io::Write;
+use std::{fs, io};
 
 fn cli() -> Command {
     Command::new("clacks")
@@ -27,6 +54,35 @@ fn cli() -> Command {
                 .about("Runs the program")
                 .arg(arg!(<CONFIG> "Path to the configuration file")),
         )
+        .subcommand(
+            Command::new("simulate")
+                .about("Prints the shutter positions caused by scripted submissions without moving any shutters, by default until all submissions were transmitted")
+                .arg(arg!(<CONFIG> "Path to the configuration file"))
+                .arg(arg!(<SCRIPT> "Path to the file with submissions, one per line: seconds after the start, a space and the message"))
+                .arg(
+                    arg!(--speed <FACTOR> "How much faster than real time the simulation runs")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("100"),
+                )
+                .arg(
+                    arg!(--duration <SECONDS> "Simulated time after which the simulation stops")
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            Command::new("pause")
+                .about("Pauses a running clacks, e.g. for maintenance")
+                .arg(arg!(<REASON> "Reason for pausing the clacks"))
+                .arg(arg!(--"accept-messages" "Keeps adding new messages to the queue while paused"))
+                .arg(server_arg())
+                .arg(token_arg()),
+        )
+        .subcommand(
+            Command::new("resume")
+                .about("Resumes a paused clacks")
+                .arg(server_arg())
+                .arg(token_arg()),
+        )
         .subcommand(
             Command::new("shutters")
                 .about("Moves shutters")
@@ -34,14 +90,96 @@ fn cli() -> Command {
                 .subcommand(Command::new("open").about("Opens shutters"))
                 .subcommand(Command::new("close").about("Opens shutters")),
         )
+        .subcommand(
+            Command::new("decode")
+                .about("Decodes shutter positions, one line of comma separated open shutters per position, an empty line means that all shutters are closed")
+                .arg(arg!([FILE] "Path to the file with shutter positions, reads from stdin if not given"))
+                .arg(arg!(--config <CONFIG> "Path to the configuration file defining the encoding")),
+        )
         .subcommand(
             Command::new("encoding")
                 .about("Interacts with the encoding")
                 .subcommand_required(true)
-                .subcommand(Command::new("show").about("Displays used and unused combinations")),
+                .subcommand(
+                    Command::new("show")
+                        .about("Displays used and unused combinations")
+                        .arg(arg!([CONFIG] "Path to the configuration file defining the encoding"))
+                        .arg(preset_arg()),
+                )
+                .subcommand(
+                    Command::new("analyze")
+                        .about("Reports symbols which are easy to misread")
+                        .arg(arg!([CONFIG] "Path to the configuration file defining the encoding"))
+                        .arg(preset_arg()),
+                )
+                .subcommand(
+                    Command::new("optimize")
+                        .about("Reassigns shutter positions so that frequent characters move fewer shutters, each line of the corpus is a message")
+                        .arg(arg!(<CORPUS> "Path to the text file with example messages"))
+                        .arg(arg!([CONFIG] "Path to the configuration file defining the encoding"))
+                        .arg(preset_arg())
+                        .arg(
+                            arg!(--"minimum-distance" <DISTANCE> "Minimum number of shutters by which all symbols differ")
+                                .value_parser(clap::value_parser!(u32).range(1..=6))
+                                .default_value("1"),
+                        )
+                        .arg(arg!(--output <FILE> "Path to the file to which the encoding table is written, prints it if not given")),
+                ),
+        )
+        .subcommand(
+            Command::new("render")
+                .about("Renders images of the clacks")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("frame")
+                        .about("Renders shutter positions")
+                        .arg(arg!([SHUTTERS] "Comma separated open shutters, all shutters are closed if not given"))
+                        .arg(format_arg())
+                        .arg(output_arg()),
+                )
+                .subcommand(
+                    Command::new("message")
+                        .about("Renders all symbols of a message next to each other or as an animation")
+                        .arg(arg!(<CONFIG> "Path to the configuration file"))
+                        .arg(arg!(<MESSAGE> "Message to render"))
+                        .arg(arg!(--animated "Follows the configured timing instead of showing all symbols at once"))
+                        .arg(format_arg())
+                        .arg(output_arg()),
+                )
+                .subcommand(
+                    Command::new("chart")
+                        .about("Renders a chart of all symbols of the encoding as an SVG image")
+                        .arg(arg!([CONFIG] "Path to the configuration file defining the encoding"))
+                        .arg(preset_arg())
+                        .arg(output_arg()),
+                ),
         )
 }
 
+fn format_arg() -> Arg {
+    arg!(--format <FORMAT> "Image format")
+        .value_parser(["svg", "png", "gif"])
+        .default_value("svg")
+}
+
+fn output_arg() -> Arg {
+    arg!(--output <FILE> "Path to the file to which the image is written, prints it if not given")
+}
+
+fn preset_arg() -> Arg {
+    arg!(--preset <PRESET> "Name of the preset to use instead of the configured encoding")
+        .value_parser(EncodingPreset::iter().map(|v| v.name()).collect::<Vec<_>>())
+        .conflicts_with("CONFIG")
+}
+
+fn server_arg() -> Arg {
+    arg!(--server <URL> "Address of the running clacks").default_value("http://localhost:8080")
+}
+
+fn token_arg() -> Arg {
+    arg!(--token <TOKEN> "Token of the operator").required(true)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::Builder::from_env(Env::default().filter_or("RUST_LOG", "info")).init();
@@ -52,6 +190,35 @@ async fn main() -> Result<()> {
             let config_file_path = sub_matches.try_get_one::<String>("CONFIG")?.unwrap();
             run(config_file_path).await?;
         }
+        Some(("simulate", sub_matches)) => {
+            let config_file_path = sub_matches.try_get_one::<String>("CONFIG")?.unwrap();
+            let script_file_path = sub_matches.try_get_one::<String>("SCRIPT")?.unwrap();
+            let speed = sub_matches.try_get_one::<u32>("speed")?.unwrap();
+            let duration = sub_matches.try_get_one::<u64>("duration")?;
+            simulate(
+                config_file_path,
+                script_file_path,
+                *speed,
+                duration.copied(),
+            )?;
+        }
+        Some(("pause", sub_matches)) => {
+            let reason = sub_matches.try_get_one::<String>("REASON")?.unwrap();
+            let accept_messages = sub_matches.get_flag("accept-messages");
+            send_operator_request(
+                sub_matches,
+                "/api/pause",
+                Some(serde_json::json!({
+                    "reason": reason,
+                    "acceptMessages": accept_messages,
+                })),
+            )?;
+            info!("the clacks was paused");
+        }
+        Some(("resume", sub_matches)) => {
+            send_operator_request(sub_matches, "/api/resume", None)?;
+            info!("the clacks was resumed");
+        }
         Some(("shutters", sub_matches)) => match sub_matches.subcommand() {
             Some(("open", _sub_matches)) => {
                 move_shutters(&ShutterPosition::Open)?;
@@ -61,18 +228,112 @@ async fn main() -> Result<()> {
             }
             _ => unreachable!(),
         },
+        Some(("decode", sub_matches)) => {
+            let file_path = sub_matches.try_get_one::<String>("FILE")?;
+            let config_file_path = sub_matches.try_get_one::<String>("config")?;
+            decode(file_path, config_file_path)?;
+        }
         Some(("encoding", sub_matches)) => match sub_matches.subcommand() {
-            Some(("show", _sub_matches)) => {
-                show_encoding()?;
+            Some(("show", sub_matches)) => {
+                let encoding = encoding_from_args(sub_matches)?;
+                show_encoding(&encoding)?;
+            }
+            Some(("analyze", sub_matches)) => {
+                let encoding = encoding_from_args(sub_matches)?;
+                analyze_encoding(&encoding);
+            }
+            Some(("optimize", sub_matches)) => {
+                let encoding = encoding_from_args(sub_matches)?;
+                let corpus_file_path = sub_matches.try_get_one::<String>("CORPUS")?.unwrap();
+                let minimum_distance = sub_matches.try_get_one::<u32>("minimum-distance")?.unwrap();
+                let output_file_path = sub_matches.try_get_one::<String>("output")?;
+                optimize_encoding(
+                    &encoding,
+                    corpus_file_path,
+                    *minimum_distance,
+                    output_file_path,
+                )?;
             }
             _ => unreachable!(),
         },
+        Some(("render", sub_matches)) => {
+            let renderer = Renderer::new();
+            let (image, output_file_path) = match sub_matches.subcommand() {
+                Some(("frame", sub_matches)) => {
+                    let shutter_positions = match sub_matches.try_get_one::<String>("SHUTTERS")? {
+                        Some(shutters) => parse_shutter_positions(shutters)?,
+                        None => ShutterPositions::new_with_all_closed(),
+                    };
+                    let format = image_format(sub_matches)?;
+                    (
+                        renderer.render_frame(&shutter_positions, format)?,
+                        sub_matches.try_get_one::<String>("output")?,
+                    )
+                }
+                Some(("message", sub_matches)) => {
+                    let config_file_path = sub_matches.try_get_one::<String>("CONFIG")?.unwrap();
+                    let config = ConfigLoader::new(config_file_path).load()?;
+                    let message = sub_matches.try_get_one::<String>("MESSAGE")?.unwrap();
+                    let transliterator =
+                        Transliterator::new(config.encoding(), config.transliterations().clone())?;
+                    let transliteration = transliterator.transliterate(message)?;
+                    let encoded_message = config.encoding().encode(transliteration.message())?;
+                    let format = image_format(sub_matches)?;
+                    let image = match sub_matches.get_flag("animated") {
+                        true => {
+                            renderer.render_animation(&encoded_message, config.timing(), format)?
+                        }
+                        false => renderer.render_filmstrip(&encoded_message, format)?,
+                    };
+                    (image, sub_matches.try_get_one::<String>("output")?)
+                }
+                Some(("chart", sub_matches)) => {
+                    let encoding = encoding_from_args(sub_matches)?;
+                    (
+                        renderer.render_chart(
+                            encoding.name(),
+                            &encoding.version(),
+                            &encoding.symbols(),
+                            ImageFormat::Svg,
+                        )?,
+                        sub_matches.try_get_one::<String>("output")?,
+                    )
+                }
+                _ => unreachable!(),
+            };
+            match output_file_path {
+                Some(output_file_path) => fs::write(output_file_path, image)?,
+                None => io::stdout().write_all(&image)?,
+            }
+        }
         _ => unreachable!(),
     }
 
     Ok(())
 }
 
+fn encoding_from_args(sub_matches: &ArgMatches) -> Result<Encoding> {
+    match sub_matches.try_get_one::<String>("preset")? {
+        Some(preset) => Ok(EncodingPreset::try_from(preset.as_str())?.encoding()),
+        None => load_encoding(sub_matches.try_get_one::<String>("CONFIG")?),
+    }
+}
+
+fn image_format(sub_matches: &ArgMatches) -> Result<ImageFormat> {
+    let format = sub_matches.try_get_one::<String>("format")?.unwrap();
+    ImageFormat::try_from(format.as_str())
+}
+
+fn parse_shutter_positions(line: &str) -> Result<ShutterPositions> {
+    let open_shutters = line
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| ShutterLocation::try_from(v.to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    ShutterPositions::new(&open_shutters)
+}
+
 async fn run(config_file_path: &str) -> Result<()> {
     let config_loader = ConfigLoader::new(config_file_path);
     let config = config_loader.load()?;
@@ -88,17 +349,56 @@ async fn run(config_file_path: &str) -> Result<()> {
 
     let shutters_controller = servos::ShuttersController::new(servo_controller);
 
-    let queue = domain::Queue::new(config.queue_size())?;
-    let encoding = domain::Encoding::default();
+    let queue = match config.queue_path() {
+        Some(queue_path) => domain::Queue::new_with_storage(
+            config.queue_size(),
+            adapters::storage::FileQueueStorage::new(queue_path, config.encoding().clone()),
+        )?,
+        None => domain::Queue::new(config.queue_size())?,
+    };
+    let encoding = config.encoding().clone();
 
-    let messages_to_inject = config
-        .messages_to_inject()
-        .iter()
-        .map(|v| encoding.encode(v))
-        .collect::<Result<Vec<_>>>()?;
-    let messages_to_inject = domain::MessagesToInject::new(messages_to_inject);
+    let messages_to_inject = match config.injection_state_path() {
+        Some(injection_state_path) => domain::injection::MessagesToInject::new_with_storage(
+            config.messages_to_inject().to_vec(),
+            encoding.clone(),
+            config.time_zone().clone(),
+            adapters::storage::FileInjectionStorage::new(injection_state_path),
+        )?,
+        None => domain::injection::MessagesToInject::new(
+            config.messages_to_inject().to_vec(),
+            encoding.clone(),
+            config.time_zone().clone(),
+        )?,
+    }
+    .with_strategy(config.injection_strategy());
+    let messages_to_inject = match config.injection_seed() {
+        Some(seed) => messages_to_inject.with_seed(seed),
+        None => messages_to_inject,
+    };
 
-    let clacks = domain::Clacks::new(config.timing().clone(), queue.clone(), messages_to_inject);
+    let message_log = domain::MessageLog::new();
+    let clock = domain::time::SystemClock::new();
+    let clacks = domain::Clacks::new(
+        config.timing().clone(),
+        queue.clone(),
+        messages_to_inject,
+        message_log.clone(),
+    )
+    .with_park_position(config.park_position().clone())
+    .with_clock(clock.clone());
+    let clacks = match config.quiet_hours() {
+        Some(quiet_hours) => clacks.with_quiet_hours(quiet_hours.clone()),
+        None => clacks,
+    };
+    let clacks = match config.clock_mode() {
+        Some(clock_mode) => clacks.with_clock_messages(domain::clock_mode::ClockMessages::new(
+            clock_mode.clone(),
+            encoding.clone(),
+            config.time_zone().clone(),
+        )?),
+        None => clacks,
+    };
 
     let update_clacks_handler = UpdateClacksHandler::new(
         clacks.clone(),
@@ -106,16 +406,118 @@ async fn run(config_file_path: &str) -> Result<()> {
         pubsub.clone(),
         shutters_controller,
     );
+    let transliterator = Transliterator::new(&encoding, config.transliterations().clone())?;
+
+    let now = clock.now();
+    let recurring_messages = match config.recurring_messages_path() {
+        Some(recurring_messages_path) => domain::recurring::RecurringMessages::new_with_storage(
+            config.time_zone().clone(),
+            config.recurring_messages().to_vec(),
+            adapters::storage::FileRecurringMessagesStorage::new(recurring_messages_path),
+            &now,
+        )?,
+        None => domain::recurring::RecurringMessages::new(
+            config.time_zone().clone(),
+            config.recurring_messages().to_vec(),
+            &now,
+        )?,
+    };
+    let enqueue_recurring_messages_handler = EnqueueRecurringMessagesHandler::new(
+        recurring_messages.clone(),
+        queue.clone(),
+        encoding.clone(),
+        transliterator.clone(),
+        metrics.clone(),
+        pubsub.clone(),
+        clock.clone(),
+    );
+    let get_recurring_messages_handler =
+        GetRecurringMessagesHandler::new(recurring_messages.clone(), metrics.clone());
+    let add_recurring_message_handler = AddRecurringMessageHandler::new(
+        recurring_messages.clone(),
+        encoding.clone(),
+        transliterator.clone(),
+        metrics.clone(),
+        clock.clone(),
+    );
+    let remove_recurring_message_handler =
+        RemoveRecurringMessageHandler::new(recurring_messages, metrics.clone());
+
     let add_message_to_queue_handler = AddMessageToQueueHandler::new(
         queue.clone(),
         metrics.clone(),
         encoding.clone(),
+        transliterator.clone(),
+        pubsub.clone(),
+        clacks.clone(),
+        clock.clone(),
+    );
+    let abort_transmission_handler =
+        AbortTransmissionHandler::new(clacks.clone(), encoding.clone(), metrics.clone());
+    let preempt_transmission_handler = PreemptTransmissionHandler::new(
+        clacks.clone(),
+        encoding.clone(),
+        transliterator.clone(),
+        metrics.clone(),
+        clock.clone(),
+    );
+    let pause_clacks_handler =
+        PauseClacksHandler::new(clacks.clone(), metrics.clone(), clock.clone());
+    let resume_clacks_handler = ResumeClacksHandler::new(clacks.clone(), metrics.clone());
+    let get_state_handler = GetStateHandler::new(
+        clacks.clone(),
+        queue.clone(),
+        metrics.clone(),
+        clock.clone(),
+    );
+    let get_message_handler = GetMessageHandler::new(
+        queue.clone(),
+        message_log.clone(),
+        metrics.clone(),
+        clock.clone(),
+    );
+    let get_scheduled_messages_handler =
+        GetScheduledMessagesHandler::new(queue.clone(), metrics.clone());
+    let cancel_scheduled_message_handler = CancelScheduledMessageHandler::new(
+        queue.clone(),
+        message_log.clone(),
+        metrics.clone(),
+        pubsub.clone(),
+        clock.clone(),
+    );
+    let remove_message_from_queue_handler = RemoveMessageFromQueueHandler::new(
+        queue.clone(),
+        message_log.clone(),
+        metrics.clone(),
         pubsub.clone(),
+        clock.clone(),
+    );
+    let move_message_in_queue_handler = MoveMessageInQueueHandler::new(
+        queue.clone(),
+        metrics.clone(),
+        pubsub.clone(),
+        clock.clone(),
+    );
+    let clear_queue_handler = ClearQueueHandler::new(
+        queue.clone(),
+        message_log,
+        metrics.clone(),
+        pubsub.clone(),
+        clock.clone(),
     );
-    let get_state_handler = GetStateHandler::new(clacks.clone(), queue.clone(), metrics.clone());
     let get_config_handler = GetConfigHandler::new(encoding.clone(), metrics.clone());
+    let get_encoding_handler = GetEncodingHandler::new(encoding.clone(), metrics.clone());
+    let render_handler = RenderHandler::new(
+        encoding.clone(),
+        transliterator,
+        Renderer::new(),
+        config.timing().clone(),
+        metrics.clone(),
+    );
 
     let mut timer = timers::UpdateClacksTimer::new(update_clacks_handler);
+    let mut recurring_messages_timer =
+        timers::EnqueueRecurringMessagesTimer::new(enqueue_recurring_messages_handler);
     let server = http::Server::new();
 
     tokio::spawn({
@@ -124,18 +526,205 @@ async fn run(config_file_path: &str) -> Result<()> {
         }
     });
 
-    let http_deps = HttpDeps::new(
+    tokio::spawn({
+        async move {
+            recurring_messages_timer.run().await;
+        }
+    });
+
+    let http_deps = HttpDeps {
         get_state_handler,
+        get_message_handler,
+        get_scheduled_messages_handler,
+        cancel_scheduled_message_handler,
+        remove_message_from_queue_handler,
+        move_message_in_queue_handler,
+        clear_queue_handler,
+        abort_transmission_handler,
+        preempt_transmission_handler,
+        pause_clacks_handler,
+        resume_clacks_handler,
+        get_recurring_messages_handler,
+        add_recurring_message_handler,
+        remove_recurring_message_handler,
         add_message_to_queue_handler,
         get_config_handler,
+        get_encoding_handler,
+        render_handler,
+        operators: config.operators().clone(),
         metrics,
         pubsub,
-    );
+    };
 
     server_loop(&server, &config, http_deps).await;
     Ok(())
 }
 
+struct Submission {
+    at: std::time::Duration,
+    transliteration: Transliteration,
+}
+
+fn load_submissions(
+    script_file_path: &str,
+    transliterator: &Transliterator,
+) -> Result<Vec<Submission>> {
+    let script = fs::read_to_string(script_file_path)?;
+    let mut submissions = vec![];
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || anyhow!("line {}: expected seconds and a message: {}", i + 1, line);
+        let (at, message) = line.split_once(' ').ok_or_else(invalid)?;
+        let at: f64 = at.parse().map_err(|_| invalid())?;
+        if !at.is_finite() || at < 0.0 {
+            return Err(invalid().into());
+        }
+        let transliteration = transliterator
+            .transliterate(message.trim())
+            .map_err(|err| anyhow!("line {}: {}", i + 1, err))?;
+        submissions.push(Submission {
+            at: std::time::Duration::from_secs_f64(at),
+            transliteration,
+        });
+    }
+    submissions.sort_by_key(|v| v.at);
+    Ok(submissions)
+}
+
+fn simulate(
+    config_file_path: &str,
+    script_file_path: &str,
+    speed: u32,
+    duration: Option<u64>,
+) -> Result<()> {
+    let config = ConfigLoader::new(config_file_path).load()?;
+    let encoding = config.encoding().clone();
+    let transliterator = Transliterator::new(&encoding, config.transliterations().clone())?;
+    let submissions = load_submissions(script_file_path, &transliterator)?;
+
+    let clock = domain::time::ManualClock::new(domain::time::DateTime::now());
+    let queue = domain::Queue::new(config.queue_size())?;
+    let messages_to_inject = domain::injection::MessagesToInject::new(
+        config.messages_to_inject().to_vec(),
+        encoding.clone(),
+        config.time_zone().clone(),
+    )?
+    .with_strategy(config.injection_strategy());
+    let messages_to_inject = match config.injection_seed() {
+        Some(seed) => messages_to_inject.with_seed(seed),
+        None => messages_to_inject,
+    };
+    let clacks = domain::Clacks::new(
+        config.timing().clone(),
+        queue.clone(),
+        messages_to_inject,
+        domain::MessageLog::new(),
+    )
+    .with_park_position(config.park_position().clone())
+    .with_clock(clock.clone());
+    let clacks = match config.quiet_hours() {
+        Some(quiet_hours) => clacks.with_quiet_hours(quiet_hours.clone()),
+        None => clacks,
+    };
+    let clacks = match config.clock_mode() {
+        Some(clock_mode) => clacks.with_clock_messages(domain::clock_mode::ClockMessages::new(
+            clock_mode.clone(),
+            encoding.clone(),
+            config.time_zone().clone(),
+        )?),
+        None => clacks,
+    };
+
+    let step = timers::UPDATE_CLACKS_EVERY;
+    let duration = duration.map(std::time::Duration::from_secs);
+    let mut elapsed = std::time::Duration::ZERO;
+    let mut submissions = submissions.into_iter().peekable();
+    let mut last_shown = None;
+    loop {
+        while let Some(submission) = submissions.next_if(|v| v.at <= elapsed) {
+            let transliteration = submission.transliteration;
+            queue.add_message(domain::QueuedMessage::new(
+                domain::MessageId::generate(),
+                encoding.encode(transliteration.message())?,
+                domain::Priority::Public,
+                clock.now(),
+            ))?;
+            println!(
+                "{:>8.1}s  submitted '{}'",
+                elapsed.as_secs_f64(),
+                transliteration.message().text()
+            );
+        }
+
+        clacks.update()?;
+        let shutter_positions = clacks.get_desired_shutter_positions();
+        let symbol = clacks
+            .current_message()
+            .and_then(|v| v.current().map(|v| v.element().clone()));
+        let shown = (shutter_positions, symbol);
+        if last_shown.as_ref() != Some(&shown) {
+            let symbol = match &shown.1 {
+                Some(MessageComponent::Character(character)) => format!("'{}'", character),
+                Some(MessageComponent::Shift) => "SHIFT".to_string(),
+                Some(MessageComponent::Checksum) => "CHECKSUM".to_string(),
+                Some(MessageComponent::End) => "END".to_string(),
+                Some(MessageComponent::Cancel) => "CANCEL".to_string(),
+                None => "-".to_string(),
+            };
+            println!(
+                "{:>8.1}s  {:<8}  {}",
+                elapsed.as_secs_f64(),
+                symbol,
+                shown.0
+            );
+            last_shown = Some(shown);
+        }
+
+        let done = match duration {
+            Some(duration) => elapsed >= duration,
+            None => {
+                submissions.peek().is_none()
+                    && queue.get_messages(&clock.now())?.is_empty()
+                    && clacks.current_message().is_none()
+            }
+        };
+        if done {
+            return Ok(());
+        }
+
+        std::thread::sleep(step / speed);
+        clock.advance(&domain::time::Duration::new_from_std(step));
+        elapsed += step;
+    }
+}
+
+fn send_operator_request(
+    sub_matches: &ArgMatches,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<()> {
+    let server = sub_matches.try_get_one::<String>("server")?.unwrap();
+    let token = sub_matches.try_get_one::<String>("token")?.unwrap();
+    let request = ureq::post(format!("{}{}", server.trim_end_matches('/'), path))
+        .header("Authorization", format!("Bearer {}", token))
+        .config()
+        .http_status_as_error(false)
+        .build();
+    let mut response = match body {
+        Some(body) => request.send_json(body)?,
+        None => request.send_empty()?,
+    };
+
+    if !response.status().is_success() {
+        let message = response.body_mut().read_to_string()?;
+        return Err(anyhow!("the server returned {}: {}", response.status(), message).into());
+    }
+    Ok(())
+}
+
 fn move_shutters(position: &ShutterPosition) -> Result<()> {
     #[cfg(not(feature = "raspberry_pi"))]
     let servo_controller = adapters::MockServoController::new();
@@ -151,47 +740,126 @@ fn move_shutters(position: &ShutterPosition) -> Result<()> {
     })
 }
 
-fn show_encoding() -> Result<()> {
-    let encoding = Encoding::default();
+fn load_encoding(config_file_path: Option<&String>) -> Result<Encoding> {
+    Ok(match config_file_path {
+        Some(config_file_path) => ConfigLoader::new(config_file_path)
+            .load()?
+            .encoding()
+            .clone(),
+        None => Encoding::default(),
+    })
+}
+
+fn decode(file_path: Option<&String>, config_file_path: Option<&String>) -> Result<()> {
+    let encoding = load_encoding(config_file_path)?;
+    let input = match file_path {
+        Some(file_path) => fs::read_to_string(file_path)?,
+        None => io::read_to_string(io::stdin())?,
+    };
 
-    for i in 0..64 {
-        let mut open_shutters = vec![];
+    let mut decoder = Decoder::new(encoding);
+    for (i, line) in input.lines().enumerate() {
+        let shutter_positions = parse_shutter_positions(line)?;
 
-        if i & 1 != 0 {
-            open_shutters.push(ShutterLocation::TopLeft)
+        match decoder.push(&shutter_positions) {
+            Some(DecoderEvent::Message(message)) => println!("{}", message.text()),
+            Some(DecoderEvent::InvalidMessage(text)) => {
+                warn!("line {}: invalid message '{}'", i + 1, text)
+            }
+            Some(DecoderEvent::InvalidChecksum(text)) => {
+                warn!("line {}: invalid checksum for message '{}'", i + 1, text)
+            }
+            Some(DecoderEvent::Cancelled(text)) => {
+                warn!("line {}: message '{}' was cancelled", i + 1, text)
+            }
+            Some(DecoderEvent::UnrecognisedPositions(positions)) => {
+                warn!(
+                    "line {}: unrecognised shutter positions {}",
+                    i + 1,
+                    positions
+                )
+            }
+            None => {}
         }
+    }
 
-        if i & 1 << 1 != 0 {
-            open_shutters.push(ShutterLocation::TopRight)
-        }
+    if let Some(text) = decoder.pending_text() {
+        warn!("input ended before the end of the message '{}'", text);
+    }
 
-        if i & 1 << 2 != 0 {
-            open_shutters.push(ShutterLocation::MiddleLeft)
-        }
+    Ok(())
+}
 
-        if i & 1 << 3 != 0 {
-            open_shutters.push(ShutterLocation::MiddleRight)
-        }
+fn show_encoding(encoding: &Encoding) -> Result<()> {
+    for i in 0..64 {
+        let shutter_positions = ShutterPositions::new_from_bits(i)?;
 
-        if i & 1 << 4 != 0 {
-            open_shutters.push(ShutterLocation::BottomLeft)
-        }
+        let status: Vec<String> = encoding
+            .check_usage(&shutter_positions)
+            .iter()
+            .map(|symbol| symbol.to_string())
+            .collect();
+        let status = status.join(", ");
 
-        if i & 1 << 5 != 0 {
-            open_shutters.push(ShutterLocation::BottomRight)
-        }
+        println!("{}\t{}\t{}", i, status, shutter_positions)
+    }
 
-        let shutter_positions = ShutterPositions::new(&open_shutters)?;
+    Ok(())
+}
 
-        let status = match encoding.check_usage(&shutter_positions) {
-            None => "",
-            Some(v) => match v {
-                MessageComponent::Character(character) => &format!("'{}'", character),
-                MessageComponent::End => "<END>",
-            },
-        };
+fn analyze_encoding(encoding: &Encoding) {
+    let analysis = analysis::analyze(encoding);
 
-        println!("{}\t{}\t{}", i, status, shutter_positions)
+    match analysis::minimum_distance(encoding) {
+        Some((distance, a, b)) => println!("Minimum distance: {} ({} and {})", distance, a, b),
+        None => println!("Minimum distance: -"),
+    }
+
+    println!();
+    println!("Symbols one shutter apart:");
+    for pair in analysis.close_pairs() {
+        println!("\t{}\t{}", pair.first(), pair.second());
+    }
+
+    println!();
+    println!("Symbols which turn into each other when mirrored:");
+    for pair in analysis.mirror_confusions() {
+        println!("\t{}\t{}", pair.first(), pair.second());
+    }
+
+    println!();
+    println!("Open shutters per symbol:");
+    for (symbol, count) in analysis.shutter_counts() {
+        println!("\t{}\t{}", symbol, count);
+    }
+}
+
+fn optimize_encoding(
+    encoding: &Encoding,
+    corpus_file_path: &str,
+    minimum_distance: u32,
+    output_file_path: Option<&String>,
+) -> Result<()> {
+    let corpus = fs::read_to_string(corpus_file_path)?;
+    let frequencies = optimizer::Frequencies::new(encoding, &corpus);
+    if frequencies.skipped_characters() > 0 {
+        warn!(
+            "skipped {} characters which the encoding can't encode",
+            frequencies.skipped_characters()
+        );
+    }
+
+    let optimized = optimizer::optimize(encoding, &frequencies, minimum_distance)?;
+    info!(
+        "expected shutter flips per symbol: {:.2} before, {:.2} after",
+        optimizer::expected_flips(encoding, &frequencies)?,
+        optimizer::expected_flips(&optimized, &frequencies)?
+    );
+
+    let table = adapters::encoding_to_toml(&optimized)?;
+    match output_file_path {
+        Some(output_file_path) => fs::write(output_file_path, table)?,
+        None => print!("{}", table),
     }
 
     Ok(())
@@ -214,42 +882,165 @@ where
 }
 
 #[derive(Clone)]
-struct HttpDeps<GSH, AMTQH, GCH> {
+struct HttpDeps<
+    GSH,
+    GMH,
+    GSMH,
+    CSMH,
+    RMFQH,
+    MMIQH,
+    CQH,
+    ATH,
+    PTH,
+    PCH,
+    RCH,
+    GRMH,
+    ARMH,
+    RRMH,
+    AMTQH,
+    GCH,
+    GEH,
+    RH,
+> {
     get_state_handler: GSH,
+    get_message_handler: GMH,
+    get_scheduled_messages_handler: GSMH,
+    cancel_scheduled_message_handler: CSMH,
+    remove_message_from_queue_handler: RMFQH,
+    move_message_in_queue_handler: MMIQH,
+    clear_queue_handler: CQH,
+    abort_transmission_handler: ATH,
+    preempt_transmission_handler: PTH,
+    pause_clacks_handler: PCH,
+    resume_clacks_handler: RCH,
+    get_recurring_messages_handler: GRMH,
+    add_recurring_message_handler: ARMH,
+    remove_recurring_message_handler: RRMH,
     add_message_to_queue_handler: AMTQH,
     get_config_handler: GCH,
+    get_encoding_handler: GEH,
+    render_handler: RH,
+    operators: Operators,
     metrics: adapters::Metrics,
     pubsub: adapters::PubSub,
 }
 
-impl<GSH, AMTQH, GCH> HttpDeps<GSH, AMTQH, GCH> {
-    pub fn new(
-        get_state_handler: GSH,
-        add_message_to_queue_handler: AMTQH,
-        get_config_handler: GCH,
-        metrics: adapters::Metrics,
-        pubsub: PubSub,
-    ) -> Self {
-        Self {
-            get_state_handler,
-            add_message_to_queue_handler,
-            get_config_handler,
-            metrics,
-            pubsub,
-        }
-    }
-}
-
-impl<GSH, AMTQH, GCH> http::Deps for HttpDeps<GSH, AMTQH, GCH>
+impl<
+    GSH,
+    GMH,
+    GSMH,
+    CSMH,
+    RMFQH,
+    MMIQH,
+    CQH,
+    ATH,
+    PTH,
+    PCH,
+    RCH,
+    GRMH,
+    ARMH,
+    RRMH,
+    AMTQH,
+    GCH,
+    GEH,
+    RH,
+> http::Deps
+    for HttpDeps<
+        GSH,
+        GMH,
+        GSMH,
+        CSMH,
+        RMFQH,
+        MMIQH,
+        CQH,
+        ATH,
+        PTH,
+        PCH,
+        RCH,
+        GRMH,
+        ARMH,
+        RRMH,
+        AMTQH,
+        GCH,
+        GEH,
+        RH,
+    >
 where
     GSH: app::GetStateHandler,
+    GMH: app::GetMessageHandler,
+    GSMH: app::GetScheduledMessagesHandler,
+    CSMH: app::CancelScheduledMessageHandler,
+    RMFQH: app::RemoveMessageFromQueueHandler,
+    MMIQH: app::MoveMessageInQueueHandler,
+    CQH: app::ClearQueueHandler,
+    ATH: app::AbortTransmissionHandler,
+    PTH: app::PreemptTransmissionHandler,
+    PCH: app::PauseClacksHandler,
+    RCH: app::ResumeClacksHandler,
+    GRMH: app::GetRecurringMessagesHandler,
+    ARMH: app::AddRecurringMessageHandler,
+    RRMH: app::RemoveRecurringMessageHandler,
     AMTQH: app::AddMessageToQueueHandler,
     GCH: app::GetConfigHandler,
+    GEH: app::GetEncodingHandler,
+    RH: app::RenderHandler,
 {
     fn get_state_handler(&self) -> &impl app::GetStateHandler {
         &self.get_state_handler
     }
 
+    fn get_message_handler(&self) -> &impl app::GetMessageHandler {
+        &self.get_message_handler
+    }
+
+    fn get_scheduled_messages_handler(&self) -> &impl app::GetScheduledMessagesHandler {
+        &self.get_scheduled_messages_handler
+    }
+
+    fn cancel_scheduled_message_handler(&self) -> &impl app::CancelScheduledMessageHandler {
+        &self.cancel_scheduled_message_handler
+    }
+
+    fn remove_message_from_queue_handler(&self) -> &impl app::RemoveMessageFromQueueHandler {
+        &self.remove_message_from_queue_handler
+    }
+
+    fn move_message_in_queue_handler(&self) -> &impl app::MoveMessageInQueueHandler {
+        &self.move_message_in_queue_handler
+    }
+
+    fn clear_queue_handler(&self) -> &impl app::ClearQueueHandler {
+        &self.clear_queue_handler
+    }
+
+    fn abort_transmission_handler(&self) -> &impl app::AbortTransmissionHandler {
+        &self.abort_transmission_handler
+    }
+
+    fn preempt_transmission_handler(&self) -> &impl app::PreemptTransmissionHandler {
+        &self.preempt_transmission_handler
+    }
+
+    fn pause_clacks_handler(&self) -> &impl app::PauseClacksHandler {
+        &self.pause_clacks_handler
+    }
+
+    fn resume_clacks_handler(&self) -> &impl app::ResumeClacksHandler {
+        &self.resume_clacks_handler
+    }
+
+    fn get_recurring_messages_handler(&self) -> &impl app::GetRecurringMessagesHandler {
+        &self.get_recurring_messages_handler
+    }
+
+    fn add_recurring_message_handler(&self) -> &impl app::AddRecurringMessageHandler {
+        &self.add_recurring_message_handler
+    }
+
+    fn remove_recurring_message_handler(&self) -> &impl app::RemoveRecurringMessageHandler {
+        &self.remove_recurring_message_handler
+    }
+
     fn add_message_to_queue_handler(&self) -> &impl app::AddMessageToQueueHandler {
         &self.add_message_to_queue_handler
     }
@@ -258,6 +1049,18 @@ where
         &self.get_config_handler
     }
 
+    fn get_encoding_handler(&self) -> &impl app::GetEncodingHandler {
+        &self.get_encoding_handler
+    }
+
+    fn render_handler(&self) -> &impl app::RenderHandler {
+        &self.render_handler
+    }
+
+    fn operators(&self) -> &Operators {
+        &self.operators
+    }
+
     fn metrics(&self) -> &Registry {
         self.metrics.registry()
     }
@@ -1,14 +1,35 @@
-use crate::domain::{Message, TimingConfig};
+use crate::domain::clock_mode::ClockMode;
+use crate::domain::injection::{InjectionStrategy, MessageToInject};
+use crate::domain::quiet_hours::QuietHours;
+use crate::domain::recurring::RecurringMessage;
+use crate::domain::time::TimeZone;
+use crate::domain::transliteration::Transliterator;
+use crate::domain::{Encoding, ShutterPositions, TimingConfig};
 use crate::errors::Result;
 use anyhow::anyhow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Config {
     address: String,
     queue_size: usize,
     environment: Environment,
-    messages_to_inject: Vec<Message>,
+    messages_to_inject: Vec<MessageToInject>,
     timing: TimingConfig,
+    encoding: Encoding,
+    transliterations: HashMap<String, String>,
+    queue_path: Option<PathBuf>,
+    operators: Operators,
+    park_position: ShutterPositions,
+    time_zone: TimeZone,
+    recurring_messages: Vec<RecurringMessage>,
+    recurring_messages_path: Option<PathBuf>,
+    quiet_hours: Option<QuietHours>,
+    injection_strategy: InjectionStrategy,
+    injection_seed: Option<u64>,
+    injection_state_path: Option<PathBuf>,
+    clock_mode: Option<ClockMode>,
 }
 
 impl Config {
@@ -16,8 +37,10 @@ impl Config {
         address: impl Into<String>,
         queue_size: usize,
         environment: Environment,
-        messages_to_inject: Vec<Message>,
+        messages_to_inject: Vec<MessageToInject>,
         timing: TimingConfig,
+        encoding: Encoding,
+        transliterations: HashMap<String, String>,
     ) -> Result<Self> {
         let address = address.into();
         if address.is_empty() {
@@ -26,15 +49,122 @@ impl Config {
         if queue_size == 0 {
             return Err(anyhow!("queue size must be positive").into());
         }
+        for message_to_inject in &messages_to_inject {
+            message_to_inject.template().check_encodable(&encoding)?;
+        }
         Ok(Self {
             address,
             queue_size,
             environment,
             messages_to_inject,
             timing,
+            encoding,
+            transliterations,
+            queue_path: None,
+            operators: Operators::default(),
+            park_position: ShutterPositions::new_with_all_closed(),
+            time_zone: TimeZone::Local,
+            recurring_messages: vec![],
+            recurring_messages_path: None,
+            quiet_hours: None,
+            injection_strategy: InjectionStrategy::default(),
+            injection_seed: None,
+            injection_state_path: None,
+            clock_mode: None,
+        })
+    }
+
+    /// Makes the queue persist across restarts by saving it to the given file.
+    pub fn with_queue_path(self, queue_path: impl Into<PathBuf>) -> Self {
+        Self {
+            queue_path: Some(queue_path.into()),
+            ..self
+        }
+    }
+
+    pub fn with_operators(self, operators: Operators) -> Self {
+        Self { operators, ..self }
+    }
+
+    /// Position in which the shutters are kept while the clacks is paused.
+    pub fn with_park_position(self, park_position: ShutterPositions) -> Self {
+        Self {
+            park_position,
+            ..self
+        }
+    }
+
+    /// Time zone in which schedules are evaluated, the local time zone by default.
+    pub fn with_time_zone(self, time_zone: TimeZone) -> Self {
+        Self { time_zone, ..self }
+    }
+
+    /// Rejects messages which can't be encoded now rather than every time they are due.
+    pub fn with_recurring_messages(
+        self,
+        recurring_messages: Vec<RecurringMessage>,
+    ) -> Result<Self> {
+        let transliterator = Transliterator::new(&self.encoding, self.transliterations.clone())?;
+        for recurring_message in &recurring_messages {
+            transliterator
+                .transliterate(recurring_message.message().text())
+                .and_then(|v| self.encoding.encode(v.message()))
+                .map_err(|err| {
+                    anyhow!("recurring message '{}': {}", recurring_message.name(), err)
+                })?;
+        }
+        Ok(Self {
+            recurring_messages,
+            ..self
         })
     }
 
+    /// Keeps recurring messages added using the API and makes it possible to catch up on runs
+    /// missed while the clacks wasn't running.
+    pub fn with_recurring_messages_path(self, recurring_messages_path: impl Into<PathBuf>) -> Self {
+        Self {
+            recurring_messages_path: Some(recurring_messages_path.into()),
+            ..self
+        }
+    }
+
+    pub fn with_quiet_hours(self, quiet_hours: QuietHours) -> Self {
+        Self {
+            quiet_hours: Some(quiet_hours),
+            ..self
+        }
+    }
+
+    pub fn with_injection_strategy(self, injection_strategy: InjectionStrategy) -> Self {
+        Self {
+            injection_strategy,
+            ..self
+        }
+    }
+
+    /// Makes the order of injected messages reproducible, e.g. for demos.
+    pub fn with_injection_seed(self, injection_seed: u64) -> Self {
+        Self {
+            injection_seed: Some(injection_seed),
+            ..self
+        }
+    }
+
+    /// Keeps the position of the rotation of injected messages across restarts.
+    pub fn with_injection_state_path(self, injection_state_path: impl Into<PathBuf>) -> Self {
+        Self {
+            injection_state_path: Some(injection_state_path.into()),
+            ..self
+        }
+    }
+
+    pub fn with_clock_mode(self, clock_mode: ClockMode) -> Self {
+        Self {
+            clock_mode: Some(clock_mode),
+            ..self
+        }
+    }
+
     pub fn address(&self) -> &str {
         &self.address
     }
@@ -47,13 +177,97 @@ impl Config {
         &self.environment
     }
 
-    pub fn messages_to_inject(&self) -> &[Message] {
+    pub fn messages_to_inject(&self) -> &[MessageToInject] {
         &self.messages_to_inject
     }
 
     pub fn timing(&self) -> &TimingConfig {
         &self.timing
     }
+
+    pub fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    pub fn transliterations(&self) -> &HashMap<String, String> {
+        &self.transliterations
+    }
+
+    pub fn queue_path(&self) -> Option<&Path> {
+        self.queue_path.as_deref()
+    }
+
+    pub fn operators(&self) -> &Operators {
+        &self.operators
+    }
+
+    pub fn park_position(&self) -> &ShutterPositions {
+        &self.park_position
+    }
+
+    pub fn time_zone(&self) -> &TimeZone {
+        &self.time_zone
+    }
+
+    pub fn recurring_messages(&self) -> &[RecurringMessage] {
+        &self.recurring_messages
+    }
+
+    pub fn recurring_messages_path(&self) -> Option<&Path> {
+        self.recurring_messages_path.as_deref()
+    }
+
+    pub fn quiet_hours(&self) -> Option<&QuietHours> {
+        self.quiet_hours.as_ref()
+    }
+
+    pub fn injection_strategy(&self) -> InjectionStrategy {
+        self.injection_strategy
+    }
+
+    pub fn injection_seed(&self) -> Option<u64> {
+        self.injection_seed
+    }
+
+    pub fn injection_state_path(&self) -> Option<&Path> {
+        self.injection_state_path.as_deref()
+    }
+
+    pub fn clock_mode(&self) -> Option<&ClockMode> {
+        self.clock_mode.as_ref()
+    }
+}
+
+/// Operators can manage the queue. Each of them authenticates using their own token. If no
+/// operators are configured then nobody can manage the queue.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Operators {
+    // token to name
+    tokens: HashMap<String, String>,
+}
+
+impl Operators {
+    /// Takes a mapping of operator names to their tokens.
+    pub fn new(operators: HashMap<String, String>) -> Result<Self> {
+        let mut tokens = HashMap::new();
+        for (name, token) in operators {
+            if name.is_empty() {
+                return Err(anyhow!("operator name can't be empty").into());
+            }
+            if token.is_empty() {
+                return Err(anyhow!("token of operator '{}' can't be empty", name).into());
+            }
+            if tokens.insert(token, name.clone()).is_some() {
+                return Err(anyhow!("token of operator '{}' is already in use", name).into());
+            }
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Returns the name of the operator using the token.
+    pub fn authenticate(&self, token: &str) -> Option<&str> {
+        self.tokens.get(token).map(|name| name.as_str())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -0,0 +1,173 @@
+use crate::domain::{Encoding, EncodingSymbol, MessageComponent};
+
+/// Describes how easy it is to misread the symbols of an encoding. Only symbols which can appear
+/// in the same place of a message are compared, that is characters from the same plane, shifts
+/// and the message end.
+pub struct EncodingAnalysis {
+    close_pairs: Vec<SymbolPair>,
+    mirror_confusions: Vec<SymbolPair>,
+    shutter_counts: Vec<(EncodingSymbol, usize)>,
+}
+
+impl EncodingAnalysis {
+    /// Returns pairs of symbols which differ by a single shutter.
+    pub fn close_pairs(&self) -> &[SymbolPair] {
+        &self.close_pairs
+    }
+
+    /// Returns pairs of symbols which turn into each other when seen from behind the tower.
+    pub fn mirror_confusions(&self) -> &[SymbolPair] {
+        &self.mirror_confusions
+    }
+
+    /// Returns the number of open shutters for each symbol.
+    pub fn shutter_counts(&self) -> &[(EncodingSymbol, usize)] {
+        &self.shutter_counts
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolPair {
+    first: EncodingSymbol,
+    second: EncodingSymbol,
+}
+
+impl SymbolPair {
+    pub fn first(&self) -> &EncodingSymbol {
+        &self.first
+    }
+
+    pub fn second(&self) -> &EncodingSymbol {
+        &self.second
+    }
+
+    pub fn distance(&self) -> u32 {
+        self.first
+            .shutter_positions()
+            .distance(self.second.shutter_positions())
+    }
+}
+
+pub fn analyze(encoding: &Encoding) -> EncodingAnalysis {
+    let symbols = encoding.symbols();
+    let pairs = confusable_pairs(&symbols);
+
+    let close_pairs = pairs
+        .iter()
+        .filter(|pair| pair.distance() == 1)
+        .cloned()
+        .collect();
+
+    let mirror_confusions = pairs
+        .iter()
+        .filter(|pair| {
+            &pair.first.shutter_positions().mirrored() == pair.second.shutter_positions()
+        })
+        .cloned()
+        .collect();
+
+    let shutter_counts = symbols
+        .into_iter()
+        .map(|symbol| {
+            let count = symbol.shutter_positions().open_shutters_count();
+            (symbol, count)
+        })
+        .collect();
+
+    EncodingAnalysis {
+        close_pairs,
+        mirror_confusions,
+        shutter_counts,
+    }
+}
+
+/// Returns the smallest distance between two symbols which can be confused with each other or
+/// nothing if the encoding has a single symbol.
+pub fn minimum_distance(encoding: &Encoding) -> Option<(u32, EncodingSymbol, EncodingSymbol)> {
+    confusable_pairs(&encoding.symbols())
+        .into_iter()
+        .min_by_key(|pair| pair.distance())
+        .map(|pair| (pair.distance(), pair.first, pair.second))
+}
+
+fn confusable_pairs(symbols: &[EncodingSymbol]) -> Vec<SymbolPair> {
+    let mut pairs = vec![];
+    for (i, first) in symbols.iter().enumerate() {
+        for second in &symbols[i + 1..] {
+            if can_be_confused(first, second) {
+                pairs.push(SymbolPair {
+                    first: first.clone(),
+                    second: second.clone(),
+                });
+            }
+        }
+    }
+    pairs
+}
+
+fn can_be_confused(a: &EncodingSymbol, b: &EncodingSymbol) -> bool {
+    match (a.element(), b.element()) {
+        (MessageComponent::Character(_), MessageComponent::Character(_)) => a.plane() == b.plane(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{EncodingPlane, ShutterLocation, ShutterPositions};
+    use crate::errors::Result;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reports_confusable_symbols() -> Result<()> {
+        let planes = vec![EncodingPlane::new(
+            "default",
+            None,
+            HashMap::from([
+                (
+                    "A".to_string(),
+                    ShutterPositions::new(&[ShutterLocation::TopLeft])?,
+                ),
+                (
+                    "B".to_string(),
+                    ShutterPositions::new(&[ShutterLocation::TopRight])?,
+                ),
+                (
+                    "C".to_string(),
+                    ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::TopRight])?,
+                ),
+            ]),
+        )?];
+        let encoding = Encoding::new(planes, ShutterPositions::new_with_all_open())?;
+
+        let analysis = analyze(&encoding);
+
+        let describe = |pairs: &[SymbolPair]| -> Vec<String> {
+            pairs
+                .iter()
+                .map(|pair| format!("{} {}", pair.first(), pair.second()))
+                .collect()
+        };
+        assert_eq!(
+            vec!["'A' (default) 'C' (default)", "'B' (default) 'C' (default)"],
+            describe(analysis.close_pairs())
+        );
+        assert_eq!(
+            vec!["'A' (default) 'B' (default)"],
+            describe(analysis.mirror_confusions())
+        );
+        assert_eq!(
+            vec![1, 1, 2, 6],
+            analysis
+                .shutter_counts()
+                .iter()
+                .map(|(_, count)| *count)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(encoding.check_minimum_distance(1).is_ok());
+        assert!(encoding.check_minimum_distance(2).is_err());
+        Ok(())
+    }
+}
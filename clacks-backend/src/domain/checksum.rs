@@ -0,0 +1,74 @@
+use crate::domain::ShutterPositions;
+
+/// Calculates the checksum of the given shutter positions. The checksum is the sum of the 6-bit
+/// values of the shutter positions modulo the number of usable codes, mapped onto the usable
+/// codes in ascending order. All codes are usable except for all shutters closed and the
+/// reserved codes, e.g. the message end, so that the checksum can't be mistaken for them. Any
+/// single misread symbol changes the checksum unless it flips five or more shutters, swapped
+/// symbols don't change it.
+pub fn checksum<'a>(
+    positions: impl IntoIterator<Item = &'a ShutterPositions>,
+    reserved: &[&ShutterPositions],
+) -> ShutterPositions {
+    let usable: Vec<u8> = (1..64)
+        .filter(|bits| !reserved.iter().any(|v| v.bits() == *bits))
+        .collect();
+    let sum: usize = positions.into_iter().map(|v| usize::from(v.bits())).sum();
+    ShutterPositions::new_from_bits(usable[sum % usable.len()]).unwrap()
+}
+
+pub fn verify_checksum<'a>(
+    positions: impl IntoIterator<Item = &'a ShutterPositions>,
+    reserved: &[&ShutterPositions],
+    expected_checksum: &ShutterPositions,
+) -> bool {
+    &checksum(positions, reserved) == expected_checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+
+    #[test]
+    fn detects_single_misread_symbols() -> Result<()> {
+        let positions = vec![
+            ShutterPositions::new_from_bits(63)?,
+            ShutterPositions::new_from_bits(1)?,
+            ShutterPositions::new_from_bits(12)?,
+        ];
+        let message_end = ShutterPositions::new_from_bits(30)?;
+        let reserved = [&message_end];
+        let checksum = checksum(&positions, &reserved);
+        assert!(verify_checksum(&positions, &reserved, &checksum));
+
+        for i in 0..positions.len() {
+            for bits in 1..64 {
+                let mut misread = positions.clone();
+                misread[i] = ShutterPositions::new_from_bits(bits)?;
+                let flipped = (positions[i].bits() ^ bits).count_ones();
+                if misread != positions && flipped < 5 {
+                    assert!(!verify_checksum(&misread, &reserved, &checksum));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn never_uses_reserved_codes() -> Result<()> {
+        let message_end = ShutterPositions::new_from_bits(30)?;
+        for bits in 1..64 {
+            for other in 1..64 {
+                let positions = [
+                    ShutterPositions::new_from_bits(bits)?,
+                    ShutterPositions::new_from_bits(other)?,
+                ];
+                let checksum = checksum(&positions, &[&message_end]);
+                assert_ne!(message_end, checksum);
+                assert!(!checksum.all_closed());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use crate::domain::templates::{MessageTemplate, TemplateValues};
+use crate::domain::time::{DateTime, Duration, NaiveDateTime, TimeZone};
+use crate::domain::windows::TimeWindows;
+use crate::domain::{EncodedMessage, Encoding, TimingConfig};
+use crate::errors::Result;
+use anyhow::anyhow;
+use std::sync::{Arc, Mutex};
+
+const CLOCK_TEMPLATE: &str = "{time:%H %M}";
+
+/// A quarter hour is only shown if the clacks becomes idle shortly after it, otherwise the
+/// time would be outdated by the time it is transmitted.
+const QUARTER_HOUR_GRACE_PERIOD_IN_MINUTES: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClockSchedule {
+    /// The time is shown whenever this much time passed since it was last shown.
+    Interval(Duration),
+    /// The time is shown at :00, :15, :30 and :45.
+    QuarterHour,
+}
+
+/// Shows the local time while nobody submits messages. The time is transmitted using its own
+/// timing, e.g. to make it faster to read than regular messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockMode {
+    schedule: ClockSchedule,
+    timing: Option<TimingConfig>,
+    windows: Option<TimeWindows>,
+}
+
+impl ClockMode {
+    pub fn new(schedule: ClockSchedule) -> Result<Self> {
+        if let ClockSchedule::Interval(interval) = &schedule
+            && interval.as_seconds() <= 0.0
+        {
+            return Err(anyhow!("clock interval must be positive").into());
+        }
+        Ok(Self {
+            schedule,
+            timing: None,
+            windows: None,
+        })
+    }
+
+    /// The timing of regular messages is used by default.
+    pub fn with_timing(self, timing: TimingConfig) -> Self {
+        Self {
+            timing: Some(timing),
+            ..self
+        }
+    }
+
+    /// The time is only shown during the windows, all the time by default.
+    pub fn with_windows(self, windows: TimeWindows) -> Self {
+        Self {
+            windows: Some(windows),
+            ..self
+        }
+    }
+
+    pub fn schedule(&self) -> &ClockSchedule {
+        &self.schedule
+    }
+
+    pub fn timing(&self) -> Option<&TimingConfig> {
+        self.timing.as_ref()
+    }
+
+    pub fn windows(&self) -> Option<&TimeWindows> {
+        self.windows.as_ref()
+    }
+}
+
+#[derive(Clone)]
+pub struct ClockMessages {
+    clock_mode: ClockMode,
+    template: MessageTemplate,
+    encoding: Encoding,
+    time_zone: TimeZone,
+    last_shown_at: Arc<Mutex<Option<DateTime>>>,
+}
+
+impl ClockMessages {
+    /// The time is shown as "HH MM" in the time zone.
+    pub fn new(clock_mode: ClockMode, encoding: Encoding, time_zone: TimeZone) -> Result<Self> {
+        let template = MessageTemplate::new(CLOCK_TEMPLATE)?;
+        template
+            .check_encodable(&encoding)
+            .map_err(|err| anyhow!("clock mode can't be used with this encoding: {}", err))?;
+        Ok(Self {
+            clock_mode,
+            template,
+            encoding,
+            time_zone,
+            last_shown_at: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn timing(&self) -> Option<&TimingConfig> {
+        self.clock_mode.timing()
+    }
+
+    /// Returns the time to show if it is due, the time counts as shown once it is returned.
+    pub fn get(&self, now: &DateTime) -> Result<Option<EncodedMessage>> {
+        if let Some(windows) = &self.clock_mode.windows
+            && !windows.contains(now)
+        {
+            return Ok(None);
+        }
+
+        let mut last_shown_at = self.last_shown_at.lock().unwrap();
+        let local = now.in_timezone(self.time_zone.clone());
+        let due = match &self.clock_mode.schedule {
+            ClockSchedule::Interval(interval) => last_shown_at
+                .as_ref()
+                .is_none_or(|shown_at| &(now - shown_at) >= interval),
+            ClockSchedule::QuarterHour => {
+                let naive = local.naive_local();
+                let quarter_hour = NaiveDateTime::new_from_date_and_time(
+                    &naive.date(),
+                    naive.hour(),
+                    naive.minute() - naive.minute() % 15,
+                )?
+                .resolve_in_timezone(&self.time_zone);
+                &quarter_hour <= now
+                    && now - &quarter_hour
+                        < Duration::new_from_minutes(QUARTER_HOUR_GRACE_PERIOD_IN_MINUTES)
+                    && last_shown_at
+                        .as_ref()
+                        .is_none_or(|shown_at| shown_at < &quarter_hour)
+            }
+        };
+        if !due {
+            return Ok(None);
+        }
+
+        let message = self.template.render(&TemplateValues::new(local, 0))?;
+        let encoded_message = self.encoding.encode(&message)?;
+        *last_shown_at = Some(now.clone());
+        Ok(Some(encoded_message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::windows::{TimeOfDay, TimeWindow, Weekday};
+    use crate::domain::{EncodedMessagePart, Message};
+
+    fn at(hour: u32, min: u32, sec: u32) -> DateTime {
+        // 2024-06-03 is a Monday
+        NaiveDateTime::new_from_ymdhms(2024, 6, 3, hour, min, sec).attach_timezone(TimeZone::UTC)
+    }
+
+    fn shown(
+        clock_messages: &ClockMessages,
+        now: &DateTime,
+    ) -> Result<Option<Vec<EncodedMessagePart>>> {
+        Ok(clock_messages.get(now)?.map(|v| v.parts().to_vec()))
+    }
+
+    fn time(text: &str) -> Result<Option<Vec<EncodedMessagePart>>> {
+        let encoded_message = Encoding::default().encode(&Message::new(text)?)?;
+        Ok(Some(encoded_message.parts().to_vec()))
+    }
+
+    #[test]
+    fn shows_time_on_the_quarter_hour() -> Result<()> {
+        let clock_mode = ClockMode::new(ClockSchedule::QuarterHour)?;
+        let clock_messages = ClockMessages::new(
+            clock_mode,
+            Encoding::default(),
+            TimeZone::new_from_name("Europe/London")?,
+        )?;
+
+        assert_eq!(shown(&clock_messages, &at(8, 14, 59))?, None);
+        assert_eq!(shown(&clock_messages, &at(8, 15, 10))?, time("09 15")?);
+        assert_eq!(shown(&clock_messages, &at(8, 16, 0))?, None);
+        // the clacks was busy until it was too late to show 09:30
+        assert_eq!(shown(&clock_messages, &at(8, 36, 0))?, None);
+        assert_eq!(shown(&clock_messages, &at(8, 45, 0))?, time("09 45")?);
+        Ok(())
+    }
+
+    #[test]
+    fn shows_time_at_interval_during_windows() -> Result<()> {
+        let windows = TimeWindows::new(
+            TimeZone::UTC,
+            vec![TimeWindow::new(
+                &[Weekday::Monday],
+                TimeOfDay::new(8, 0)?,
+                TimeOfDay::new(9, 0)?,
+            )?],
+        );
+        let clock_mode = ClockMode::new(ClockSchedule::Interval(Duration::new_from_minutes(10)))?
+            .with_windows(windows);
+        let clock_messages = ClockMessages::new(clock_mode, Encoding::default(), TimeZone::UTC)?;
+
+        assert_eq!(shown(&clock_messages, &at(7, 59, 0))?, None);
+        assert_eq!(shown(&clock_messages, &at(8, 0, 0))?, time("08 00")?);
+        assert_eq!(shown(&clock_messages, &at(8, 9, 59))?, None);
+        assert_eq!(shown(&clock_messages, &at(8, 10, 0))?, time("08 10")?);
+        assert_eq!(shown(&clock_messages, &at(9, 0, 0))?, None);
+        Ok(())
+    }
+}
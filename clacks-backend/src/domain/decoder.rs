@@ -0,0 +1,309 @@
+use crate::domain::checksum::verify_checksum;
+use crate::domain::{Encoding, Message, ShiftScope, ShutterPositions};
+
+/// Turns a stream of shutter positions back into messages. Showing the same shutter positions
+/// several times in a row is treated as showing them once unless all shutters are closed in
+/// between, which is how the clacks pauses between characters.
+pub struct Decoder {
+    encoding: Encoding,
+    locked_plane: usize,
+    run_plane: Option<usize>,
+    character_plane: Option<usize>,
+    previous: Option<ShutterPositions>,
+    text: String,
+
+    // if the encoding uses checksums each symbol is held back until the next one arrives as the
+    // symbol right before the message end is the checksum
+    pending: Option<ShutterPositions>,
+    received: Vec<ShutterPositions>,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            locked_plane: 0,
+            run_plane: None,
+            character_plane: None,
+            previous: None,
+            text: String::new(),
+            pending: None,
+            received: vec![],
+        }
+    }
+
+    pub fn push(&mut self, positions: &ShutterPositions) -> Option<DecoderEvent> {
+        if positions.all_closed() {
+            self.previous = None;
+            return None;
+        }
+
+        if self.previous.as_ref() == Some(positions) {
+            return None;
+        }
+        self.previous = Some(positions.clone());
+
+        if self.encoding.cancel() == Some(positions) {
+            self.pending = None;
+            self.received.clear();
+            let text = std::mem::take(&mut self.text);
+            self.end_message();
+            return Some(DecoderEvent::Cancelled(text));
+        }
+
+        if !self.encoding.checksum() {
+            if positions == self.encoding.message_end() {
+                return Some(self.end_message());
+            }
+            return self.decode(positions);
+        }
+
+        if positions == self.encoding.message_end() {
+            let checksum_matches = match self.pending.take() {
+                Some(checksum) => verify_checksum(
+                    &self.received,
+                    &self.encoding.checksum_reserved(),
+                    &checksum,
+                ),
+                None => false,
+            };
+            self.received.clear();
+            return Some(match self.end_message() {
+                DecoderEvent::Message(message) if !checksum_matches => {
+                    DecoderEvent::InvalidChecksum(message.text().to_string())
+                }
+                event => event,
+            });
+        }
+
+        let pending = self.pending.replace(positions.clone())?;
+        self.received.push(pending.clone());
+        self.decode(&pending)
+    }
+
+    fn end_message(&mut self) -> DecoderEvent {
+        let text = std::mem::take(&mut self.text);
+        self.locked_plane = 0;
+        self.run_plane = None;
+        self.character_plane = None;
+        match Message::new(text.clone()) {
+            Ok(message) => DecoderEvent::Message(message),
+            Err(_) => DecoderEvent::InvalidMessage(text),
+        }
+    }
+
+    fn decode(&mut self, positions: &ShutterPositions) -> Option<DecoderEvent> {
+        let planes = self.encoding.planes();
+        if let Some(index) = planes
+            .iter()
+            .position(|plane| plane.shift().map(|v| v.shutter_positions()) == Some(positions))
+        {
+            match planes[index].shift_scope() {
+                Some(ShiftScope::Locking) | None => {
+                    self.locked_plane = index;
+                    self.run_plane = None;
+                }
+                Some(ShiftScope::Character) => {
+                    self.character_plane = Some(index);
+                    self.run_plane = None;
+                }
+                Some(ShiftScope::Run) => self.run_plane = Some(index),
+            }
+            return None;
+        }
+
+        let plane = match (self.character_plane.take(), self.run_plane) {
+            (Some(plane), _) => plane,
+            (None, Some(plane)) if planes[plane].character(positions).is_some() => plane,
+            (None, _) => {
+                self.run_plane = None;
+                self.locked_plane
+            }
+        };
+
+        match planes[plane].character(positions) {
+            Some(character) => {
+                self.text.push_str(character);
+                None
+            }
+            None => Some(DecoderEvent::UnrecognisedPositions(positions.clone())),
+        }
+    }
+
+    /// Returns the text decoded since the last message end, if any.
+    pub fn pending_text(&self) -> Option<&str> {
+        if self.text.is_empty() {
+            None
+        } else {
+            Some(&self.text)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecoderEvent {
+    Message(Message),
+    InvalidMessage(String),
+    InvalidChecksum(String),
+    /// The transmission was aborted, contains the text received until then.
+    Cancelled(String),
+    UnrecognisedPositions(ShutterPositions),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ShutterLocation;
+    use crate::errors::Result;
+
+    #[test]
+    fn decodes_encoded_messages() -> Result<()> {
+        let encoding = Encoding::default();
+        let mut decoder = Decoder::new(encoding.clone());
+
+        let mut events = vec![];
+        for text in ["hello world", "gnu 42"] {
+            let encoded_message = encoding.encode(&Message::new(text)?)?;
+            for part in encoded_message.parts() {
+                events.extend(decoder.push(part.shutter_positions()));
+                events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+            }
+        }
+
+        assert_eq!(
+            vec![
+                DecoderEvent::Message(Message::new("HELLO WORLD")?),
+                DecoderEvent::Message(Message::new("GNU 42")?),
+            ],
+            events
+        );
+        assert_eq!(None, decoder.pending_text());
+        Ok(())
+    }
+
+    #[test]
+    fn verifies_checksums() -> Result<()> {
+        let encoding = Encoding::default().with_checksum();
+        let mut decoder = Decoder::new(encoding.clone());
+
+        let encoded_message = encoding.encode(&Message::new("clacks")?)?;
+        let mut events = vec![];
+        for part in encoded_message.parts() {
+            events.extend(decoder.push(part.shutter_positions()));
+            events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+        }
+
+        let mut misread: Vec<ShutterPositions> = encoded_message
+            .parts()
+            .iter()
+            .map(|part| part.shutter_positions().clone())
+            .collect();
+        misread[1] = encoding.encode(&Message::new("x")?)?.parts()[0]
+            .shutter_positions()
+            .clone();
+        for positions in &misread {
+            events.extend(decoder.push(positions));
+            events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+        }
+
+        assert_eq!(
+            vec![
+                DecoderEvent::Message(Message::new("CLACKS")?),
+                DecoderEvent::InvalidChecksum("CXACKS".to_string()),
+            ],
+            events
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_messages_whose_checksum_would_be_the_message_end() -> Result<()> {
+        let encoding = Encoding::default().with_checksum();
+        let mut decoder = Decoder::new(encoding.clone());
+
+        let texts = ["CK", "EP", "FT", "GW", "MV", "QR", "XX"];
+        let mut events = vec![];
+        for text in texts {
+            let encoded_message = encoding.encode(&Message::new(text)?)?;
+            for part in encoded_message.parts() {
+                events.extend(decoder.push(part.shutter_positions()));
+                events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+            }
+        }
+
+        let expected = texts
+            .iter()
+            .map(|text| Ok(DecoderEvent::Message(Message::new(*text)?)))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(expected, events);
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_messages_whose_checksum_would_be_the_cancel_symbol() -> Result<()> {
+        let encoding = Encoding::default().with_checksum();
+        assert!(encoding.cancel().is_some());
+        let mut decoder = Decoder::new(encoding.clone());
+
+        let letters: Vec<char> = ('A'..='Z').collect();
+        for a in &letters {
+            for b in &letters {
+                let message = Message::new(format!("{}{}", a, b))?;
+                let mut events = vec![];
+                for part in encoding.encode(&message)?.parts() {
+                    events.extend(decoder.push(part.shutter_positions()));
+                    events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+                }
+                assert_eq!(vec![DecoderEvent::Message(message)], events);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reports_unrecognised_positions() -> Result<()> {
+        let mut decoder = Decoder::new(Encoding::default());
+
+        let positions = ShutterPositions::new(&[
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+        ])?;
+        assert_eq!(
+            Some(DecoderEvent::UnrecognisedPositions(positions.clone())),
+            decoder.push(&positions)
+        );
+        assert_eq!(
+            None,
+            decoder.push(&ShutterPositions::new(&[ShutterLocation::TopLeft])?)
+        );
+        assert_eq!(Some("1"), decoder.pending_text());
+        Ok(())
+    }
+
+    #[test]
+    fn discards_cancelled_messages() -> Result<()> {
+        let encoding = Encoding::default();
+        let mut decoder = Decoder::new(encoding.clone());
+
+        let mut events = vec![];
+        let parts = encoding.encode(&Message::new("gnu")?)?.parts().to_vec();
+        for part in parts[..2]
+            .iter()
+            .chain(encoding.encode_cancel().iter())
+            .chain(parts.iter())
+        {
+            events.extend(decoder.push(part.shutter_positions()));
+            events.extend(decoder.push(&ShutterPositions::new_with_all_closed()));
+        }
+
+        assert_eq!(
+            vec![
+                DecoderEvent::Cancelled("GN".to_string()),
+                DecoderEvent::Message(Message::new("GNU")?),
+            ],
+            events
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,462 @@
+use crate::domain::templates::{MessageTemplate, TemplateValues};
+use crate::domain::time::{DateTime, Duration, TimeZone};
+use crate::domain::{EncodedMessage, Encoding};
+use crate::errors::Result;
+use anyhow::anyhow;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Message which is transmitted when nobody submitted anything for a while.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageToInject {
+    template: MessageTemplate,
+    weight: u32,
+    min_interval: Option<Duration>,
+}
+
+impl MessageToInject {
+    pub fn new(template: MessageTemplate) -> Self {
+        Self {
+            template,
+            weight: 1,
+            min_interval: None,
+        }
+    }
+
+    /// Only used by [`InjectionStrategy::Weighted`], messages have a weight of 1 by default.
+    pub fn with_weight(self, weight: u32) -> Result<Self> {
+        if weight == 0 {
+            return Err(anyhow!(
+                "weight of injected message '{}' must be positive",
+                self.template.text()
+            )
+            .into());
+        }
+        Ok(Self { weight, ..self })
+    }
+
+    /// The message is skipped until at least this much time passed since it was last injected.
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        Self {
+            min_interval: Some(min_interval),
+            ..self
+        }
+    }
+
+    pub fn template(&self) -> &MessageTemplate {
+        &self.template
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    pub fn min_interval(&self) -> Option<&Duration> {
+        self.min_interval.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InjectionStrategy {
+    /// Messages are injected in the configured order.
+    RoundRobin,
+    /// Messages are injected in random order but no message repeats until all of them were
+    /// injected.
+    ShuffleBag,
+    /// Messages are picked at random, messages with a higher weight are picked more often.
+    #[default]
+    Weighted,
+}
+
+/// Position of the rotation. Messages are identified by the text of their template so that the
+/// snapshot remains useful if the configured messages change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InjectionSnapshot {
+    last: Option<String>,
+    bag: Vec<String>,
+    draws: u64,
+    last_injected_at: HashMap<String, DateTime>,
+}
+
+impl InjectionSnapshot {
+    pub fn new(
+        last: Option<String>,
+        bag: Vec<String>,
+        draws: u64,
+        last_injected_at: HashMap<String, DateTime>,
+    ) -> Self {
+        Self {
+            last,
+            bag,
+            draws,
+            last_injected_at,
+        }
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// Messages which remain to be injected before the shuffle bag is refilled.
+    pub fn bag(&self) -> &[String] {
+        &self.bag
+    }
+
+    /// Number of random draws made so far, with a seed this reproduces the random sequence.
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    pub fn last_injected_at(&self) -> &HashMap<String, DateTime> {
+        &self.last_injected_at
+    }
+}
+
+/// Keeps the position of the rotation across restarts.
+pub trait InjectionStorage: Send + Sync {
+    fn load(&self) -> Result<InjectionSnapshot>;
+    fn save(&self, snapshot: &InjectionSnapshot) -> Result<()>;
+}
+
+#[derive(Clone, Default)]
+struct Rotation {
+    last: Option<usize>,
+    bag: Vec<usize>,
+    draws: u64,
+    last_injected_at: HashMap<usize, DateTime>,
+}
+
+#[derive(Clone)]
+pub struct MessagesToInject {
+    messages: Arc<Vec<MessageToInject>>,
+    encoding: Encoding,
+    time_zone: TimeZone,
+    strategy: InjectionStrategy,
+    seed: Option<u64>,
+    rotation: Arc<Mutex<Rotation>>,
+    storage: Option<Arc<dyn InjectionStorage>>,
+}
+
+impl MessagesToInject {
+    /// Templates are rendered in the time zone right before they are transmitted.
+    pub fn new(
+        messages: Vec<MessageToInject>,
+        encoding: Encoding,
+        time_zone: TimeZone,
+    ) -> Result<Self> {
+        let mut texts = HashSet::new();
+        for message in &messages {
+            if !texts.insert(message.template.text()) {
+                return Err(anyhow!(
+                    "message to inject '{}' is configured twice",
+                    message.template.text()
+                )
+                .into());
+            }
+        }
+
+        Ok(Self {
+            messages: Arc::new(messages),
+            encoding,
+            time_zone,
+            strategy: InjectionStrategy::default(),
+            seed: None,
+            rotation: Arc::new(Mutex::new(Rotation::default())),
+            storage: None,
+        })
+    }
+
+    /// Restores the position of the rotation and saves it after every injected message.
+    pub fn new_with_storage(
+        messages: Vec<MessageToInject>,
+        encoding: Encoding,
+        time_zone: TimeZone,
+        storage: impl InjectionStorage + 'static,
+    ) -> Result<Self> {
+        let snapshot = storage.load()?;
+        let messages_to_inject = Self::new(messages, encoding, time_zone)?;
+        *messages_to_inject.rotation.lock().unwrap() = messages_to_inject.restore(snapshot);
+        Ok(Self {
+            storage: Some(Arc::new(storage)),
+            ..messages_to_inject
+        })
+    }
+
+    pub fn with_strategy(self, strategy: InjectionStrategy) -> Self {
+        Self { strategy, ..self }
+    }
+
+    /// Makes the random choices reproducible.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Returns nothing if there are no messages or if all of them were injected too recently.
+    pub fn get(&self, queue_len: usize, now: &DateTime) -> Result<Option<EncodedMessage>> {
+        let mut rotation = self.rotation.lock().unwrap();
+        let mut new_rotation = rotation.clone();
+        let Some(index) = self.choose(&mut new_rotation, now) else {
+            return Ok(None);
+        };
+
+        let values = TemplateValues::new(now.in_timezone(self.time_zone.clone()), queue_len);
+        let message = self.messages[index].template.render(&values)?;
+        let encoded_message = self.encoding.encode(&message)?;
+
+        new_rotation.last = Some(index);
+        new_rotation.last_injected_at.insert(index, now.clone());
+        if let Some(storage) = &self.storage {
+            storage.save(&self.snapshot(&new_rotation))?;
+        }
+        *rotation = new_rotation;
+        Ok(Some(encoded_message))
+    }
+
+    fn choose(&self, rotation: &mut Rotation, now: &DateTime) -> Option<usize> {
+        let eligible: Vec<bool> = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| match &message.min_interval {
+                Some(min_interval) => rotation
+                    .last_injected_at
+                    .get(&index)
+                    .is_none_or(|injected_at| &(now - injected_at) >= min_interval),
+                None => true,
+            })
+            .collect();
+        let eligible = |index: &usize| eligible[*index];
+
+        let len = self.messages.len();
+        match self.strategy {
+            InjectionStrategy::RoundRobin => {
+                let start = rotation.last.map_or(0, |v| v + 1);
+                (0..len).map(|offset| (start + offset) % len).find(eligible)
+            }
+            InjectionStrategy::ShuffleBag => {
+                if rotation.bag.is_empty() {
+                    let mut bag: Vec<usize> = (0..len).collect();
+                    bag.shuffle(&mut self.rng(rotation));
+                    // don't repeat the last message of the previous bag right away
+                    if bag.len() > 1 && bag.first() == rotation.last.as_ref() {
+                        bag.swap(0, 1);
+                    }
+                    rotation.bag = bag;
+                }
+                let position = rotation.bag.iter().position(eligible)?;
+                Some(rotation.bag.remove(position))
+            }
+            InjectionStrategy::Weighted => {
+                let candidates: Vec<usize> = (0..len).filter(eligible).collect();
+                let mut rng = self.rng(rotation);
+                candidates
+                    .choose_weighted(&mut rng, |index| self.messages[*index].weight)
+                    .ok()
+                    .copied()
+            }
+        }
+    }
+
+    /// Each draw gets its own generator so that the sequence can be continued after a restart.
+    /// Both the seed and the number of draws go into the key as adding them would make
+    /// neighbouring seeds produce shifted copies of the same sequence.
+    fn rng(&self, rotation: &mut Rotation) -> StdRng {
+        rotation.draws += 1;
+        match self.seed {
+            Some(seed) => {
+                let mut key = <StdRng as SeedableRng>::Seed::default();
+                key[..8].copy_from_slice(&seed.to_le_bytes());
+                key[8..16].copy_from_slice(&rotation.draws.to_le_bytes());
+                StdRng::from_seed(key)
+            }
+            None => StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    fn snapshot(&self, rotation: &Rotation) -> InjectionSnapshot {
+        let text = |index: &usize| self.messages[*index].template.text().to_string();
+        InjectionSnapshot {
+            last: rotation.last.as_ref().map(text),
+            bag: rotation.bag.iter().map(text).collect(),
+            draws: rotation.draws,
+            last_injected_at: rotation
+                .last_injected_at
+                .iter()
+                .map(|(index, injected_at)| (text(index), injected_at.clone()))
+                .collect(),
+        }
+    }
+
+    /// Messages which are no longer configured are dropped from the snapshot.
+    fn restore(&self, snapshot: InjectionSnapshot) -> Rotation {
+        let index = |text: &String| {
+            self.messages
+                .iter()
+                .position(|message| message.template.text() == text)
+        };
+        Rotation {
+            last: snapshot.last.as_ref().and_then(index),
+            bag: snapshot.bag.iter().filter_map(index).collect(),
+            draws: snapshot.draws,
+            last_injected_at: snapshot
+                .last_injected_at
+                .iter()
+                .filter_map(|(text, injected_at)| Some((index(text)?, injected_at.clone())))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Message;
+
+    #[test]
+    fn rotates_messages_according_to_the_strategy() -> Result<()> {
+        let messages = vec![
+            MessageToInject::new(MessageTemplate::new("A")?),
+            MessageToInject::new(MessageTemplate::new("B")?),
+            MessageToInject::new(MessageTemplate::new("C")?),
+        ];
+
+        let round_robin = messages_to_inject(messages.clone())?
+            .with_strategy(InjectionStrategy::RoundRobin)
+            .with_seed(1);
+        assert_eq!("ABCABC", inject(&round_robin, 6)?);
+
+        let shuffle_bag = messages_to_inject(messages.clone())?
+            .with_strategy(InjectionStrategy::ShuffleBag)
+            .with_seed(1);
+        let injected = inject(&shuffle_bag, 9)?;
+        for bag in injected.as_bytes().chunks(3) {
+            let mut bag = bag.to_vec();
+            bag.sort();
+            assert_eq!(b"ABC".to_vec(), bag, "injected: {}", injected);
+        }
+        assert!(!injected.as_bytes().windows(2).any(|v| v[0] == v[1]));
+
+        let weighted = messages_to_inject(vec![
+            MessageToInject::new(MessageTemplate::new("A")?).with_weight(1000)?,
+            MessageToInject::new(MessageTemplate::new("B")?),
+        ])?
+        .with_seed(1);
+        let injected = inject(&weighted, 20)?;
+        assert!(injected.matches('A').count() > 15, "injected: {}", injected);
+
+        // the seed makes the sequence reproducible
+        let shuffled = |seed| -> Result<String> {
+            let messages_to_inject = messages_to_inject(messages.clone())?
+                .with_strategy(InjectionStrategy::ShuffleBag)
+                .with_seed(seed);
+            inject(&messages_to_inject, 12)
+        };
+        assert_eq!(shuffled(7)?, shuffled(7)?);
+        Ok(())
+    }
+
+    #[test]
+    fn different_seeds_give_unrelated_sequences() -> Result<()> {
+        let weighted = |seed| -> Result<String> {
+            let messages_to_inject = messages_to_inject(vec![
+                MessageToInject::new(MessageTemplate::new("A")?),
+                MessageToInject::new(MessageTemplate::new("B")?),
+                MessageToInject::new(MessageTemplate::new("C")?),
+            ])?
+            .with_seed(seed);
+            inject(&messages_to_inject, 20)
+        };
+        for seed in 0..10 {
+            let injected = weighted(seed)?;
+            let next_injected = weighted(seed + 1)?;
+            assert_ne!(injected[1..], next_injected[..19]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn skips_messages_injected_too_recently() -> Result<()> {
+        let messages_to_inject = messages_to_inject(vec![
+            MessageToInject::new(MessageTemplate::new("A")?)
+                .with_min_interval(Duration::new_from_hours(1)),
+            MessageToInject::new(MessageTemplate::new("B")?),
+        ])?
+        .with_strategy(InjectionStrategy::RoundRobin);
+        assert_eq!("ABBB", inject(&messages_to_inject, 4)?);
+
+        let messages_to_inject = messages_to_inject.with_strategy(InjectionStrategy::ShuffleBag);
+        let mut rotation = messages_to_inject.rotation.lock().unwrap().clone();
+        rotation.bag = vec![0];
+        *messages_to_inject.rotation.lock().unwrap() = rotation;
+        assert!(messages_to_inject.get(0, &DateTime::now())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn restores_rotation_from_storage() -> Result<()> {
+        let storage = MemoryStorage::default();
+        let messages = vec![
+            MessageToInject::new(MessageTemplate::new("A")?),
+            MessageToInject::new(MessageTemplate::new("B")?),
+            MessageToInject::new(MessageTemplate::new("C")?),
+        ];
+        let messages_to_inject = MessagesToInject::new_with_storage(
+            messages.clone(),
+            Encoding::default(),
+            TimeZone::UTC,
+            storage.clone(),
+        )?
+        .with_strategy(InjectionStrategy::RoundRobin);
+        assert_eq!("AB", inject(&messages_to_inject, 2)?);
+
+        let messages_to_inject = MessagesToInject::new_with_storage(
+            messages,
+            Encoding::default(),
+            TimeZone::UTC,
+            storage,
+        )?
+        .with_strategy(InjectionStrategy::RoundRobin);
+        assert_eq!("CA", inject(&messages_to_inject, 2)?);
+        Ok(())
+    }
+
+    fn messages_to_inject(messages: Vec<MessageToInject>) -> Result<MessagesToInject> {
+        MessagesToInject::new(messages, Encoding::default(), TimeZone::UTC)
+    }
+
+    fn inject(messages_to_inject: &MessagesToInject, times: usize) -> Result<String> {
+        let encoding = Encoding::default();
+        let mut injected = String::new();
+        for _ in 0..times {
+            let message = messages_to_inject.get(0, &DateTime::now())?.unwrap();
+            for text in ["A", "B", "C"] {
+                if message.parts() == encoding.encode(&Message::new(text)?)?.parts() {
+                    injected.push_str(text);
+                }
+            }
+        }
+        Ok(injected)
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStorage {
+        snapshot: Arc<Mutex<InjectionSnapshot>>,
+    }
+
+    impl InjectionStorage for MemoryStorage {
+        fn load(&self) -> Result<InjectionSnapshot> {
+            Ok(self.snapshot.lock().unwrap().clone())
+        }
+
+        fn save(&self, snapshot: &InjectionSnapshot) -> Result<()> {
+            *self.snapshot.lock().unwrap() = snapshot.clone();
+            Ok(())
+        }
+    }
+}
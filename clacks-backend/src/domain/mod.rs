@@ -1,16 +1,32 @@
+pub mod analysis;
+pub mod checksum;
+pub mod clock_mode;
+pub mod decoder;
+pub mod injection;
+pub mod optimizer;
+pub mod presets;
+pub mod quiet_hours;
+pub mod recurring;
 pub mod servos;
+pub mod templates;
 pub mod time;
+pub mod transliteration;
+pub mod windows;
 
 use crate::app::ClacksUpdateResult;
-use crate::domain::time::Duration;
+use crate::domain::clock_mode::ClockMessages;
+use crate::domain::injection::MessagesToInject;
+use crate::domain::presets::EncodingPreset;
+use crate::domain::quiet_hours::{QuietHours, QuietPeriod};
+use crate::domain::time::{Clock as _, Duration};
 use crate::errors::Error;
 use crate::errors::Result;
 use anyhow::anyhow;
-use rand::seq::IndexedRandom;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::slice::Iter;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub const MAX_MESSAGE_LEN_BYTES: usize = 20;
@@ -49,6 +65,18 @@ impl ShutterLocation {
         }
     }
 
+    /// Returns the location at which the shutter is seen from behind the tower.
+    pub fn mirrored(&self) -> ShutterLocation {
+        match &self {
+            ShutterLocation::TopLeft => ShutterLocation::TopRight,
+            ShutterLocation::TopRight => ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft => ShutterLocation::MiddleRight,
+            ShutterLocation::MiddleRight => ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft => ShutterLocation::BottomRight,
+            ShutterLocation::BottomRight => ShutterLocation::BottomLeft,
+        }
+    }
+
     pub fn iter() -> Iter<'static, ShutterLocation> {
         static SHUTTER_LOCATIONS: [ShutterLocation; 6] = [
             ShutterLocation::TopLeft,
@@ -68,7 +96,7 @@ impl Display for ShutterLocation {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ShutterPositions {
     open_shutters: HashSet<ShutterLocation>,
 }
@@ -85,6 +113,22 @@ impl ShutterPositions {
         })
     }
 
+    /// Creates shutter positions from a 6-bit value where the shutters are taken in the order of
+    /// [ShutterLocation::iter] starting with the least significant bit, e.g. 1 is the top left
+    /// shutter and 2 is the top right shutter.
+    pub fn new_from_bits(bits: u8) -> Result<Self> {
+        if bits >= 1 << 6 {
+            return Err(anyhow!("shutter positions must fit in 6 bits").into());
+        }
+        Ok(Self {
+            open_shutters: ShutterLocation::iter()
+                .enumerate()
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .map(|(_, location)| location.clone())
+                .collect(),
+        })
+    }
+
     pub fn new_with_all_open() -> Self {
         Self {
             open_shutters: ShutterLocation::iter().cloned().collect(),
@@ -101,6 +145,31 @@ impl ShutterPositions {
         self.open_shutters.is_empty()
     }
 
+    /// Returns the 6-bit value of the shutter positions, see [ShutterPositions::new_from_bits].
+    pub fn bits(&self) -> u8 {
+        ShutterLocation::iter()
+            .enumerate()
+            .filter(|(_, location)| self.open_shutters.contains(location))
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+    }
+
+    /// Returns the shutter positions as seen from behind the tower.
+    pub fn mirrored(&self) -> Self {
+        Self {
+            open_shutters: self.open_shutters.iter().map(|v| v.mirrored()).collect(),
+        }
+    }
+
+    /// Returns the Hamming distance between the shutter positions, that is the number of
+    /// shutters which have to move to get from one to the other.
+    pub fn distance(&self, other: &ShutterPositions) -> u32 {
+        (self.bits() ^ other.bits()).count_ones()
+    }
+
+    pub fn open_shutters_count(&self) -> usize {
+        self.open_shutters.len()
+    }
+
     pub fn open_shutters(&self) -> std::collections::hash_set::Iter<'_, ShutterLocation> {
         self.open_shutters.iter()
     }
@@ -137,24 +206,38 @@ impl Display for ShutterPositions {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Message {
     text: String,
 }
 
 impl Message {
-    pub(crate) fn new(text: impl Into<String>) -> Result<Self> {
+    pub fn new(text: impl Into<String>) -> Result<Self> {
         let text = text.into();
 
         if text.is_empty() {
-            return Err(anyhow!("empty message").into());
+            return Err(Error::InvalidMessage("empty message".into()));
         }
         if text.len() > MAX_MESSAGE_LEN_BYTES {
             // yes, it's unclear if that's what we want
-            return Err(anyhow!("message too long").into());
+            return Err(Error::InvalidMessage("message too long".into()));
         }
         Ok(Self { text })
     }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+fn characters_text(parts: &[EncodedMessagePart]) -> String {
+    parts
+        .iter()
+        .filter_map(|part| match &part.element {
+            MessageComponent::Character(token) => Some(token.as_str()),
+            _ => None,
+        })
+        .collect()
 }
 
 #[derive(Clone)]
@@ -174,6 +257,27 @@ impl EncodedMessage {
                         .into());
                     }
                 }
+                MessageComponent::Shift => {
+                    if i == parts.len() - 1 {
+                        return Err(anyhow!(
+                            "shifts can't appear as the last element of an encoded message"
+                        )
+                        .into());
+                    }
+                }
+                MessageComponent::Checksum => {
+                    if i + 2 != parts.len() {
+                        return Err(anyhow!(
+                            "checksum can only appear right before the message end indicator"
+                        )
+                        .into());
+                    }
+                }
+                MessageComponent::Cancel => {
+                    return Err(
+                        anyhow!("cancel indicator can't appear in an encoded message").into(),
+                    );
+                }
                 MessageComponent::End => {
                     if i != parts.len() - 1 {
                         return Err(anyhow!(
@@ -190,18 +294,52 @@ impl EncodedMessage {
     pub fn parts(&self) -> &[EncodedMessagePart] {
         &self.parts
     }
+
+    /// Text of the encoded characters, tokens are uppercase.
+    pub fn text(&self) -> String {
+        characters_text(&self.parts)
+    }
+
+    /// Returns the shutter positions shown while transmitting the message together with how long
+    /// they are shown for, including the pause after the message.
+    pub fn timeline(&self, timing: &TimingConfig) -> Vec<(ShutterPositions, Duration)> {
+        let mut timeline = vec![];
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                timeline.push((
+                    ShutterPositions::new_with_all_closed(),
+                    timing.pause_between_characters_for.clone(),
+                ));
+            }
+            timeline.push((
+                part.shutter_positions.clone(),
+                timing.show_character_for.clone(),
+            ));
+        }
+        timeline.push((
+            ShutterPositions::new_with_all_closed(),
+            timing.pause_between_messages_for.clone(),
+        ));
+        timeline
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EncodedMessagePart {
     element: MessageComponent,
+    plane: Option<String>,
     shutter_positions: ShutterPositions,
 }
 
 impl EncodedMessagePart {
-    pub fn new(element: MessageComponent, encoding: ShutterPositions) -> Self {
+    pub fn new(
+        element: MessageComponent,
+        plane: Option<String>,
+        encoding: ShutterPositions,
+    ) -> Self {
         Self {
             element,
+            plane,
             shutter_positions: encoding,
         }
     }
@@ -210,6 +348,11 @@ impl EncodedMessagePart {
         &self.element
     }
 
+    /// Plane the character belongs to or, in case of shifts, the plane which is shifted to.
+    pub fn plane(&self) -> Option<&str> {
+        self.plane.as_deref()
+    }
+
     pub fn shutter_positions(&self) -> &ShutterPositions {
         &self.shutter_positions
     }
@@ -217,31 +360,57 @@ impl EncodedMessagePart {
 
 impl EncodedMessagePart {}
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MessageComponent {
     Character(String),
+    Shift,
+    Checksum,
     End,
+    /// Shown instead of the rest of a message when its transmission is aborted.
+    Cancel,
 }
 
 pub struct CurrentMessage {
+    id: Option<MessageId>,
     before: Vec<EncodedMessagePart>,
     current: Option<EncodedMessagePart>,
     after: Vec<EncodedMessagePart>,
+    interrupted: Option<InterruptedMessage>,
 }
 
 impl CurrentMessage {
     pub fn new(
+        id: Option<MessageId>,
         before: Vec<EncodedMessagePart>,
         current: Option<EncodedMessagePart>,
         after: Vec<EncodedMessagePart>,
     ) -> Self {
         Self {
+            id,
             before,
             current,
             after,
+            interrupted: None,
+        }
+    }
+
+    pub fn with_interrupted(self, interrupted: Option<InterruptedMessage>) -> Self {
+        Self {
+            interrupted,
+            ..self
         }
     }
 
+    /// Injected messages don't have an id as they never pass through the queue.
+    pub fn id(&self) -> Option<&MessageId> {
+        self.id.as_ref()
+    }
+
+    /// Message which will be continued once this one has been transmitted.
+    pub fn interrupted(&self) -> Option<&InterruptedMessage> {
+        self.interrupted.as_ref()
+    }
+
     pub fn before(&self) -> &[EncodedMessagePart] {
         &self.before
     }
@@ -255,472 +424,1028 @@ impl CurrentMessage {
     }
 }
 
-#[derive(Clone)]
+pub const DEFAULT_PLANE_NAME: &str = "default";
+pub const CUSTOM_ENCODING_NAME: &str = "custom";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Encoding {
-    characters: HashMap<String, ShutterPositions>,
+    name: String,
+    planes: Vec<EncodingPlane>,
     message_end: ShutterPositions,
+    cancel: Option<ShutterPositions>,
+    checksum: bool,
 }
 
 impl Default for Encoding {
     fn default() -> Self {
-        let mut characters: HashMap<String, ShutterPositions> = HashMap::new();
-        characters.insert(
-            "A".into(),
-            ShutterPositions::new(&[ShutterLocation::MiddleLeft, ShutterLocation::BottomRight])
-                .unwrap(),
-        );
-        characters.insert(
-            "B".into(),
-            ShutterPositions::new(&[ShutterLocation::MiddleRight, ShutterLocation::BottomLeft])
-                .unwrap(),
-        );
-        characters.insert(
-            "C".into(),
-            ShutterPositions::new(&[ShutterLocation::MiddleLeft, ShutterLocation::MiddleRight])
-                .unwrap(),
-        );
-        characters.insert(
-            "D".into(),
-            ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::BottomLeft])
-                .unwrap(),
-        );
-        characters.insert(
-            "E".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "F".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "G".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "H".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "I".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "J".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "K".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "L".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "M".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "N".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "O".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::MiddleRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "P".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "Q".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "R".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "S".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "T".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "U".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "V".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "W".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "X".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomLeft,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "Y".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            "Z".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleLeft,
-                ShutterLocation::MiddleRight,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        );
-        characters.insert(
-            " ".into(),
-            ShutterPositions::new(&[ShutterLocation::BottomLeft]).unwrap(),
-        );
-
-        // custom
-        characters.insert(
-            "1".into(),
-            ShutterPositions::new(&[ShutterLocation::TopLeft]).unwrap(),
-        );
-        characters.insert(
-            "2".into(),
-            ShutterPositions::new(&[ShutterLocation::TopRight]).unwrap(),
-        );
-        characters.insert(
-            "3".into(),
-            ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::TopLeft]).unwrap(),
-        );
-        characters.insert(
-            "4".into(),
-            ShutterPositions::new(&[ShutterLocation::MiddleLeft]).unwrap(),
-        );
-        characters.insert(
-            "5".into(),
-            ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::MiddleLeft])
-                .unwrap(),
-        );
-        characters.insert(
-            "6".into(),
-            ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::MiddleLeft])
-                .unwrap(),
-        );
-        characters.insert(
-            "7".into(),
-            ShutterPositions::new(&[ShutterLocation::MiddleRight]).unwrap(),
-        );
-        characters.insert(
-            "8".into(),
-            ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::MiddleRight])
-                .unwrap(),
-        );
-        characters.insert(
-            "9".into(),
-            ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::MiddleRight])
-                .unwrap(),
-        );
-        characters.insert(
-            "0".into(),
-            ShutterPositions::new(&[
-                ShutterLocation::MiddleRight,
-                ShutterLocation::TopLeft,
-                ShutterLocation::MiddleLeft,
-            ])
-            .unwrap(),
-        );
-
-        Self::new(
-            characters,
-            ShutterPositions::new(&[
-                ShutterLocation::TopLeft,
-                ShutterLocation::TopRight,
-                ShutterLocation::BottomLeft,
-                ShutterLocation::BottomRight,
-            ])
-            .unwrap(),
-        )
-        .unwrap()
+        EncodingPreset::Clacks.encoding()
     }
 }
 
 impl Encoding {
-    pub fn new(
-        characters: HashMap<String, ShutterPositions>,
-        message_end: ShutterPositions,
-    ) -> Result<Self> {
-        let mut positions: HashSet<ShutterPositions> = HashSet::new();
-
-        if characters.is_empty() {
-            return Err(anyhow!(
-                "if the characters mapping is empty then we can't encode anything"
-            )
-            .into());
+    pub fn new(planes: Vec<EncodingPlane>, message_end: ShutterPositions) -> Result<Self> {
+        if planes.is_empty() {
+            return Err(anyhow!("if there are no planes then we can't encode anything").into());
         }
 
-        let characters: HashMap<String, ShutterPositions> = characters
-            .iter()
-            .map(|(k, v)| (k.to_uppercase(), v.clone()))
-            .collect();
-
-        for (character, position) in &characters {
-            if position.all_closed() {
-                return Err(anyhow!("character encoding can't be all shutters closed").into());
+        let mut names: HashSet<&str> = HashSet::new();
+        let mut shifts: HashSet<&ShutterPositions> = HashSet::new();
+        for plane in &planes {
+            if !names.insert(&plane.name) {
+                return Err(anyhow!("duplicate plane '{}'", plane.name).into());
             }
 
-            if positions.contains(position) {
-                return Err(
-                    anyhow!("duplicate shutter position for character '{}'", character).into(),
-                );
+            match &plane.shift {
+                Some(shift) => {
+                    if !shifts.insert(&shift.shutter_positions) {
+                        return Err(anyhow!(
+                            "duplicate shutter position for shift to plane '{}'",
+                            plane.name
+                        )
+                        .into());
+                    }
+                }
+                None => {
+                    if planes.len() > 1 {
+                        return Err(anyhow!(
+                            "plane '{}' needs a shift as there is more than one plane",
+                            plane.name
+                        )
+                        .into());
+                    }
+                }
             }
-            positions.insert(position.clone());
         }
 
         if message_end.all_closed() {
             return Err(anyhow!("message end encoding can't be all shutters closed").into());
         }
 
-        if positions.contains(&message_end) {
+        if shifts.contains(&message_end) {
             return Err(anyhow!("duplicate shutter position for message end").into());
         }
 
+        for plane in &planes {
+            for (character, position) in &plane.characters {
+                if shifts.contains(position) || position == &message_end {
+                    return Err(anyhow!(
+                        "duplicate shutter position for character '{}' in plane '{}'",
+                        character,
+                        plane.name
+                    )
+                    .into());
+                }
+            }
+        }
+
         Ok(Self {
-            characters,
+            name: CUSTOM_ENCODING_NAME.to_string(),
+            planes,
             message_end,
+            cancel: None,
+            checksum: false,
+        })
+    }
+
+    /// Rejects encodings with symbols that can be confused with each other if they are less than
+    /// the minimum distance apart, see [analysis::minimum_distance].
+    pub fn check_minimum_distance(&self, minimum_distance: u32) -> Result<()> {
+        if let Some((distance, a, b)) = analysis::minimum_distance(self)
+            && distance < minimum_distance
+        {
+            return Err(anyhow!(
+                "symbols {} and {} are {} shutters apart but the minimum distance is {}",
+                a,
+                b,
+                distance,
+                minimum_distance
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Adds a symbol which is shown when the transmission of a message is aborted. It can't be
+    /// used by any other symbol.
+    pub fn with_cancel(self, cancel: ShutterPositions) -> Result<Self> {
+        if cancel.all_closed() {
+            return Err(anyhow!("cancel encoding can't be all shutters closed").into());
+        }
+        if let Some(symbol) = self.check_usage(&cancel).first() {
+            return Err(
+                anyhow!("shutter position for cancel is already used by {}", symbol).into(),
+            );
+        }
+        Ok(Self {
+            cancel: Some(cancel),
+            ..self
         })
     }
 
+    pub fn cancel(&self) -> Option<&ShutterPositions> {
+        self.cancel.as_ref()
+    }
+
+    pub fn encode_cancel(&self) -> Option<EncodedMessagePart> {
+        self.cancel
+            .as_ref()
+            .map(|cancel| EncodedMessagePart::new(MessageComponent::Cancel, None, cancel.clone()))
+    }
+
+    /// Encodes the characters which are left of an interrupted message as a message of its own.
+    /// Decoders forget the interrupted message, so the resumption shifts to its planes again and
+    /// its checksum only covers the resumed characters. Returns `None` if no characters are left.
+    pub fn encode_resumption(
+        &self,
+        remaining: &[EncodedMessagePart],
+    ) -> Result<Option<EncodedMessage>> {
+        let text = characters_text(remaining);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        self.encode(&Message::new(text)?).map(Some)
+    }
+
+    /// Makes the encoding append a checksum before the end of each message, see
+    /// [checksum::checksum].
+    pub fn with_checksum(self) -> Self {
+        Self {
+            checksum: true,
+            ..self
+        }
+    }
+
+    pub fn checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Shutter positions which the checksum never uses so that it can't be mistaken for them.
+    pub fn checksum_reserved(&self) -> Vec<&ShutterPositions> {
+        [Some(&self.message_end), self.cancel.as_ref()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a fingerprint of the symbols which changes whenever the encoding table changes so
+    /// that printed charts can be matched with the encoding used by the tower.
+    pub fn version(&self) -> String {
+        let mut description = String::new();
+        for symbol in self.symbols() {
+            description.push_str(&format!("{}={};", symbol, symbol.shutter_positions.bits()));
+        }
+        for plane in &self.planes {
+            if let Some(scope) = plane.shift_scope() {
+                description.push_str(&format!("<SCOPE {}>={:?};", plane.name, scope));
+            }
+        }
+        if self.checksum {
+            description.push_str("<CHECKSUM>;");
+        }
+
+        // FNV-1a, the standard library hashers aren't guaranteed to be stable between releases
+        let hash = description.bytes().fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+        });
+        format!("{:08x}", hash)
+    }
+
     pub fn encode(&self, message: &Message) -> Result<EncodedMessage> {
         let mut parts = vec![];
+        let mut locked_plane = &self.planes[0];
+        let mut run_plane: Option<&EncodingPlane> = None;
 
-        for c in message.text.chars() {
-            let uppercase_string = String::from(c).to_uppercase();
-            match self.characters.get(&uppercase_string) {
-                Some(positions) => {
-                    parts.push(EncodedMessagePart::new(
-                        MessageComponent::Character(uppercase_string),
-                        positions.clone(),
-                    ));
+        let characters: Vec<(char, String)> = message
+            .text
+            .chars()
+            .map(|c| (c, String::from(c).to_uppercase()))
+            .collect();
+        let mut rest = characters.as_slice();
+        while !rest.is_empty() {
+            let (token, len) = self
+                .next_token(rest)
+                .ok_or(Error::CannotEncodeCharacter(rest[0].0))?;
+            rest = &rest[len..];
+
+            if let Some(plane) = run_plane {
+                if plane.characters.contains_key(&token) {
+                    parts.push(plane.encode_character(token));
+                    continue;
                 }
-                None => {
-                    return Err(Error::CannotEncodeCharacter(c));
+                run_plane = None;
+
+                // characters after a run are read in the locked plane unless they look like a
+                // character from the run, then the run has to be ended explicitly
+                if let Some(positions) = locked_plane.characters.get(&token)
+                    && plane.character(positions).is_some()
+                {
+                    parts.push(locked_plane.encode_shift()?);
                 }
             }
+
+            if locked_plane.characters.contains_key(&token) {
+                parts.push(locked_plane.encode_character(token));
+                continue;
+            }
+
+            let plane = self
+                .planes
+                .iter()
+                .find(|plane| plane.characters.contains_key(&token))
+                .ok_or_else(|| anyhow!("no plane contains '{}'", token))?;
+            parts.push(plane.encode_shift()?);
+            match plane.shift_scope() {
+                Some(ShiftScope::Locking) | None => locked_plane = plane,
+                Some(ShiftScope::Character) => {}
+                Some(ShiftScope::Run) => run_plane = Some(plane),
+            }
+            parts.push(plane.encode_character(token));
+        }
+        if self.checksum {
+            let checksum = checksum::checksum(
+                parts.iter().map(|v| &v.shutter_positions),
+                &self.checksum_reserved(),
+            );
+            parts.push(EncodedMessagePart::new(
+                MessageComponent::Checksum,
+                None,
+                checksum,
+            ));
         }
         parts.push(EncodedMessagePart::new(
             MessageComponent::End,
+            None,
             self.message_end.clone(),
         ));
 
         EncodedMessage::new(parts)
     }
 
+    /// Finds the longest character or phrase at the start of the text and returns it together
+    /// with the number of consumed characters.
+    fn next_token(&self, characters: &[(char, String)]) -> Option<(String, usize)> {
+        (1..=characters.len()).rev().find_map(|len| {
+            let token: String = characters[..len]
+                .iter()
+                .map(|(_, uppercase_string)| uppercase_string.as_str())
+                .collect();
+            self.planes
+                .iter()
+                .any(|plane| plane.characters.contains_key(&token))
+                .then_some((token, len))
+        })
+    }
+
     pub fn supported_characters(&self) -> Vec<String> {
-        self.characters.keys().cloned().collect()
+        self.tokens()
+            .into_iter()
+            .filter(|token| token.chars().count() == 1)
+            .collect()
+    }
+
+    /// Returns tokens longer than a single character which are encoded as a single symbol.
+    pub fn phrases(&self) -> Vec<String> {
+        self.tokens()
+            .into_iter()
+            .filter(|token| token.chars().count() > 1)
+            .collect()
+    }
+
+    fn tokens(&self) -> Vec<String> {
+        let mut tokens: Vec<String> = vec![];
+        for plane in &self.planes {
+            for token in plane.characters.keys() {
+                if !tokens.contains(token) {
+                    tokens.push(token.clone());
+                }
+            }
+        }
+        tokens
+    }
+
+    pub fn planes(&self) -> &[EncodingPlane] {
+        &self.planes
+    }
+
+    pub fn message_end(&self) -> &ShutterPositions {
+        &self.message_end
+    }
+
+    /// Returns all symbols which are encoded using the given shutter positions. The same shutter
+    /// positions can be reused by characters in different planes.
+    pub fn check_usage(&self, positions: &ShutterPositions) -> Vec<EncodingSymbol> {
+        self.symbols()
+            .into_iter()
+            .filter(|symbol| &symbol.shutter_positions == positions)
+            .collect()
     }
 
-    pub fn check_usage(&self, positions: &ShutterPositions) -> Option<MessageComponent> {
-        for (character, character_positions) in &self.characters {
-            if character_positions == positions {
-                return Some(MessageComponent::Character(character.clone()));
+    /// Returns all symbols of the encoding. Each plane contributes its shift followed by its
+    /// characters ordered by their shutter positions, the message end and cancel come last.
+    pub fn symbols(&self) -> Vec<EncodingSymbol> {
+        let mut symbols = vec![];
+
+        for plane in &self.planes {
+            if let Some(shift) = &plane.shift {
+                symbols.push(EncodingSymbol::new(
+                    MessageComponent::Shift,
+                    Some(plane.name.clone()),
+                    shift.shutter_positions.clone(),
+                ));
+            }
+
+            let mut characters: Vec<(&String, &ShutterPositions)> =
+                plane.characters.iter().collect();
+            characters.sort_by_key(|(character, positions)| (positions.bits(), *character));
+            for (character, positions) in characters {
+                symbols.push(EncodingSymbol::new(
+                    MessageComponent::Character(character.clone()),
+                    Some(plane.name.clone()),
+                    positions.clone(),
+                ));
             }
         }
 
-        if positions == &self.message_end {
-            return Some(MessageComponent::End);
+        symbols.push(EncodingSymbol::new(
+            MessageComponent::End,
+            None,
+            self.message_end.clone(),
+        ));
+
+        if let Some(cancel) = &self.cancel {
+            symbols.push(EncodingSymbol::new(
+                MessageComponent::Cancel,
+                None,
+                cancel.clone(),
+            ));
         }
 
-        None
+        symbols
     }
 }
 
-#[derive(Clone)]
-pub struct Queue {
-    messages: Arc<Mutex<Vec<EncodedMessage>>>,
-    max_messages: usize,
+/// Set of characters which can be used after shifting to it, similarly to the letters and figures
+/// shifts in the Baudot code. All messages start in the first plane of an encoding. Apart from
+/// single characters a plane can contain whole phrases, longer phrases are preferred when
+/// encoding. A phrase can take two symbols by placing it in a plane with a character scoped
+/// shift.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingPlane {
+    name: String,
+    shift: Option<Shift>,
+    characters: HashMap<String, ShutterPositions>,
 }
 
-impl Queue {
-    pub fn new(max_messages: usize) -> Result<Self> {
-        if max_messages == 0 {
-            return Err(anyhow!("max_messages in the queue can't be set to zero").into());
+impl EncodingPlane {
+    pub fn new(
+        name: impl Into<String>,
+        shift: Option<Shift>,
+        characters: HashMap<String, ShutterPositions>,
+    ) -> Result<Self> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(anyhow!("plane name can't be empty").into());
+        }
+
+        if characters.is_empty() {
+            return Err(anyhow!(
+                "if the characters mapping is empty then we can't encode anything"
+            )
+            .into());
+        }
+
+        let mut uppercase_characters: HashMap<String, ShutterPositions> = HashMap::new();
+        for (character, position) in characters {
+            if character.is_empty() {
+                return Err(anyhow!("characters can't be empty").into());
+            }
+
+            let uppercase_character = character.to_uppercase();
+            if uppercase_characters.contains_key(&uppercase_character) {
+                return Err(anyhow!("duplicate character '{}'", uppercase_character).into());
+            }
+            uppercase_characters.insert(uppercase_character, position);
         }
+        let characters = uppercase_characters;
+
+        let mut positions: HashSet<ShutterPositions> = HashSet::new();
+        for (character, position) in &characters {
+            if position.all_closed() {
+                return Err(anyhow!("character encoding can't be all shutters closed").into());
+            }
+
+            if positions.contains(position) {
+                return Err(
+                    anyhow!("duplicate shutter position for character '{}'", character).into(),
+                );
+            }
+            positions.insert(position.clone());
+        }
+
         Ok(Self {
-            messages: Arc::new(Mutex::new(vec![])),
-            max_messages,
+            name,
+            shift,
+            characters,
         })
     }
 
-    pub fn add_message(&self, message: EncodedMessage) -> Result<()> {
-        let mut messages = self.messages.lock().unwrap();
-        if messages.len() >= self.max_messages {
-            return Err(Error::QueueIsFull);
-        }
-        messages.push(message);
-        Ok(())
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shift(&self) -> Option<&Shift> {
+        self.shift.as_ref()
+    }
+
+    pub fn shift_scope(&self) -> Option<&ShiftScope> {
+        self.shift.as_ref().map(|v| &v.scope)
+    }
+
+    pub fn characters(&self) -> &HashMap<String, ShutterPositions> {
+        &self.characters
+    }
+
+    pub fn character(&self, positions: &ShutterPositions) -> Option<&str> {
+        self.characters
+            .iter()
+            .find(|(_, character_positions)| *character_positions == positions)
+            .map(|(character, _)| character.as_str())
     }
 
-    pub fn pop_message(&self) -> Option<EncodedMessage> {
-        let mut messages = self.messages.lock().unwrap();
-        messages.pop()
+    fn encode_character(&self, character: String) -> EncodedMessagePart {
+        let positions = self.characters[&character].clone();
+        EncodedMessagePart::new(
+            MessageComponent::Character(character),
+            Some(self.name.clone()),
+            positions,
+        )
     }
 
-    pub fn get_messages(&self) -> Result<Vec<EncodedMessage>> {
-        let messages = self.messages.lock().unwrap();
-        Ok(messages.clone())
+    fn encode_shift(&self) -> Result<EncodedMessagePart> {
+        let shift = self
+            .shift
+            .as_ref()
+            .ok_or_else(|| anyhow!("plane '{}' can't be shifted to", self.name))?;
+        Ok(EncodedMessagePart::new(
+            MessageComponent::Shift,
+            Some(self.name.clone()),
+            shift.shutter_positions.clone(),
+        ))
     }
 }
 
-#[derive(Clone)]
-pub struct MessagesToInject {
-    messages: Arc<Vec<EncodedMessage>>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shift {
+    shutter_positions: ShutterPositions,
+    scope: ShiftScope,
 }
 
-impl MessagesToInject {
-    pub fn new(messages: Vec<EncodedMessage>) -> Self {
-        Self {
-            messages: Arc::new(messages),
+impl Shift {
+    pub fn new(shutter_positions: ShutterPositions, scope: ShiftScope) -> Result<Self> {
+        if shutter_positions.all_closed() {
+            return Err(anyhow!("shift encoding can't be all shutters closed").into());
         }
+        Ok(Self {
+            shutter_positions,
+            scope,
+        })
+    }
+
+    pub fn shutter_positions(&self) -> &ShutterPositions {
+        &self.shutter_positions
     }
 
-    pub fn get(&self) -> Option<&EncodedMessage> {
-        self.messages.choose(&mut rand::rng())
+    pub fn scope(&self) -> &ShiftScope {
+        &self.scope
     }
 }
 
+/// Describes for how long the characters are read in a plane after shifting to it.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct TimingConfig {
-    show_character_for: Duration,
-    pause_between_characters_for: Duration,
-    pause_between_messages_for: Duration,
-    inject_message_if_no_next_message_after_pausing_between_messages_for: Duration,
+pub enum ShiftScope {
+    /// Until the next shift, like the figures shift in the Baudot code.
+    Locking,
+    /// Only for the next character, like the capital sign in Braille.
+    Character,
+    /// Until a character which isn't in the plane, like the number sign in Braille. That
+    /// character and the ones after it are read in the previous plane again.
+    Run,
 }
 
-impl TimingConfig {
-    pub fn new(
-        show_character_for: Duration,
+#[derive(Debug, Clone)]
+pub struct EncodingSymbol {
+    element: MessageComponent,
+    plane: Option<String>,
+    shutter_positions: ShutterPositions,
+}
+
+impl EncodingSymbol {
+    pub fn new(
+        element: MessageComponent,
+        plane: Option<String>,
+        shutter_positions: ShutterPositions,
+    ) -> Self {
+        Self {
+            element,
+            plane,
+            shutter_positions,
+        }
+    }
+
+    pub fn element(&self) -> &MessageComponent {
+        &self.element
+    }
+
+    pub fn plane(&self) -> Option<&str> {
+        self.plane.as_deref()
+    }
+
+    pub fn shutter_positions(&self) -> &ShutterPositions {
+        &self.shutter_positions
+    }
+}
+
+impl Display for EncodingSymbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.element, &self.plane) {
+            (MessageComponent::Character(character), Some(plane)) => {
+                write!(f, "'{}' ({})", character, plane)
+            }
+            (MessageComponent::Character(character), None) => write!(f, "'{}'", character),
+            (MessageComponent::Shift, Some(plane)) => write!(f, "<SHIFT {}>", plane),
+            (MessageComponent::Shift, None) => write!(f, "<SHIFT>"),
+            (MessageComponent::Checksum, _) => write!(f, "<CHECKSUM>"),
+            (MessageComponent::End, _) => write!(f, "<END>"),
+            (MessageComponent::Cancel, _) => write!(f, "<CANCEL>"),
+        }
+    }
+}
+
+/// Messages of a higher priority are transmitted first, messages of the same priority are
+/// transmitted in the order in which they were submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Operator,
+    Scheduled,
+    Public,
+    Injected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageId(String);
+
+impl MessageId {
+    pub fn new(id: impl Into<String>) -> Result<Self> {
+        let id = id.into();
+        if id.is_empty() {
+            return Err(anyhow!("message id can't be empty").into());
+        }
+        Ok(Self(id))
+    }
+
+    pub fn generate() -> Self {
+        Self(format!("{:016x}", rand::random::<u64>()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for MessageId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone)]
+pub struct QueuedMessage {
+    id: MessageId,
+    message: EncodedMessage,
+    priority: Priority,
+    submitted_at: time::DateTime,
+    not_before: Option<time::DateTime>,
+    // assigned by the queue, orders messages which are available at the same time
+    sequence: u64,
+}
+
+impl QueuedMessage {
+    pub fn new(
+        id: MessageId,
+        message: EncodedMessage,
+        priority: Priority,
+        submitted_at: time::DateTime,
+    ) -> Self {
+        Self {
+            id,
+            message,
+            priority,
+            submitted_at,
+            not_before: None,
+            sequence: 0,
+        }
+    }
+
+    /// Holds the message in the queue until the given time.
+    pub fn with_not_before(self, not_before: time::DateTime) -> Self {
+        Self {
+            not_before: Some(not_before),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    pub fn message(&self) -> &EncodedMessage {
+        &self.message
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn submitted_at(&self) -> &time::DateTime {
+        &self.submitted_at
+    }
+
+    pub fn not_before(&self) -> Option<&time::DateTime> {
+        self.not_before.as_ref()
+    }
+
+    /// Time from which the message can be transmitted.
+    pub fn available_at(&self) -> &time::DateTime {
+        self.not_before.as_ref().unwrap_or(&self.submitted_at)
+    }
+
+    fn transmission_order(&self) -> (Priority, &time::DateTime, u64) {
+        (self.priority, self.available_at(), self.sequence)
+    }
+}
+
+/// Messages start out queued, then they are transmitted unless they are rejected by an operator
+/// or expire while waiting in the queue. Operators can also abort messages during transmission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatus {
+    Queued,
+    Transmitting,
+    Transmitted,
+    Rejected,
+    Expired,
+    Aborted,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageRecord {
+    id: MessageId,
+    status: MessageStatus,
+    submitted_at: time::DateTime,
+    transmission_started_at: Option<time::DateTime>,
+    finished_at: Option<time::DateTime>,
+}
+
+impl MessageRecord {
+    pub fn new(message: &QueuedMessage, status: MessageStatus) -> Self {
+        Self {
+            id: message.id.clone(),
+            status,
+            submitted_at: message.submitted_at.clone(),
+            transmission_started_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub fn with_transmission_started_at(self, transmission_started_at: time::DateTime) -> Self {
+        Self {
+            transmission_started_at: Some(transmission_started_at),
+            ..self
+        }
+    }
+
+    pub fn with_finished_at(self, finished_at: time::DateTime) -> Self {
+        Self {
+            finished_at: Some(finished_at),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    pub fn status(&self) -> MessageStatus {
+        self.status
+    }
+
+    pub fn submitted_at(&self) -> &time::DateTime {
+        &self.submitted_at
+    }
+
+    pub fn transmission_started_at(&self) -> Option<&time::DateTime> {
+        self.transmission_started_at.as_ref()
+    }
+
+    pub fn finished_at(&self) -> Option<&time::DateTime> {
+        self.finished_at.as_ref()
+    }
+}
+
+const MAX_MESSAGE_RECORDS: usize = 1000;
+
+/// Remembers what happened to messages after they left the queue. Only the most recent records
+/// are kept.
+#[derive(Clone, Default)]
+pub struct MessageLog {
+    records: Arc<Mutex<VecDeque<MessageRecord>>>,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, record: MessageRecord) {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|v| v.id != record.id);
+        records.push_back(record);
+        while records.len() > MAX_MESSAGE_RECORDS {
+            records.pop_front();
+        }
+    }
+
+    /// Finishes the transmission of the message, messages which aren't being transmitted
+    /// anymore are left alone.
+    pub fn finish(&self, id: &MessageId, status: MessageStatus, finished_at: time::DateTime) {
+        let mut records = self.records.lock().unwrap();
+        if let Some(record) = records
+            .iter_mut()
+            .find(|v| &v.id == id && v.status == MessageStatus::Transmitting)
+        {
+            record.status = status;
+            record.finished_at = Some(finished_at);
+        }
+    }
+
+    pub fn get(&self, id: &MessageId) -> Option<MessageRecord> {
+        let records = self.records.lock().unwrap();
+        records.iter().find(|v| &v.id == id).cloned()
+    }
+}
+
+/// Keeps the queue across restarts. Saved messages include the messages which are being
+/// transmitted first so that they aren't lost if the transmission doesn't finish.
+pub trait QueueStorage: Send + Sync {
+    fn load(&self) -> Result<Vec<QueuedMessage>>;
+    fn save(&self, messages: &[QueuedMessage]) -> Result<()>;
+}
+
+#[derive(Clone)]
+pub struct Queue {
+    contents: Arc<Mutex<QueueContents>>,
+    max_messages: usize,
+    storage: Option<Arc<dyn QueueStorage>>,
+}
+
+#[derive(Clone, Default)]
+struct QueueContents {
+    // kept in transmission order
+    messages: Vec<QueuedMessage>,
+    // popped but not finished yet
+    transmitting: Vec<QueuedMessage>,
+    next_sequence: u64,
+}
+
+impl QueueContents {
+    fn ids(&self) -> impl Iterator<Item = &MessageId> {
+        self.transmitting
+            .iter()
+            .chain(self.messages.iter())
+            .map(|v| &v.id)
+    }
+}
+
+impl Queue {
+    pub fn new(max_messages: usize) -> Result<Self> {
+        if max_messages == 0 {
+            return Err(anyhow!("max_messages in the queue can't be set to zero").into());
+        }
+        Ok(Self {
+            contents: Arc::new(Mutex::new(QueueContents::default())),
+            max_messages,
+            storage: None,
+        })
+    }
+
+    /// Restores the messages from the storage and saves them after every change. Restored
+    /// messages are kept even if there are more of them than the queue can hold. Messages whose
+    /// transmission didn't finish are restored at the front of the queue.
+    pub fn new_with_storage(
+        max_messages: usize,
+        storage: impl QueueStorage + 'static,
+    ) -> Result<Self> {
+        let queue = Self::new(max_messages)?;
+        {
+            let mut contents = queue.contents.lock().unwrap();
+            for message in storage.load()? {
+                let sequence = contents.next_sequence;
+                contents.messages.push(QueuedMessage {
+                    sequence,
+                    ..message
+                });
+                contents.next_sequence += 1;
+            }
+        }
+        Ok(Self {
+            storage: Some(Arc::new(storage)),
+            ..queue
+        })
+    }
+
+    /// Messages which are held until a specific time don't count towards the size of the queue
+    /// as they could otherwise block it for a long time.
+    pub fn add_message(&self, message: QueuedMessage) -> Result<()> {
+        self.update(|contents| {
+            let messages = &mut contents.messages;
+            if message.not_before.is_none()
+                && messages.iter().filter(|v| v.not_before.is_none()).count() >= self.max_messages
+            {
+                return Err(Error::QueueIsFull);
+            }
+            let message = QueuedMessage {
+                sequence: contents.next_sequence,
+                ..message
+            };
+            contents.next_sequence += 1;
+            // operators can reorder messages so the queue isn't necessarily sorted
+            let index = messages
+                .iter()
+                .position(|v| v.transmission_order() > message.transmission_order())
+                .unwrap_or(messages.len());
+            messages.insert(index, message);
+            Ok(())
+        })
+    }
+
+    pub fn remove_message(&self, id: &MessageId) -> Result<QueuedMessage> {
+        self.update_messages(|messages| {
+            let index = find_message(messages, id)?;
+            Ok(messages.remove(index))
+        })
+    }
+
+    /// Moves the message to the given position in the order returned by
+    /// [Queue::get_messages]. Messages can only be moved among available messages of the same
+    /// priority, positions outside of that range are clamped to it. Held messages keep their
+    /// place until they are released.
+    pub fn move_message(
+        &self,
+        id: &MessageId,
+        position: usize,
+        now: &time::DateTime,
+    ) -> Result<()> {
+        self.update_messages(|messages| {
+            let index = find_message(messages, id)?;
+            if messages[index].available_at() > now {
+                return Ok(());
+            }
+            let message = messages.remove(index);
+            // available messages are listed first and in the order in which they are stored
+            let available: Vec<&QueuedMessage> = messages
+                .iter()
+                .filter(|v| v.available_at() <= now)
+                .collect();
+            let first = available.partition_point(|v| v.priority < message.priority);
+            let last = available.partition_point(|v| v.priority <= message.priority);
+            let index = match position.clamp(first, last) {
+                position if position < last => find_message(messages, &available[position].id)?,
+                _ => messages.partition_point(|v| v.priority <= message.priority),
+            };
+            messages.insert(index, message);
+            Ok(())
+        })
+    }
+
+    pub fn clear(&self) -> Result<Vec<QueuedMessage>> {
+        self.update_messages(|messages| Ok(std::mem::take(messages)))
+    }
+
+    /// Returns the first message which is no longer held. The message is kept in the storage
+    /// until it is finished.
+    pub fn pop_message(&self, now: &time::DateTime) -> Result<Option<QueuedMessage>> {
+        self.update(|contents| {
+            match contents
+                .messages
+                .iter()
+                .position(|v| v.available_at() <= now)
+            {
+                Some(index) => {
+                    let message = contents.messages.remove(index);
+                    contents.transmitting.push(message.clone());
+                    Ok(Some(message))
+                }
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Forgets a popped message once it has been transmitted, aborted or discarded. Messages
+    /// which didn't come from the queue are ignored.
+    pub fn finish_message(&self, id: &MessageId) -> Result<()> {
+        self.update(|contents| {
+            contents.transmitting.retain(|v| &v.id != id);
+            Ok(())
+        })
+    }
+
+    /// Returns the messages in the order in which they will be transmitted. Messages which are
+    /// held until later are listed after the available ones in the order in which they will be
+    /// released.
+    pub fn get_messages(&self, now: &time::DateTime) -> Result<Vec<QueuedMessage>> {
+        let contents = self.contents.lock().unwrap();
+        let (mut messages, mut held): (Vec<QueuedMessage>, Vec<QueuedMessage>) = contents
+            .messages
+            .iter()
+            .cloned()
+            .partition(|v| v.available_at() <= now);
+        held.sort_by(|a, b| a.available_at().cmp(b.available_at()));
+        messages.append(&mut held);
+        Ok(messages)
+    }
+
+    /// Returns the messages which were queued for a specific time, including the ones which
+    /// are already available, ordered by that time.
+    pub fn get_scheduled_messages(&self) -> Result<Vec<QueuedMessage>> {
+        let contents = self.contents.lock().unwrap();
+        let mut messages: Vec<QueuedMessage> = contents
+            .messages
+            .iter()
+            .filter(|v| v.not_before.is_some())
+            .cloned()
+            .collect();
+        messages.sort_by(|a, b| a.available_at().cmp(b.available_at()));
+        Ok(messages)
+    }
+
+    fn update_messages<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<QueuedMessage>) -> Result<T>,
+    ) -> Result<T> {
+        self.update(|contents| f(&mut contents.messages))
+    }
+
+    /// Changes are only applied once they have been saved. Nothing is saved if nothing changed
+    /// as the queue is polled frequently and e.g. SD cards wear out.
+    fn update<T>(&self, f: impl FnOnce(&mut QueueContents) -> Result<T>) -> Result<T> {
+        let mut contents = self.contents.lock().unwrap();
+        let mut new_contents = contents.clone();
+        let result = f(&mut new_contents)?;
+        // messages are only ever added, removed or moved
+        let changed = !contents.ids().eq(new_contents.ids());
+        if changed && let Some(storage) = &self.storage {
+            let snapshot: Vec<QueuedMessage> = new_contents
+                .transmitting
+                .iter()
+                .chain(new_contents.messages.iter())
+                .cloned()
+                .collect();
+            storage.save(&snapshot)?;
+        }
+        *contents = new_contents;
+        Ok(result)
+    }
+}
+
+fn find_message(messages: &[QueuedMessage], id: &MessageId) -> Result<usize> {
+    messages
+        .iter()
+        .position(|v| &v.id == id)
+        .ok_or_else(|| Error::MessageNotFound(id.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimingConfig {
+    show_character_for: Duration,
+    pause_between_characters_for: Duration,
+    pause_between_messages_for: Duration,
+    inject_message_if_no_next_message_after_pausing_between_messages_for: Duration,
+    message_expiry: Option<Duration>,
+}
+
+impl TimingConfig {
+    pub fn new(
+        show_character_for: Duration,
         pause_between_characters_for: Duration,
         pause_between_messages_for: Duration,
         inject_message_if_no_next_message_after_pausing_between_messages_for: Duration,
@@ -730,6 +1455,16 @@ impl TimingConfig {
             pause_between_characters_for,
             pause_between_messages_for,
             inject_message_if_no_next_message_after_pausing_between_messages_for,
+            message_expiry: None,
+        }
+    }
+
+    /// Messages which waited in the queue for longer than this are dropped instead of being
+    /// transmitted.
+    pub fn with_message_expiry(self, message_expiry: Duration) -> Self {
+        Self {
+            message_expiry: Some(message_expiry),
+            ..self
         }
     }
 
@@ -750,37 +1485,368 @@ impl TimingConfig {
     ) -> &Duration {
         &self.inject_message_if_no_next_message_after_pausing_between_messages_for
     }
+
+    pub fn message_expiry(&self) -> Option<&Duration> {
+        self.message_expiry.as_ref()
+    }
+}
+
+/// Describes how an interrupted message continues once the urgent message which interrupted it
+/// has been transmitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreemptMode {
+    /// Continue with the character which was shown when the message was interrupted. The rest
+    /// of the message is transmitted as a message of its own.
+    Resume,
+    /// Transmit the whole message again.
+    Restart,
+}
+
+/// Message which was interrupted by an urgent message. Urgent messages can be interrupted too in
+/// which case the messages are continued in reverse order.
+#[derive(Clone)]
+pub struct InterruptedMessage {
+    id: Option<MessageId>,
+    message: EncodedMessage,
+    before: Vec<EncodedMessagePart>,
+    after: Vec<EncodedMessagePart>,
+    mode: PreemptMode,
+    interrupted: Option<Box<InterruptedMessage>>,
+}
+
+impl InterruptedMessage {
+    pub fn id(&self) -> Option<&MessageId> {
+        self.id.as_ref()
+    }
+
+    /// The whole message which is transmitted again if the message is restarted, even if it
+    /// was interrupted while it was being resumed.
+    pub fn message(&self) -> &EncodedMessage {
+        &self.message
+    }
+
+    /// Parts which were fully shown before the message was interrupted.
+    pub fn before(&self) -> &[EncodedMessagePart] {
+        &self.before
+    }
+
+    /// Parts which still have to be shown if the message is resumed. If the message is resumed
+    /// they are encoded as a message of their own.
+    pub fn after(&self) -> &[EncodedMessagePart] {
+        &self.after
+    }
+
+    pub fn mode(&self) -> PreemptMode {
+        self.mode
+    }
+
+    pub fn interrupted(&self) -> Option<&InterruptedMessage> {
+        self.interrupted.as_deref()
+    }
+}
+
+/// Describes why the clacks was paused, e.g. for maintenance, and by whom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pause {
+    reason: String,
+    paused_by: String,
+    paused_at: time::DateTime,
+    accept_messages: bool,
+}
+
+impl Pause {
+    pub fn new(
+        reason: impl Into<String>,
+        paused_by: impl Into<String>,
+        accept_messages: bool,
+        paused_at: time::DateTime,
+    ) -> Self {
+        Self {
+            reason: reason.into(),
+            paused_by: paused_by.into(),
+            paused_at,
+            accept_messages,
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn paused_by(&self) -> &str {
+        &self.paused_by
+    }
+
+    pub fn paused_at(&self) -> &time::DateTime {
+        &self.paused_at
+    }
+
+    /// If set messages are still added to the queue and transmitted once the clacks is resumed.
+    pub fn accept_messages(&self) -> bool {
+        self.accept_messages
+    }
 }
 
 #[derive(Clone)]
 pub struct Clacks {
     current_state: Arc<Mutex<Box<dyn ClacksState>>>,
+    // set if the state was replaced outside of an update, e.g. when aborting a message
+    state_replaced: Arc<AtomicBool>,
+    park_position: ShutterPositions,
+    quiet_hours: Option<QuietHours>,
+    quiet: Arc<AtomicBool>,
+    // set when the last quiet period ended, messages held during it don't expire
+    active_since: Arc<Mutex<Option<time::DateTime>>>,
     config: TimingConfig,
     queue: Queue,
     messages_to_inject: MessagesToInject,
+    clock_messages: Option<ClockMessages>,
+    message_log: MessageLog,
+    clock: Arc<dyn time::Clock>,
 }
 
 impl Clacks {
-    pub fn new(config: TimingConfig, queue: Queue, messages_to_inject: MessagesToInject) -> Self {
+    pub fn new(
+        config: TimingConfig,
+        queue: Queue,
+        messages_to_inject: MessagesToInject,
+        message_log: MessageLog,
+    ) -> Self {
+        let clock = time::SystemClock::new();
         Self {
-            current_state: Arc::new(Mutex::new(Box::new(ClacksWaitingForNextMessage::new()))),
+            current_state: Arc::new(Mutex::new(Box::new(ClacksWaitingForNextMessage::new(
+                clock.now(),
+            )))),
+            state_replaced: Arc::new(AtomicBool::new(false)),
+            park_position: ShutterPositions::new_with_all_closed(),
+            quiet_hours: None,
+            quiet: Arc::new(AtomicBool::new(false)),
+            active_since: Arc::new(Mutex::new(None)),
             config,
             queue,
             messages_to_inject,
+            clock_messages: None,
+            message_log,
+            clock: Arc::new(clock),
         }
     }
 
+    /// Replaces the system clock, e.g. to simulate the clacks faster than real time.
+    pub fn with_clock(mut self, clock: impl time::Clock + 'static) -> Self {
+        self.current_state = Arc::new(Mutex::new(Box::new(ClacksWaitingForNextMessage::new(
+            clock.now(),
+        ))));
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Position in which the shutters are kept while the clacks is paused or idle during quiet
+    /// hours, all shutters are closed by default.
+    pub fn with_park_position(mut self, park_position: ShutterPositions) -> Self {
+        self.park_position = park_position;
+        self
+    }
+
+    /// During quiet hours no messages are dequeued or injected. A message which is already
+    /// being transmitted is finished.
+    pub fn with_quiet_hours(mut self, quiet_hours: QuietHours) -> Self {
+        self.quiet_hours = Some(quiet_hours);
+        self
+    }
+
+    /// Shows the time while the queue is empty, before any message is injected.
+    pub fn with_clock_messages(mut self, clock_messages: ClockMessages) -> Self {
+        self.clock_messages = Some(clock_messages);
+        self
+    }
+
     pub fn update(&self) -> Result<ClacksUpdateResult> {
+        let now = self.clock.now();
+        let quiet = self
+            .quiet_hours
+            .as_ref()
+            .is_some_and(|quiet_hours| quiet_hours.is_quiet(&now));
+        let quiet_changed = self.quiet.swap(quiet, Ordering::SeqCst) != quiet;
+        if quiet_changed && !quiet {
+            *self.active_since.lock().unwrap() = Some(now.clone());
+        }
+        let active_since = self.active_since.lock().unwrap().clone();
+
+        let context = ClacksContext {
+            queue: &self.queue,
+            config: &self.config,
+            messages_to_inject: &self.messages_to_inject,
+            clock_messages: self.clock_messages.as_ref(),
+            message_log: &self.message_log,
+            quiet,
+            active_since: active_since.as_ref(),
+            now,
+        };
+
         let mut current_state = self.current_state.lock().unwrap();
-        if let Some(new_state) =
-            current_state.update(&self.queue, &self.config, &self.messages_to_inject)?
-        {
+        if let Some(new_state) = current_state.update(&context)? {
             *current_state = new_state;
+            self.state_replaced.store(false, Ordering::SeqCst);
             return Ok(ClacksUpdateResult::StateChanged);
         };
+        if self.state_replaced.swap(false, Ordering::SeqCst) || quiet_changed {
+            return Ok(ClacksUpdateResult::StateChanged);
+        }
         Ok(ClacksUpdateResult::StateNotChanged)
     }
 
+    /// Stops transmitting the current message and shows the cancel symbol instead, if the
+    /// encoding has one. Returns the id of the aborted message if it has one.
+    pub fn abort(&self, cancel: Option<EncodedMessagePart>) -> Result<Option<MessageId>> {
+        let mut current_state = self.current_state.lock().unwrap();
+        let transmission = match current_state.transmission() {
+            Some(transmission) if !transmission.after.is_empty() => transmission,
+            _ => return Err(Error::NoMessageIsBeingTransmitted),
+        };
+
+        let now = self.clock.now();
+        if let Some(id) = &transmission.id {
+            self.queue.finish_message(id)?;
+            self.message_log
+                .finish(id, MessageStatus::Aborted, now.clone());
+        }
+
+        let interrupted = current_state.interrupted();
+        *current_state = match cancel {
+            Some(cancel) => Box::new(ClacksShowingCharacter {
+                id: transmission.id.clone(),
+                message: transmission.message,
+                before: transmission.before,
+                current: cancel,
+                after: vec![],
+                started_at: now,
+                interrupted,
+                timing: Timing::Messages,
+            }),
+            None => Box::new(ClacksPausingBetweenMessages::new(
+                interrupted,
+                Timing::Messages,
+                now,
+            )),
+        };
+        self.state_replaced.store(true, Ordering::SeqCst);
+        Ok(transmission.id)
+    }
+
+    /// Interrupts the current message, if there is one, and transmits the urgent message right
+    /// away. The interrupted message is cut off with the cancel symbol, if the encoding has
+    /// one, and continued after the urgent message. Resumed messages are transmitted as
+    /// messages of their own which are encoded by `resume`, see [Encoding::encode_resumption].
+    pub fn preempt(
+        &self,
+        message: QueuedMessage,
+        mode: PreemptMode,
+        cancel: Option<EncodedMessagePart>,
+        resume: impl FnOnce(&[EncodedMessagePart]) -> Result<Option<EncodedMessage>>,
+    ) -> Result<()> {
+        let mut current_state = self.current_state.lock().unwrap();
+        if current_state.pause().is_some() {
+            return Err(Error::ClacksIsPaused);
+        }
+
+        let interrupted = match current_state.transmission() {
+            Some(transmission) if !transmission.after.is_empty() => {
+                let (mode, after) = match mode {
+                    PreemptMode::Resume => match resume(&transmission.after)? {
+                        Some(resumption) => (PreemptMode::Resume, resumption.parts),
+                        // only the checksum and the end are left which mean nothing on their own
+                        None => (PreemptMode::Restart, transmission.after),
+                    },
+                    PreemptMode::Restart => (PreemptMode::Restart, transmission.after),
+                };
+                Some(InterruptedMessage {
+                    id: transmission.id,
+                    message: transmission.message,
+                    before: transmission.before,
+                    after,
+                    mode,
+                    interrupted: current_state.interrupted().map(Box::new),
+                })
+            }
+            _ => current_state.interrupted(),
+        };
+
+        let now = self.clock.now();
+        self.message_log.record(
+            MessageRecord::new(&message, MessageStatus::Transmitting)
+                .with_transmission_started_at(now.clone()),
+        );
+
+        let mut parts = message.message.parts.clone();
+        if let Some(cancel) = cancel
+            && interrupted.is_some()
+        {
+            parts.insert(0, cancel);
+        }
+        let current = parts.remove(0);
+        *current_state = Box::new(ClacksShowingCharacter {
+            id: Some(message.id),
+            message: message.message,
+            before: vec![],
+            current,
+            after: parts,
+            started_at: now,
+            interrupted,
+            timing: Timing::Messages,
+        });
+        self.state_replaced.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Stops transmitting messages and parks the shutters until the clacks is resumed. The
+    /// message which is being transmitted is restarted after resuming. Pausing a clacks which
+    /// is already paused replaces the pause.
+    pub fn pause(&self, pause: Pause) -> Result<()> {
+        let mut current_state = self.current_state.lock().unwrap();
+        let interrupted = match current_state.transmission() {
+            Some(transmission) if !transmission.after.is_empty() => Some(InterruptedMessage {
+                id: transmission.id,
+                message: transmission.message,
+                before: transmission.before,
+                after: transmission.after,
+                mode: PreemptMode::Restart,
+                interrupted: current_state.interrupted().map(Box::new),
+            }),
+            _ => current_state.interrupted(),
+        };
+
+        *current_state = Box::new(ClacksPaused { pause, interrupted });
+        self.state_replaced.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        let mut current_state = self.current_state.lock().unwrap();
+        if current_state.pause().is_none() {
+            return Err(Error::ClacksIsNotPaused);
+        }
+
+        let interrupted = current_state.interrupted();
+        *current_state = Box::new(ClacksPausingBetweenMessages::new(
+            interrupted,
+            Timing::Messages,
+            self.clock.now(),
+        ));
+        self.state_replaced.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn current_pause(&self) -> Option<Pause> {
+        let current_state = self.current_state.lock().unwrap();
+        current_state.pause()
+    }
+
+    pub fn current_quiet_period(&self) -> Option<QuietPeriod> {
+        self.quiet_hours
+            .as_ref()
+            .and_then(|quiet_hours| quiet_hours.period_at(&self.clock.now()))
+    }
+
     pub fn current_message(&self) -> Option<CurrentMessage> {
         let current_state = self.current_state.lock().unwrap();
         current_state.current_message()
@@ -788,6 +1854,12 @@ impl Clacks {
 
     pub fn get_desired_shutter_positions(&self) -> ShutterPositions {
         let current_state = self.current_state.lock().unwrap();
+        if current_state.pause().is_some()
+            || (self.quiet.load(Ordering::SeqCst) && current_state.transmission().is_none())
+        {
+            return self.park_position.clone();
+        }
+
         match current_state.current_message() {
             None => ShutterPositions::new_with_all_closed(),
             Some(current_message) => match current_message.current {
@@ -798,14 +1870,65 @@ impl Clacks {
     }
 }
 
+struct ClacksContext<'a> {
+    queue: &'a Queue,
+    config: &'a TimingConfig,
+    messages_to_inject: &'a MessagesToInject,
+    clock_messages: Option<&'a ClockMessages>,
+    message_log: &'a MessageLog,
+    quiet: bool,
+    active_since: Option<&'a time::DateTime>,
+    now: time::DateTime,
+}
+
+impl ClacksContext<'_> {
+    fn timing(&self, timing: Timing) -> &TimingConfig {
+        match timing {
+            Timing::Messages => self.config,
+            Timing::Clock => self
+                .clock_messages
+                .and_then(|clock_messages| clock_messages.timing())
+                .unwrap_or(self.config),
+        }
+    }
+
+    /// Waiting during quiet hours doesn't count.
+    fn waiting_since<'a>(&'a self, since: &'a time::DateTime) -> &'a time::DateTime {
+        match self.active_since {
+            Some(active_since) if active_since > since => active_since,
+            _ => since,
+        }
+    }
+}
+
+/// Timing profile used while transmitting a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Timing {
+    Messages,
+    Clock,
+}
+
+/// Message which is being transmitted, the current character counts as not shown yet.
+struct Transmission {
+    id: Option<MessageId>,
+    message: EncodedMessage,
+    before: Vec<EncodedMessagePart>,
+    after: Vec<EncodedMessagePart>,
+}
+
 trait ClacksState: Send {
-    fn update(
-        &self,
-        queue: &Queue,
-        config: &TimingConfig,
-        messages_to_inject: &MessagesToInject,
-    ) -> Result<Option<Box<dyn ClacksState>>>;
+    fn update(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>>;
     fn current_message(&self) -> Option<CurrentMessage>;
+    fn transmission(&self) -> Option<Transmission>;
+
+    /// Message which is continued once nothing else is transmitted anymore.
+    fn interrupted(&self) -> Option<InterruptedMessage> {
+        None
+    }
+
+    fn pause(&self) -> Option<Pause> {
+        None
+    }
 }
 
 struct ClacksWaitingForNextMessage {
@@ -813,33 +1936,51 @@ struct ClacksWaitingForNextMessage {
 }
 
 impl ClacksWaitingForNextMessage {
-    pub fn new() -> Self {
-        Self {
-            started_at: time::DateTime::now(),
-        }
+    pub fn new(now: time::DateTime) -> Self {
+        Self { started_at: now }
     }
 }
 
 impl ClacksState for ClacksWaitingForNextMessage {
-    fn update(
-        &self,
-        queue: &Queue,
-        config: &TimingConfig,
-        messages_to_inject: &MessagesToInject,
-    ) -> Result<Option<Box<dyn ClacksState>>> {
-        if let Some(encoded_message) = queue.pop_message() {
+    fn update(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        if context.quiet {
+            return Ok(None);
+        }
+
+        if let Some(state) = self.next_queued_message(context)? {
+            return Ok(Some(state));
+        }
+
+        let now = &context.now;
+        if let Some(clock_messages) = context.clock_messages
+            && let Some(encoded_message) = clock_messages.get(now)?
+        {
             return Ok(Some(Box::new(ClacksShowingCharacter::new_message(
+                None,
                 encoded_message,
+                Timing::Clock,
+                now.clone(),
             ))));
         }
 
-        let since = &time::DateTime::now() - &self.started_at;
-        if since >= config.inject_message_if_no_next_message_after_pausing_between_messages_for
-            && let Some(encoded_message) = messages_to_inject.get()
+        let since = now - context.waiting_since(&self.started_at);
+        if since
+            >= context
+                .config
+                .inject_message_if_no_next_message_after_pausing_between_messages_for
+            && let Some(encoded_message) = context
+                .messages_to_inject
+                .get(context.queue.get_messages(now)?.len(), now)?
         {
-            return Ok(Some(Box::new(ClacksShowingCharacter::new_message(
-                encoded_message.clone(),
-            ))));
+            // injected messages go through the queue so that they are listed and tracked like
+            // the other messages
+            context.queue.add_message(QueuedMessage::new(
+                MessageId::generate(),
+                encoded_message,
+                Priority::Injected,
+                now.clone(),
+            ))?;
+            return self.next_queued_message(context);
         }
 
         Ok(None)
@@ -848,27 +1989,76 @@ impl ClacksState for ClacksWaitingForNextMessage {
     fn current_message(&self) -> Option<CurrentMessage> {
         None
     }
+
+    fn transmission(&self) -> Option<Transmission> {
+        None
+    }
+}
+
+impl ClacksWaitingForNextMessage {
+    fn next_queued_message(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        let now = &context.now;
+        while let Some(queued_message) = context.queue.pop_message(now)? {
+            if let Some(expiry) = context.config.message_expiry()
+                && now - context.waiting_since(queued_message.available_at()) >= *expiry
+            {
+                context.queue.finish_message(&queued_message.id)?;
+                context.message_log.record(
+                    MessageRecord::new(&queued_message, MessageStatus::Expired)
+                        .with_finished_at(now.clone()),
+                );
+                continue;
+            }
+
+            context.message_log.record(
+                MessageRecord::new(&queued_message, MessageStatus::Transmitting)
+                    .with_transmission_started_at(now.clone()),
+            );
+            return Ok(Some(Box::new(ClacksShowingCharacter::new_message(
+                Some(queued_message.id),
+                queued_message.message,
+                Timing::Messages,
+                now.clone(),
+            ))));
+        }
+        Ok(None)
+    }
 }
 
 struct ClacksShowingCharacter {
+    // clock messages don't have an id
+    id: Option<MessageId>,
+    // the whole message, resumed messages only show the parts which were left of it
+    message: EncodedMessage,
     before: Vec<EncodedMessagePart>,
     current: EncodedMessagePart,
     after: Vec<EncodedMessagePart>,
     started_at: time::DateTime,
+    interrupted: Option<InterruptedMessage>,
+    timing: Timing,
 }
 
 impl ClacksShowingCharacter {
-    pub fn new_message(message: EncodedMessage) -> Self {
+    pub fn new_message(
+        id: Option<MessageId>,
+        message: EncodedMessage,
+        timing: Timing,
+        now: time::DateTime,
+    ) -> Self {
         let first = message.parts[0].clone();
         Self {
+            id,
             before: vec![],
             current: first,
-            after: message.parts.into_iter().skip(1).collect(),
-            started_at: time::DateTime::now(),
+            after: message.parts.iter().skip(1).cloned().collect(),
+            message,
+            started_at: now,
+            interrupted: None,
+            timing,
         }
     }
 
-    fn next_character(state: &ClacksPausingBetweenCharacters) -> Result<Self> {
+    fn next_character(state: &ClacksPausingBetweenCharacters, now: time::DateTime) -> Result<Self> {
         if state.after.is_empty() {
             return Err(
                 anyhow!("after can't be empty when advancing to the next character").into(),
@@ -877,50 +2067,107 @@ impl ClacksShowingCharacter {
 
         let next = state.after[0].clone();
         Ok(Self {
+            id: state.id.clone(),
+            message: state.message.clone(),
             before: state.before.clone(),
             current: next,
             after: state.after.clone().into_iter().skip(1).collect(),
-            started_at: time::DateTime::now(),
+            started_at: now,
+            interrupted: state.interrupted.clone(),
+            timing: state.timing,
         })
     }
+
+    fn continue_message(interrupted: &InterruptedMessage, now: time::DateTime) -> Self {
+        let mut after = match interrupted.mode {
+            // the resumption is a message of its own
+            PreemptMode::Resume => interrupted.after.clone(),
+            PreemptMode::Restart => interrupted.message.parts.clone(),
+        };
+        let current = after.remove(0);
+        Self {
+            id: interrupted.id.clone(),
+            message: interrupted.message.clone(),
+            before: vec![],
+            current,
+            after,
+            started_at: now,
+            interrupted: interrupted.interrupted.as_deref().cloned(),
+            timing: Timing::Messages,
+        }
+    }
 }
 
 impl ClacksState for ClacksShowingCharacter {
-    fn update(
-        &self,
-        _queue: &Queue,
-        config: &TimingConfig,
-        _messages_to_inject: &MessagesToInject,
-    ) -> Result<Option<Box<dyn ClacksState>>> {
-        let since = &time::DateTime::now() - &self.started_at;
-        if since < config.show_character_for {
+    fn update(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        let since = &context.now - &self.started_at;
+        if since < context.timing(self.timing).show_character_for {
             return Ok(None);
         }
 
         if !self.after.is_empty() {
-            return Ok(Some(Box::new(ClacksPausingBetweenCharacters::new(self)?)));
+            return Ok(Some(Box::new(ClacksPausingBetweenCharacters::new(
+                self,
+                context.now.clone(),
+            )?)));
         }
 
-        Ok(Some(Box::new(ClacksPausingBetweenMessages::new())))
+        if let Some(id) = &self.id {
+            context.queue.finish_message(id)?;
+            context
+                .message_log
+                .finish(id, MessageStatus::Transmitted, context.now.clone());
+        }
+        Ok(Some(Box::new(ClacksPausingBetweenMessages::new(
+            self.interrupted.clone(),
+            self.timing,
+            context.now.clone(),
+        ))))
     }
 
     fn current_message(&self) -> Option<CurrentMessage> {
-        Some(CurrentMessage::new(
-            self.before.clone(),
-            Some(self.current.clone()),
-            self.after.clone(),
-        ))
+        Some(
+            CurrentMessage::new(
+                self.id.clone(),
+                self.before.clone(),
+                Some(self.current.clone()),
+                self.after.clone(),
+            )
+            .with_interrupted(self.interrupted.clone()),
+        )
+    }
+
+    fn transmission(&self) -> Option<Transmission> {
+        let after = match self.current.element {
+            // the cancel symbol isn't part of the message
+            MessageComponent::Cancel => self.after.clone(),
+            _ => [vec![self.current.clone()], self.after.clone()].concat(),
+        };
+        Some(Transmission {
+            id: self.id.clone(),
+            message: self.message.clone(),
+            before: self.before.clone(),
+            after,
+        })
+    }
+
+    fn interrupted(&self) -> Option<InterruptedMessage> {
+        self.interrupted.clone()
     }
 }
 
 struct ClacksPausingBetweenCharacters {
+    id: Option<MessageId>,
+    message: EncodedMessage,
     before: Vec<EncodedMessagePart>,
     after: Vec<EncodedMessagePart>,
     started_at: time::DateTime,
+    interrupted: Option<InterruptedMessage>,
+    timing: Timing,
 }
 
 impl ClacksPausingBetweenCharacters {
-    pub fn new(state: &ClacksShowingCharacter) -> Result<Self> {
+    pub fn new(state: &ClacksShowingCharacter, now: time::DateTime) -> Result<Self> {
         if state.after.is_empty() {
             return Err(
                 anyhow!("after can't be empty when entering pausing before characters").into(),
@@ -931,67 +2178,1136 @@ impl ClacksPausingBetweenCharacters {
         before.push(state.current.clone());
 
         Ok(Self {
+            id: state.id.clone(),
+            message: state.message.clone(),
             before,
             after: state.after.clone(),
-            started_at: time::DateTime::now(),
+            started_at: now,
+            interrupted: state.interrupted.clone(),
+            timing: state.timing,
         })
     }
 }
 
 impl ClacksState for ClacksPausingBetweenCharacters {
-    fn update(
-        &self,
-        _queue: &Queue,
-        config: &TimingConfig,
-        _messages_to_inject: &MessagesToInject,
-    ) -> Result<Option<Box<dyn ClacksState>>> {
-        let since = &time::DateTime::now() - &self.started_at;
-        if since < config.pause_between_characters_for {
+    fn update(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        let since = &context.now - &self.started_at;
+        if since < context.timing(self.timing).pause_between_characters_for {
             return Ok(None);
         }
 
         Ok(Some(Box::new(ClacksShowingCharacter::next_character(
             self,
+            context.now.clone(),
         )?)))
     }
 
     fn current_message(&self) -> Option<CurrentMessage> {
-        Some(CurrentMessage::new(
-            self.before.clone(),
-            None,
-            self.after.clone(),
-        ))
+        Some(
+            CurrentMessage::new(
+                self.id.clone(),
+                self.before.clone(),
+                None,
+                self.after.clone(),
+            )
+            .with_interrupted(self.interrupted.clone()),
+        )
+    }
+
+    fn transmission(&self) -> Option<Transmission> {
+        Some(Transmission {
+            id: self.id.clone(),
+            message: self.message.clone(),
+            before: self.before.clone(),
+            after: self.after.clone(),
+        })
+    }
+
+    fn interrupted(&self) -> Option<InterruptedMessage> {
+        self.interrupted.clone()
     }
 }
 
 struct ClacksPausingBetweenMessages {
     started_at: time::DateTime,
+    interrupted: Option<InterruptedMessage>,
+    // timing of the message which was transmitted before the pause
+    timing: Timing,
 }
 
 impl ClacksPausingBetweenMessages {
-    pub fn new() -> Self {
+    pub fn new(
+        interrupted: Option<InterruptedMessage>,
+        timing: Timing,
+        now: time::DateTime,
+    ) -> Self {
         Self {
-            started_at: time::DateTime::now(),
+            started_at: now,
+            interrupted,
+            timing,
         }
     }
 }
 
 impl ClacksState for ClacksPausingBetweenMessages {
-    fn update(
-        &self,
-        _queue: &Queue,
-        config: &TimingConfig,
-        _messages_to_inject: &MessagesToInject,
-    ) -> Result<Option<Box<dyn ClacksState>>> {
-        let since = &time::DateTime::now() - &self.started_at;
-        if since < config.pause_between_messages_for {
+    fn update(&self, context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        let since = &context.now - &self.started_at;
+        if since < context.timing(self.timing).pause_between_messages_for {
             return Ok(None);
         }
 
-        Ok(Some(Box::new(ClacksWaitingForNextMessage::new())))
+        if let Some(interrupted) = &self.interrupted {
+            return Ok(Some(Box::new(ClacksShowingCharacter::continue_message(
+                interrupted,
+                context.now.clone(),
+            ))));
+        }
+        Ok(Some(Box::new(ClacksWaitingForNextMessage::new(
+            context.now.clone(),
+        ))))
+    }
+
+    fn current_message(&self) -> Option<CurrentMessage> {
+        // nothing is shown but the interrupted message is still waiting to be continued
+        self.interrupted.as_ref().map(|interrupted| {
+            CurrentMessage::new(None, vec![], None, vec![])
+                .with_interrupted(Some(interrupted.clone()))
+        })
+    }
+
+    fn transmission(&self) -> Option<Transmission> {
+        None
+    }
+
+    fn interrupted(&self) -> Option<InterruptedMessage> {
+        self.interrupted.clone()
+    }
+}
+
+struct ClacksPaused {
+    pause: Pause,
+    interrupted: Option<InterruptedMessage>,
+}
+
+impl ClacksState for ClacksPaused {
+    fn update(&self, _context: &ClacksContext) -> Result<Option<Box<dyn ClacksState>>> {
+        // nothing is dequeued or injected until the clacks is resumed
+        Ok(None)
     }
 
     fn current_message(&self) -> Option<CurrentMessage> {
+        self.interrupted.as_ref().map(|interrupted| {
+            CurrentMessage::new(None, vec![], None, vec![])
+                .with_interrupted(Some(interrupted.clone()))
+        })
+    }
+
+    fn transmission(&self) -> Option<Transmission> {
         None
     }
+
+    fn interrupted(&self) -> Option<InterruptedMessage> {
+        self.interrupted.clone()
+    }
+
+    fn pause(&self) -> Option<Pause> {
+        Some(self.pause.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock_mode::{ClockMode, ClockSchedule};
+    use crate::domain::injection::MessageToInject;
+    use crate::domain::templates::MessageTemplate;
+
+    #[test]
+    fn queue_keeps_messages_submitted_at_the_same_time_in_submission_order() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let submitted_at = time::DateTime::new_from_unix_timestamp(1000);
+        for (text, priority) in [
+            ("public 1", Priority::Public),
+            ("public 2", Priority::Public),
+            ("operator", Priority::Operator),
+            ("public 3", Priority::Public),
+        ] {
+            queue.add_message(QueuedMessage::new(
+                MessageId::generate(),
+                encoding.encode(&Message::new(text)?)?,
+                priority,
+                submitted_at.clone(),
+            ))?;
+        }
+
+        let mut order = vec![];
+        while let Some(queued_message) = queue.pop_message(&time::DateTime::now())? {
+            order.push(queued_message.message().text());
+        }
+        assert_eq!(vec!["OPERATOR", "PUBLIC 1", "PUBLIC 2", "PUBLIC 3"], order);
+        Ok(())
+    }
+
+    #[test]
+    fn queue_pops_messages_by_priority_and_submission_time() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let submitted_at = time::DateTime::new_from_unix_timestamp(1000);
+        for (text, priority, offset) in [
+            ("public 1", Priority::Public, 0),
+            ("public 2", Priority::Public, 1),
+            ("injected", Priority::Injected, 2),
+            ("operator", Priority::Operator, 3),
+            ("public 0", Priority::Public, -1),
+        ] {
+            let submitted_at = match offset >= 0 {
+                true => &submitted_at + time::Duration::new_from_seconds(offset as u64),
+                false => &submitted_at - time::Duration::new_from_seconds(1),
+            };
+            queue.add_message(QueuedMessage::new(
+                MessageId::generate(),
+                encoding.encode(&Message::new(text)?)?,
+                priority,
+                submitted_at,
+            ))?;
+        }
+
+        let mut order = vec![];
+        while let Some(queued_message) = queue.pop_message(&time::DateTime::now())? {
+            let text: String = queued_message
+                .message()
+                .parts()
+                .iter()
+                .filter_map(|part| match part.element() {
+                    MessageComponent::Character(character) => Some(character.as_str()),
+                    _ => None,
+                })
+                .collect();
+            order.push(text);
+        }
+        assert_eq!(
+            vec!["OPERATOR", "PUBLIC 0", "PUBLIC 1", "PUBLIC 2", "INJECTED"],
+            order
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn queue_holds_scheduled_messages_until_they_are_due() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(1)?;
+        let now = time::DateTime::now();
+        let public = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("public")?)?,
+            Priority::Public,
+            now.clone(),
+        );
+        let later = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("later")?)?,
+            Priority::Scheduled,
+            now.clone(),
+        )
+        .with_not_before(&now + time::Duration::new_from_hours(1));
+        let due = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("due")?)?,
+            Priority::Scheduled,
+            now.clone(),
+        )
+        .with_not_before(&now - time::Duration::new_from_seconds(1));
+        queue.add_message(public.clone())?;
+        queue.add_message(later.clone())?;
+        queue.add_message(due.clone())?;
+
+        assert_eq!(
+            Some(due.id()),
+            queue
+                .pop_message(&time::DateTime::now())?
+                .as_ref()
+                .map(|v| v.id())
+        );
+        assert_eq!(
+            Some(public.id()),
+            queue
+                .pop_message(&time::DateTime::now())?
+                .as_ref()
+                .map(|v| v.id())
+        );
+        assert!(queue.pop_message(&time::DateTime::now())?.is_none());
+        assert_eq!(1, queue.get_messages(&time::DateTime::now())?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn queue_is_only_saved_when_it_changes() -> Result<()> {
+        #[derive(Clone, Default)]
+        struct CountingStorage {
+            saves: Arc<Mutex<usize>>,
+        }
+
+        impl QueueStorage for CountingStorage {
+            fn load(&self) -> Result<Vec<QueuedMessage>> {
+                Ok(vec![])
+            }
+
+            fn save(&self, _messages: &[QueuedMessage]) -> Result<()> {
+                *self.saves.lock().unwrap() += 1;
+                Ok(())
+            }
+        }
+
+        let storage = CountingStorage::default();
+        let queue = Queue::new_with_storage(10, storage.clone())?;
+        let now = time::DateTime::now();
+        for _ in 0..10 {
+            assert!(queue.pop_message(&now)?.is_none());
+        }
+        assert_eq!(0, *storage.saves.lock().unwrap());
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            Encoding::default().encode(&Message::new("held")?)?,
+            Priority::Scheduled,
+            now.clone(),
+        )
+        .with_not_before(&now + time::Duration::new_from_hours(1));
+        queue.add_message(message.clone())?;
+        for _ in 0..10 {
+            assert!(queue.pop_message(&now)?.is_none());
+        }
+        queue.move_message(message.id(), 0, &now)?;
+        assert_eq!(1, *storage.saves.lock().unwrap());
+
+        queue.remove_message(message.id())?;
+        assert_eq!(2, *storage.saves.lock().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn queue_moves_messages_within_their_priority() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let mut ids = vec![];
+        for (text, priority) in [
+            ("operator", Priority::Operator),
+            ("public 1", Priority::Public),
+            ("public 2", Priority::Public),
+            ("public 3", Priority::Public),
+        ] {
+            let id = MessageId::generate();
+            queue.add_message(QueuedMessage::new(
+                id.clone(),
+                encoding.encode(&Message::new(text)?)?,
+                priority,
+                time::DateTime::now(),
+            ))?;
+            ids.push(id);
+        }
+
+        queue.move_message(&ids[3], 0, &time::DateTime::now())?;
+        queue.remove_message(&ids[1])?;
+        queue.add_message(QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("public 4")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        ))?;
+
+        let messages = queue.get_messages(&time::DateTime::now())?;
+        assert_eq!(4, messages.len());
+        assert_eq!(&ids[0], messages[0].id());
+        assert_eq!(&ids[3], messages[1].id());
+        assert_eq!(&ids[2], messages[2].id());
+        assert!(matches!(
+            queue.remove_message(&ids[1]),
+            Err(Error::MessageNotFound(_))
+        ));
+
+        assert_eq!(4, queue.clear()?.len());
+        assert!(queue.get_messages(&time::DateTime::now())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn queue_lists_held_messages_after_available_ones() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let now = time::DateTime::new_from_unix_timestamp(1000);
+        let held = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("held")?)?,
+            Priority::Scheduled,
+            now.clone(),
+        )
+        .with_not_before(&now + time::Duration::new_from_hours(1));
+        queue.add_message(held.clone())?;
+        let mut public = vec![];
+        for text in ["public 1", "public 2"] {
+            let message = QueuedMessage::new(
+                MessageId::generate(),
+                encoding.encode(&Message::new(text)?)?,
+                Priority::Public,
+                now.clone(),
+            );
+            queue.add_message(message.clone())?;
+            public.push(message);
+        }
+
+        let ids = |now: &time::DateTime| -> Result<Vec<MessageId>> {
+            Ok(queue
+                .get_messages(now)?
+                .iter()
+                .map(|v| v.id().clone())
+                .collect())
+        };
+        assert_eq!(
+            vec![
+                public[0].id().clone(),
+                public[1].id().clone(),
+                held.id().clone()
+            ],
+            ids(&now)?
+        );
+
+        queue.move_message(public[1].id(), 0, &now)?;
+        queue.move_message(held.id(), 0, &now)?;
+        assert_eq!(
+            vec![
+                public[1].id().clone(),
+                public[0].id().clone(),
+                held.id().clone()
+            ],
+            ids(&now)?
+        );
+
+        let later = &now + time::Duration::new_from_hours(2);
+        assert_eq!(
+            vec![
+                held.id().clone(),
+                public[1].id().clone(),
+                public[0].id().clone()
+            ],
+            ids(&later)?
+        );
+        assert_eq!(
+            Some(public[1].id()),
+            queue.pop_message(&now)?.as_ref().map(|v| v.id())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn records_message_lifecycle() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let message_log = MessageLog::new();
+        let config = no_delay_timing().with_message_expiry(time::Duration::new_from_minutes(10));
+        let clacks = Clacks::new(
+            config,
+            queue.clone(),
+            MessagesToInject::new(vec![], Encoding::default(), time::TimeZone::UTC)?,
+            message_log.clone(),
+        );
+
+        let old_message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("old")?)?,
+            Priority::Public,
+            time::DateTime::now() - time::Duration::new_from_hours(1),
+        );
+        let new_message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("new")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(old_message.clone())?;
+        queue.add_message(new_message.clone())?;
+
+        clacks.update()?;
+        let expired = message_log.get(old_message.id()).unwrap();
+        assert_eq!(MessageStatus::Expired, expired.status());
+        assert!(expired.transmission_started_at().is_none());
+        let transmitting = message_log.get(new_message.id()).unwrap();
+        assert_eq!(MessageStatus::Transmitting, transmitting.status());
+        assert_eq!(
+            Some(new_message.id()),
+            clacks.current_message().unwrap().id()
+        );
+
+        while clacks.current_message().is_some() {
+            clacks.update()?;
+        }
+        let transmitted = message_log.get(new_message.id()).unwrap();
+        assert_eq!(MessageStatus::Transmitted, transmitted.status());
+        assert!(transmitted.finished_at().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn aborts_message_with_cancel_symbol() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let message_log = MessageLog::new();
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(vec![], Encoding::default(), time::TimeZone::UTC)?,
+            message_log.clone(),
+        );
+
+        assert!(matches!(
+            clacks.abort(encoding.encode_cancel()),
+            Err(Error::NoMessageIsBeingTransmitted)
+        ));
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("abc")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(message.clone())?;
+        clacks.update()?;
+        clacks.update()?;
+
+        assert_eq!(
+            Some(message.id().clone()),
+            clacks.abort(encoding.encode_cancel())?
+        );
+        let current_message = clacks.current_message().unwrap();
+        assert_eq!(1, current_message.before().len());
+        assert_eq!(
+            &MessageComponent::Cancel,
+            current_message.current().unwrap().element()
+        );
+        assert!(current_message.after().is_empty());
+        assert!(matches!(clacks.update()?, ClacksUpdateResult::StateChanged));
+        assert_eq!(
+            MessageStatus::Aborted,
+            message_log.get(message.id()).unwrap().status()
+        );
+
+        while clacks.current_message().is_some() {
+            clacks.update()?;
+        }
+        assert_eq!(
+            MessageStatus::Aborted,
+            message_log.get(message.id()).unwrap().status()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn continues_interrupted_message_after_urgent_message() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(vec![], Encoding::default(), time::TimeZone::UTC)?,
+            MessageLog::new(),
+        );
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("abc")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(message.clone())?;
+        clacks.update()?;
+        clacks.update()?;
+        clacks.update()?;
+
+        let urgent = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("sos")?)?,
+            Priority::Operator,
+            time::DateTime::now(),
+        );
+        clacks.preempt(
+            urgent.clone(),
+            PreemptMode::Resume,
+            encoding.encode_cancel(),
+            |remaining| encoding.encode_resumption(remaining),
+        )?;
+
+        let current_message = clacks.current_message().unwrap();
+        assert_eq!(Some(urgent.id()), current_message.id());
+        assert_eq!(
+            &MessageComponent::Cancel,
+            current_message.current().unwrap().element()
+        );
+        let interrupted = current_message.interrupted().unwrap();
+        assert_eq!(Some(message.id()), interrupted.id());
+        assert_eq!(&message.message().parts()[..1], interrupted.before());
+        assert_eq!(&message.message().parts()[1..], interrupted.after());
+
+        while clacks.current_message().map(|v| v.id().cloned()) != Some(Some(message.id().clone()))
+        {
+            clacks.update()?;
+        }
+        let current_message = clacks.current_message().unwrap();
+        assert!(current_message.before().is_empty());
+        assert_eq!(
+            Some(&message.message().parts()[1]),
+            current_message.current()
+        );
+        assert!(current_message.interrupted().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn restarts_whole_message_when_its_resumption_is_interrupted() -> Result<()> {
+        let encoding = Encoding::default().with_checksum();
+        let queue = Queue::new(10)?;
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(vec![], Encoding::default(), time::TimeZone::UTC)?,
+            MessageLog::new(),
+        );
+        let is_current = |id: &MessageId| {
+            clacks
+                .current_message()
+                .and_then(|v| v.id().cloned())
+                .as_ref()
+                == Some(id)
+        };
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("abcd")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(message.clone())?;
+        clacks.update()?;
+        clacks.update()?;
+        clacks.update()?;
+
+        for (text, mode) in [
+            ("sos", PreemptMode::Resume),
+            ("mayday", PreemptMode::Restart),
+        ] {
+            clacks.preempt(
+                QueuedMessage::new(
+                    MessageId::generate(),
+                    encoding.encode(&Message::new(text)?)?,
+                    Priority::Operator,
+                    time::DateTime::now(),
+                ),
+                mode,
+                encoding.encode_cancel(),
+                |remaining| encoding.encode_resumption(remaining),
+            )?;
+            while !is_current(message.id()) {
+                clacks.update()?;
+            }
+            clacks.update()?;
+            clacks.update()?;
+        }
+
+        let current_message = clacks.current_message().unwrap();
+        assert_eq!(&message.message().parts()[..1], current_message.before());
+        assert_eq!(
+            Some(&message.message().parts()[1]),
+            current_message.current()
+        );
+        assert_eq!(&message.message().parts()[2..], current_message.after());
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_resumed_messages() -> Result<()> {
+        let encoding = Encoding::new(
+            vec![
+                EncodingPlane::new(
+                    "letters",
+                    Some(Shift::new(
+                        ShutterPositions::new_from_bits(1)?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([
+                        ("A".to_string(), ShutterPositions::new_from_bits(2)?),
+                        ("B".to_string(), ShutterPositions::new_from_bits(3)?),
+                    ]),
+                )?,
+                EncodingPlane::new(
+                    "figures",
+                    Some(Shift::new(
+                        ShutterPositions::new_from_bits(4)?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([
+                        ("1".to_string(), ShutterPositions::new_from_bits(2)?),
+                        ("2".to_string(), ShutterPositions::new_from_bits(3)?),
+                    ]),
+                )?,
+            ],
+            ShutterPositions::new_from_bits(62)?,
+        )?
+        .with_cancel(ShutterPositions::new_with_all_open())?
+        .with_checksum();
+        let queue = Queue::new(10)?;
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(vec![], encoding.clone(), time::TimeZone::UTC)?,
+            MessageLog::new(),
+        );
+        let mut decoder = decoder::Decoder::new(encoding.clone());
+        let mut events = vec![];
+        let mut observe = |clacks: &Clacks| {
+            events.extend(decoder.push(&clacks.get_desired_shutter_positions()));
+        };
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("a12b")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(message)?;
+        // stop in the middle of the figures
+        while clacks.current_message().and_then(|v| v.current().cloned())
+            != Some(EncodedMessagePart::new(
+                MessageComponent::Character("2".into()),
+                Some("figures".into()),
+                ShutterPositions::new_from_bits(3)?,
+            ))
+        {
+            clacks.update()?;
+            observe(&clacks);
+        }
+
+        let urgent = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("b")?)?,
+            Priority::Operator,
+            time::DateTime::now(),
+        );
+        clacks.preempt(
+            urgent,
+            PreemptMode::Resume,
+            encoding.encode_cancel(),
+            |remaining| encoding.encode_resumption(remaining),
+        )?;
+        observe(&clacks);
+        while clacks.current_message().is_some() {
+            clacks.update()?;
+            observe(&clacks);
+        }
+
+        assert_eq!(
+            vec![
+                decoder::DecoderEvent::Cancelled("A1".into()),
+                decoder::DecoderEvent::Message(Message::new("B")?),
+                decoder::DecoderEvent::Message(Message::new("2B")?),
+            ],
+            events
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn restarts_interrupted_message_after_resuming() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let park_position = ShutterPositions::new(&[ShutterLocation::TopLeft])?;
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(
+                vec![MessageToInject::new(MessageTemplate::new("injected")?)],
+                encoding.clone(),
+                time::TimeZone::UTC,
+            )?,
+            MessageLog::new(),
+        )
+        .with_park_position(park_position.clone());
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("abc")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        );
+        queue.add_message(message.clone())?;
+        clacks.update()?;
+        clacks.update()?;
+        clacks.update()?;
+
+        clacks.pause(Pause::new(
+            "maintenance",
+            "alice",
+            true,
+            time::DateTime::now(),
+        ))?;
+        queue.add_message(QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("def")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        ))?;
+        for _ in 0..5 {
+            clacks.update()?;
+        }
+
+        assert_eq!(
+            Some("alice"),
+            clacks.current_pause().as_ref().map(|v| v.paused_by())
+        );
+        assert_eq!(park_position, clacks.get_desired_shutter_positions());
+        assert_eq!(1, queue.get_messages(&time::DateTime::now())?.len());
+        let current_message = clacks.current_message().unwrap();
+        assert!(current_message.current().is_none());
+        assert_eq!(
+            PreemptMode::Restart,
+            current_message.interrupted().unwrap().mode()
+        );
+
+        clacks.resume()?;
+        clacks.update()?;
+
+        assert!(clacks.current_pause().is_none());
+        let current_message = clacks.current_message().unwrap();
+        assert_eq!(Some(message.id()), current_message.id());
+        assert!(current_message.before().is_empty());
+        assert_eq!(
+            Some(&message.message().parts()[0]),
+            current_message.current()
+        );
+        assert!(matches!(clacks.resume(), Err(Error::ClacksIsNotPaused)));
+        Ok(())
+    }
+
+    #[test]
+    fn follows_timing_of_the_clock() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let message_log = MessageLog::new();
+        let clock = time::ManualClock::new(time::DateTime::new_from_unix_timestamp(1000));
+        let clacks = Clacks::new(
+            TimingConfig::new(
+                time::Duration::new_from_seconds(2),
+                time::Duration::new_from_seconds(1),
+                time::Duration::new_from_seconds(3),
+                time::Duration::new_from_seconds(4),
+            ),
+            queue.clone(),
+            MessagesToInject::new(
+                vec![MessageToInject::new(MessageTemplate::new("C")?)],
+                encoding.clone(),
+                time::TimeZone::UTC,
+            )?,
+            message_log.clone(),
+        )
+        .with_clock(clock.clone());
+
+        let message = QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("AB")?)?,
+            Priority::Public,
+            clock.now(),
+        );
+        let id = message.id().clone();
+        queue.add_message(message)?;
+
+        let shown = |seconds| -> Result<Option<MessageComponent>> {
+            clock.advance(&time::Duration::new_from_seconds(seconds));
+            clacks.update()?;
+            Ok(clacks
+                .current_message()
+                .and_then(|v| v.current().map(|v| v.element().clone())))
+        };
+        let character = |c: &str| Some(MessageComponent::Character(c.to_string()));
+
+        assert_eq!(character("A"), shown(0)?);
+        assert_eq!(character("A"), shown(1)?);
+        assert_eq!(None, shown(1)?);
+        assert_eq!(character("B"), shown(1)?);
+        assert_eq!(None, shown(2)?);
+        assert_eq!(Some(MessageComponent::End), shown(1)?);
+        assert_eq!(None, shown(2)?);
+        assert_eq!(
+            MessageStatus::Transmitted,
+            message_log.get(&id).unwrap().status()
+        );
+        // pausing between messages and then waiting before injecting a message
+        assert_eq!(None, shown(3)?);
+        assert_eq!(None, shown(3)?);
+        assert_eq!(character("C"), shown(1)?);
+        Ok(())
+    }
+
+    #[test]
+    fn renders_injected_message_before_transmitting_it() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let clacks = Clacks::new(
+            no_delay_timing(),
+            queue.clone(),
+            MessagesToInject::new(
+                vec![MessageToInject::new(MessageTemplate::new(
+                    "QUEUE {queue_len}",
+                )?)],
+                encoding.clone(),
+                time::TimeZone::UTC,
+            )?,
+            MessageLog::new(),
+        );
+        // held messages count towards the length of the queue
+        queue.add_message(
+            QueuedMessage::new(
+                MessageId::generate(),
+                encoding.encode(&Message::new("later")?)?,
+                Priority::Scheduled,
+                time::DateTime::now(),
+            )
+            .with_not_before(time::DateTime::now() + time::Duration::new_from_hours(1)),
+        )?;
+
+        clacks.update()?;
+
+        // injected messages go through the queue
+        let current_message = clacks.current_message().unwrap();
+        assert!(current_message.id().is_some());
+        assert_eq!(1, queue.get_messages(&time::DateTime::now())?.len());
+        let expected = encoding.encode(&Message::new("QUEUE 1")?)?;
+        assert_eq!(Some(&expected.parts()[0]), current_message.current());
+        assert_eq!(&expected.parts()[1..], current_message.after());
+        Ok(())
+    }
+
+    #[test]
+    fn shows_clock_when_idle_using_its_own_timing() -> Result<()> {
+        let encoding = Encoding::default();
+        let queue = Queue::new(10)?;
+        let new_clacks = || -> Result<Clacks> {
+            let hour = time::Duration::new_from_hours(1);
+            let slow_timing = TimingConfig::new(
+                hour.clone(),
+                hour.clone(),
+                hour.clone(),
+                time::Duration::new_from_seconds(0),
+            );
+            let clock_mode =
+                ClockMode::new(ClockSchedule::Interval(hour))?.with_timing(no_delay_timing());
+            Ok(Clacks::new(
+                slow_timing,
+                queue.clone(),
+                MessagesToInject::new(
+                    vec![MessageToInject::new(MessageTemplate::new("A")?)],
+                    encoding.clone(),
+                    time::TimeZone::UTC,
+                )?,
+                MessageLog::new(),
+            )
+            .with_clock_messages(ClockMessages::new(
+                clock_mode,
+                encoding.clone(),
+                time::TimeZone::UTC,
+            )?))
+        };
+
+        // the queue comes first
+        queue.add_message(QueuedMessage::new(
+            MessageId::generate(),
+            encoding.encode(&Message::new("B")?)?,
+            Priority::Public,
+            time::DateTime::now(),
+        ))?;
+        let clacks = new_clacks()?;
+        clacks.update()?;
+        assert!(clacks.current_message().unwrap().id().is_some());
+
+        // the time is shown instead of injecting a message
+        let clacks = new_clacks()?;
+        clacks.update()?;
+        let current_message = clacks.current_message().unwrap();
+        assert!(current_message.id().is_none());
+        let time_len = encoding.encode(&Message::new("12 34")?)?.parts().len();
+        assert_eq!(time_len, 1 + current_message.after().len());
+
+        // the clock timing has no delay unlike the timing of regular messages
+        clacks.update()?;
+        let current_message = clacks.current_message().unwrap();
+        assert!(current_message.current().is_none());
+        assert_eq!(1, current_message.before().len());
+        Ok(())
+    }
+
+    fn no_delay_timing() -> TimingConfig {
+        let no_delay = time::Duration::new_from_seconds(0);
+        TimingConfig::new(
+            no_delay.clone(),
+            no_delay.clone(),
+            no_delay.clone(),
+            no_delay,
+        )
+    }
+
+    #[test]
+    fn encode_inserts_shifts_when_plane_changes() -> Result<()> {
+        let encoding = Encoding::new(
+            vec![
+                EncodingPlane::new(
+                    "letters",
+                    Some(Shift::new(
+                        ShutterPositions::new(&[ShutterLocation::BottomLeft])?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([
+                        (
+                            "A".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::TopLeft])?,
+                        ),
+                        (
+                            "B".to_string(),
+                            ShutterPositions::new(&[ShutterLocation::TopRight])?,
+                        ),
+                    ]),
+                )?,
+                EncodingPlane::new(
+                    "figures",
+                    Some(Shift::new(
+                        ShutterPositions::new(&[ShutterLocation::BottomRight])?,
+                        ShiftScope::Locking,
+                    )?),
+                    HashMap::from([(
+                        "1".to_string(),
+                        ShutterPositions::new(&[ShutterLocation::TopLeft])?,
+                    )]),
+                )?,
+            ],
+            ShutterPositions::new_with_all_open(),
+        )?;
+
+        let encoded_message = encoding.encode(&Message::new("a1b")?)?;
+
+        let parts: Vec<(MessageComponent, Option<&str>)> = encoded_message
+            .parts()
+            .iter()
+            .map(|part| (part.element().clone(), part.plane()))
+            .collect();
+        assert_eq!(
+            vec![
+                (MessageComponent::Character("A".into()), Some("letters")),
+                (MessageComponent::Shift, Some("figures")),
+                (MessageComponent::Character("1".into()), Some("figures")),
+                (MessageComponent::Shift, Some("letters")),
+                (MessageComponent::Character("B".into()), Some("letters")),
+                (MessageComponent::End, None),
+            ],
+            parts
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn version_changes_with_the_encoding_table() -> Result<()> {
+        let encoding_with_scope = |scope: ShiftScope| -> Result<Encoding> {
+            Encoding::new(
+                vec![
+                    EncodingPlane::new(
+                        "letters",
+                        Some(Shift::new(
+                            ShutterPositions::new_from_bits(3)?,
+                            ShiftScope::Locking,
+                        )?),
+                        HashMap::from([("A".to_string(), ShutterPositions::new_from_bits(1)?)]),
+                    )?,
+                    EncodingPlane::new(
+                        "figures",
+                        Some(Shift::new(ShutterPositions::new_from_bits(2)?, scope)?),
+                        HashMap::from([("1".to_string(), ShutterPositions::new_from_bits(1)?)]),
+                    )?,
+                ],
+                ShutterPositions::new_with_all_open(),
+            )
+        };
+
+        let locking = encoding_with_scope(ShiftScope::Locking)?.version();
+        assert_eq!(locking, encoding_with_scope(ShiftScope::Locking)?.version());
+        assert_ne!(
+            locking,
+            encoding_with_scope(ShiftScope::Character)?.version()
+        );
+        assert_ne!(locking, encoding_with_scope(ShiftScope::Run)?.version());
+        assert_ne!(
+            locking,
+            encoding_with_scope(ShiftScope::Locking)?
+                .with_checksum()
+                .version()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_prefers_longest_phrases() -> Result<()> {
+        let characters: HashMap<String, ShutterPositions> = ["G", "N", "U", " ", "GNU"]
+            .iter()
+            .enumerate()
+            .map(|(i, character)| {
+                Ok((
+                    character.to_string(),
+                    ShutterPositions::new_from_bits(i as u8 + 1)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let encoding = Encoding::new(
+            vec![
+                EncodingPlane::new(
+                    "letters",
+                    Some(Shift::new(
+                        ShutterPositions::new_from_bits(62)?,
+                        ShiftScope::Locking,
+                    )?),
+                    characters,
+                )?,
+                EncodingPlane::new(
+                    "phrases",
+                    Some(Shift::new(
+                        ShutterPositions::new_from_bits(61)?,
+                        ShiftScope::Character,
+                    )?),
+                    HashMap::from([("PRATCHETT".to_string(), ShutterPositions::new_from_bits(1)?)]),
+                )?,
+            ],
+            ShutterPositions::new_with_all_open(),
+        )?;
+
+        let encoded_message = encoding.encode(&Message::new("gnu gn pratchett")?)?;
+
+        let parts: Vec<(MessageComponent, Option<&str>)> = encoded_message
+            .parts()
+            .iter()
+            .map(|part| (part.element().clone(), part.plane()))
+            .collect();
+        assert_eq!(
+            vec![
+                (MessageComponent::Character("GNU".into()), Some("letters")),
+                (MessageComponent::Character(" ".into()), Some("letters")),
+                (MessageComponent::Character("G".into()), Some("letters")),
+                (MessageComponent::Character("N".into()), Some("letters")),
+                (MessageComponent::Character(" ".into()), Some("letters")),
+                (MessageComponent::Shift, Some("phrases")),
+                (
+                    MessageComponent::Character("PRATCHETT".into()),
+                    Some("phrases")
+                ),
+                (MessageComponent::End, None),
+            ],
+            parts
+        );
+        let mut phrases = encoding.phrases();
+        phrases.sort();
+        assert_eq!(vec!["GNU".to_string(), "PRATCHETT".to_string()], phrases);
+        Ok(())
+    }
 }
@@ -0,0 +1,255 @@
+use crate::domain::{Encoding, EncodingPlane, ShutterPositions};
+use crate::errors::Result;
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+/// How often each token of an encoding appears in a corpus. Every non-empty line of the corpus
+/// is treated as a separate message.
+pub struct Frequencies {
+    tokens: HashMap<String, u64>,
+    messages: u64,
+    skipped_characters: u64,
+}
+
+impl Frequencies {
+    pub fn new(encoding: &Encoding, corpus: &str) -> Self {
+        let mut tokens: HashMap<String, u64> = HashMap::new();
+        let mut messages = 0;
+        let mut skipped_characters = 0;
+
+        for line in corpus.lines().filter(|line| !line.trim().is_empty()) {
+            messages += 1;
+
+            let characters: Vec<(char, String)> = line
+                .chars()
+                .map(|c| (c, String::from(c).to_uppercase()))
+                .collect();
+            let mut rest = characters.as_slice();
+            while !rest.is_empty() {
+                match encoding.next_token(rest) {
+                    Some((token, len)) => {
+                        *tokens.entry(token).or_default() += 1;
+                        rest = &rest[len..];
+                    }
+                    None => {
+                        skipped_characters += 1;
+                        rest = &rest[1..];
+                    }
+                }
+            }
+        }
+
+        Self {
+            tokens,
+            messages,
+            skipped_characters,
+        }
+    }
+
+    pub fn token(&self, token: &str) -> u64 {
+        self.tokens.get(token).copied().unwrap_or(0)
+    }
+
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Returns the number of characters in the corpus which the encoding can't encode.
+    pub fn skipped_characters(&self) -> u64 {
+        self.skipped_characters
+    }
+}
+
+/// Returns the average number of shutters which move per transmitted symbol. All shutters are
+/// closed between characters so every symbol costs twice its number of open shutters.
+pub fn expected_flips(encoding: &Encoding, frequencies: &Frequencies) -> Result<f64> {
+    let plane = single_plane(encoding)?;
+
+    let mut symbols = frequencies.messages();
+    let mut flips = frequencies.messages() * symbol_flips(encoding.message_end());
+    for (token, positions) in plane.characters() {
+        symbols += frequencies.token(token);
+        flips += frequencies.token(token) * symbol_flips(positions);
+    }
+
+    if symbols == 0 {
+        return Ok(0.0);
+    }
+    Ok(flips as f64 / symbols as f64)
+}
+
+/// Reassigns the shutter positions of a single plane encoding so that frequent symbols open as
+/// few shutters as possible while all symbols stay at least the minimum distance apart. The
+/// set of shutter positions is found by picking them greedily and then swapping them for unused
+/// ones for as long as that lowers the cost.
+pub fn optimize(
+    encoding: &Encoding,
+    frequencies: &Frequencies,
+    minimum_distance: u32,
+) -> Result<Encoding> {
+    let plane = single_plane(encoding)?;
+
+    let mut symbols: Vec<(Symbol, u64)> = plane
+        .characters()
+        .keys()
+        .map(|token| (Symbol::Character(token), frequencies.token(token)))
+        .collect();
+    symbols.push((Symbol::End, frequencies.messages()));
+    if encoding.cancel().is_some() {
+        symbols.push((Symbol::Cancel, 0));
+    }
+    symbols.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let weights: Vec<u64> = symbols.iter().map(|(_, frequency)| *frequency).collect();
+
+    let mut candidates: Vec<u8> = (1..1 << 6).collect();
+    candidates.sort_by_key(|bits| (bits.count_ones(), *bits));
+
+    let mut codes: Vec<u8> = vec![];
+    for candidate in &candidates {
+        if codes.len() < symbols.len() && fits(&codes, *candidate, minimum_distance) {
+            codes.push(*candidate);
+        }
+    }
+    if codes.len() < symbols.len() {
+        return Err(anyhow!(
+            "{} symbols don't fit in the shutter positions with a minimum distance of {}",
+            symbols.len(),
+            minimum_distance
+        )
+        .into());
+    }
+
+    let mut best_cost = cost(&codes, &weights);
+    loop {
+        let mut improved = false;
+        for i in 0..codes.len() {
+            for candidate in &candidates {
+                if codes.contains(candidate) {
+                    continue;
+                }
+
+                let mut others = codes.clone();
+                others.remove(i);
+                if !fits(&others, *candidate, minimum_distance) {
+                    continue;
+                }
+
+                let mut new_codes = codes.clone();
+                new_codes[i] = *candidate;
+                let new_cost = cost(&new_codes, &weights);
+                if new_cost < best_cost {
+                    codes = new_codes;
+                    best_cost = new_cost;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    codes.sort_by_key(|bits| (bits.count_ones(), *bits));
+    let mut characters = HashMap::new();
+    let mut message_end = None;
+    let mut cancel = None;
+    for ((symbol, _), bits) in symbols.into_iter().zip(codes) {
+        let positions = ShutterPositions::new_from_bits(bits)?;
+        match symbol {
+            Symbol::Character(token) => {
+                characters.insert(token.clone(), positions);
+            }
+            Symbol::End => message_end = Some(positions),
+            Symbol::Cancel => cancel = Some(positions),
+        }
+    }
+
+    let planes = vec![EncodingPlane::new(plane.name(), None, characters)?];
+    let optimized = Encoding::new(planes, message_end.unwrap())?;
+    let optimized = match cancel {
+        Some(cancel) => optimized.with_cancel(cancel)?,
+        None => optimized,
+    };
+    optimized.check_minimum_distance(minimum_distance)?;
+    if encoding.checksum() {
+        return Ok(optimized.with_checksum());
+    }
+    Ok(optimized)
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Symbol<'a> {
+    Character(&'a String),
+    End,
+    Cancel,
+}
+
+fn single_plane(encoding: &Encoding) -> Result<&EncodingPlane> {
+    match encoding.planes() {
+        [plane] => Ok(plane),
+        _ => Err(anyhow!("only encodings with a single plane can be optimized").into()),
+    }
+}
+
+fn symbol_flips(positions: &ShutterPositions) -> u64 {
+    2 * positions.open_shutters_count() as u64
+}
+
+fn fits(codes: &[u8], candidate: u8, minimum_distance: u32) -> bool {
+    codes
+        .iter()
+        .all(|code| (code ^ candidate).count_ones() >= minimum_distance)
+}
+
+/// Weights are sorted in descending order so the cheapest assignment gives the codes with the
+/// fewest open shutters to the first weights.
+fn cost(codes: &[u8], weights: &[u64]) -> u64 {
+    let mut open_shutters: Vec<u64> = codes.iter().map(|v| u64::from(v.count_ones())).collect();
+    open_shutters.sort();
+    open_shutters
+        .iter()
+        .zip(weights)
+        .map(|(open_shutters, weight)| open_shutters * weight)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::analysis;
+
+    #[test]
+    fn assigns_fewer_open_shutters_to_frequent_characters() -> Result<()> {
+        let characters = [
+            ("E", 0b111100),
+            ("T", 0b110011),
+            ("A", 0b001111),
+            (" ", 0b111111),
+        ]
+        .into_iter()
+        .map(|(character, bits)| {
+            Ok((
+                character.to_string(),
+                ShutterPositions::new_from_bits(bits)?,
+            ))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+        let encoding = Encoding::new(
+            vec![EncodingPlane::new("default", None, characters)?],
+            ShutterPositions::new_from_bits(0b000011)?,
+        )?;
+        let frequencies = Frequencies::new(&encoding, "eee ta\n\nee?\n");
+        assert_eq!(2, frequencies.messages());
+        assert_eq!(1, frequencies.skipped_characters());
+
+        let optimized = optimize(&encoding, &frequencies, 2)?;
+
+        let e = &optimized.planes()[0].characters()["E"];
+        assert_eq!(1, e.open_shutters_count());
+        assert!(analysis::minimum_distance(&optimized).unwrap().0 >= 2);
+        assert!(
+            expected_flips(&optimized, &frequencies)? < expected_flips(&encoding, &frequencies)?
+        );
+        Ok(())
+    }
+}
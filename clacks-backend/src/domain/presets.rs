@@ -0,0 +1,570 @@
+use crate::domain::{
+    DEFAULT_PLANE_NAME, Encoding, EncodingPlane, Shift, ShiftScope, ShutterLocation,
+    ShutterPositions,
+};
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+/// Encodings which are built into the program and can be selected by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingPreset {
+    Clacks,
+    Sixbit,
+    Braille,
+}
+
+impl EncodingPreset {
+    pub fn iter() -> impl Iterator<Item = EncodingPreset> {
+        [
+            EncodingPreset::Clacks,
+            EncodingPreset::Sixbit,
+            EncodingPreset::Braille,
+        ]
+        .into_iter()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncodingPreset::Clacks => "clacks",
+            EncodingPreset::Sixbit => "sixbit",
+            EncodingPreset::Braille => "braille",
+        }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        let encoding = match self {
+            EncodingPreset::Clacks => clacks(),
+            EncodingPreset::Sixbit => sixbit(),
+            EncodingPreset::Braille => braille(),
+        };
+        encoding.with_name(self.name())
+    }
+}
+
+impl TryFrom<&str> for EncodingPreset {
+    type Error = crate::errors::Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        EncodingPreset::iter()
+            .find(|preset| preset.name() == value)
+            .ok_or_else(|| anyhow!("unknown encoding preset: {}", value).into())
+    }
+}
+
+/// The encoding this clacks was originally built with.
+fn clacks() -> Encoding {
+    let mut characters: HashMap<String, ShutterPositions> = HashMap::new();
+    characters.insert(
+        "A".into(),
+        ShutterPositions::new(&[ShutterLocation::MiddleLeft, ShutterLocation::BottomRight])
+            .unwrap(),
+    );
+    characters.insert(
+        "B".into(),
+        ShutterPositions::new(&[ShutterLocation::MiddleRight, ShutterLocation::BottomLeft])
+            .unwrap(),
+    );
+    characters.insert(
+        "C".into(),
+        ShutterPositions::new(&[ShutterLocation::MiddleLeft, ShutterLocation::MiddleRight])
+            .unwrap(),
+    );
+    characters.insert(
+        "D".into(),
+        ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::BottomLeft]).unwrap(),
+    );
+    characters.insert(
+        "E".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "F".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "G".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "H".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "I".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "J".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "K".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "L".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "M".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "N".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "O".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "P".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "Q".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "R".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "S".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "T".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "U".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "V".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "W".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "X".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomLeft,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "Y".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        "Z".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft,
+            ShutterLocation::MiddleRight,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    );
+    characters.insert(
+        " ".into(),
+        ShutterPositions::new(&[ShutterLocation::BottomLeft]).unwrap(),
+    );
+
+    // custom
+    characters.insert(
+        "1".into(),
+        ShutterPositions::new(&[ShutterLocation::TopLeft]).unwrap(),
+    );
+    characters.insert(
+        "2".into(),
+        ShutterPositions::new(&[ShutterLocation::TopRight]).unwrap(),
+    );
+    characters.insert(
+        "3".into(),
+        ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::TopLeft]).unwrap(),
+    );
+    characters.insert(
+        "4".into(),
+        ShutterPositions::new(&[ShutterLocation::MiddleLeft]).unwrap(),
+    );
+    characters.insert(
+        "5".into(),
+        ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::MiddleLeft]).unwrap(),
+    );
+    characters.insert(
+        "6".into(),
+        ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::MiddleLeft]).unwrap(),
+    );
+    characters.insert(
+        "7".into(),
+        ShutterPositions::new(&[ShutterLocation::MiddleRight]).unwrap(),
+    );
+    characters.insert(
+        "8".into(),
+        ShutterPositions::new(&[ShutterLocation::TopLeft, ShutterLocation::MiddleRight]).unwrap(),
+    );
+    characters.insert(
+        "9".into(),
+        ShutterPositions::new(&[ShutterLocation::TopRight, ShutterLocation::MiddleRight]).unwrap(),
+    );
+    characters.insert(
+        "0".into(),
+        ShutterPositions::new(&[
+            ShutterLocation::MiddleRight,
+            ShutterLocation::TopLeft,
+            ShutterLocation::MiddleLeft,
+        ])
+        .unwrap(),
+    );
+
+    Encoding::new(
+        vec![EncodingPlane::new(DEFAULT_PLANE_NAME, None, characters).unwrap()],
+        ShutterPositions::new(&[
+            ShutterLocation::TopLeft,
+            ShutterLocation::TopRight,
+            ShutterLocation::BottomLeft,
+            ShutterLocation::BottomRight,
+        ])
+        .unwrap(),
+    )
+    .unwrap()
+    .with_cancel(ShutterPositions::new_with_all_open())
+    .unwrap()
+}
+
+/// DEC SIXBIT where each character is shown as its 6-bit value, see
+/// [ShutterPositions::new_from_bits]. Code 0 is the space in SIXBIT but it would leave all
+/// shutters closed so the space takes the code of '_' (63) instead and the message end takes
+/// the code of '^' (62). Neither '^' nor '_' can be encoded.
+fn sixbit() -> Encoding {
+    let mut characters: HashMap<String, ShutterPositions> = HashMap::new();
+    for bits in 1..62u8 {
+        let character = char::from(b' ' + bits);
+        characters.insert(
+            character.into(),
+            ShutterPositions::new_from_bits(bits).unwrap(),
+        );
+    }
+    characters.insert(" ".into(), ShutterPositions::new_from_bits(63).unwrap());
+
+    Encoding::new(
+        vec![EncodingPlane::new(DEFAULT_PLANE_NAME, None, characters).unwrap()],
+        ShutterPositions::new_from_bits(62).unwrap(),
+    )
+    .unwrap()
+}
+
+/// Grade 1 Braille where the shutters are the dots of a Braille cell: dots 1, 2 and 3 are the
+/// left column from the top and dots 4, 5 and 6 are the right column from the top. Messages
+/// don't distinguish upper and lower case so letters are sent without the capital sign (dot 6),
+/// which is only read as a prefix of the next letter when decoding. Digits are preceded by the number sign (dots 3456) which applies until a character which
+/// isn't a digit. The letter sign (dots 56) ends a number explicitly if the next letter would
+/// be read as a digit. A blank cell would leave all shutters closed so the space is shown as
+/// dot 5 and the message end as the full cell.
+fn braille() -> Encoding {
+    let letters = [
+        ("A", "1"),
+        ("B", "12"),
+        ("C", "14"),
+        ("D", "145"),
+        ("E", "15"),
+        ("F", "124"),
+        ("G", "1245"),
+        ("H", "125"),
+        ("I", "24"),
+        ("J", "245"),
+        ("K", "13"),
+        ("L", "123"),
+        ("M", "134"),
+        ("N", "1345"),
+        ("O", "135"),
+        ("P", "1234"),
+        ("Q", "12345"),
+        ("R", "1235"),
+        ("S", "234"),
+        ("T", "2345"),
+        ("U", "136"),
+        ("V", "1236"),
+        ("W", "2456"),
+        ("X", "1346"),
+        ("Y", "13456"),
+        ("Z", "1356"),
+    ];
+    let digits = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "0"];
+    let punctuation = [
+        (" ", "5"),
+        (",", "2"),
+        (".", "256"),
+        ("?", "236"),
+        ("!", "235"),
+        ("'", "3"),
+        ("-", "36"),
+    ];
+
+    let text = letters
+        .iter()
+        .chain(punctuation.iter())
+        .map(|(character, dots)| (character.to_string(), braille_cell(dots)))
+        .collect();
+    let capitals: HashMap<String, ShutterPositions> = letters
+        .iter()
+        .map(|(letter, dots)| (letter.to_string(), braille_cell(dots)))
+        .collect();
+    let numbers = digits
+        .iter()
+        .zip(letters.iter())
+        .map(|(digit, (_, dots))| (digit.to_string(), braille_cell(dots)))
+        .collect();
+
+    Encoding::new(
+        vec![
+            EncodingPlane::new(
+                "text",
+                Some(Shift::new(braille_cell("56"), ShiftScope::Locking).unwrap()),
+                text,
+            )
+            .unwrap(),
+            EncodingPlane::new(
+                "numbers",
+                Some(Shift::new(braille_cell("3456"), ShiftScope::Run).unwrap()),
+                numbers,
+            )
+            .unwrap(),
+            EncodingPlane::new(
+                "capitals",
+                Some(Shift::new(braille_cell("6"), ShiftScope::Character).unwrap()),
+                capitals,
+            )
+            .unwrap(),
+        ],
+        braille_cell("123456"),
+    )
+    .unwrap()
+}
+
+fn braille_cell(dots: &str) -> ShutterPositions {
+    let open_shutters: Vec<ShutterLocation> = dots
+        .chars()
+        .map(|dot| match dot {
+            '1' => ShutterLocation::TopLeft,
+            '2' => ShutterLocation::MiddleLeft,
+            '3' => ShutterLocation::BottomLeft,
+            '4' => ShutterLocation::TopRight,
+            '5' => ShutterLocation::MiddleRight,
+            '6' => ShutterLocation::BottomRight,
+            other => panic!("invalid braille dot: {}", other),
+        })
+        .collect();
+    ShutterPositions::new(&open_shutters).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Message;
+    use crate::domain::decoder::{Decoder, DecoderEvent};
+    use crate::errors::Result;
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in EncodingPreset::iter() {
+            preset.encoding();
+        }
+    }
+
+    #[test]
+    fn presets_can_be_selected_by_name() -> Result<()> {
+        for preset in EncodingPreset::iter() {
+            assert_eq!(preset, EncodingPreset::try_from(preset.name())?);
+        }
+        assert!(EncodingPreset::try_from("semaphore").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn braille_uses_prefixes() -> Result<()> {
+        let encoding = EncodingPreset::Braille.encoding();
+
+        let encoded_message = encoding.encode(&Message::new("a1b2 c")?)?;
+
+        let cells: Vec<&ShutterPositions> = encoded_message
+            .parts()
+            .iter()
+            .map(|part| part.shutter_positions())
+            .collect();
+        assert_eq!(
+            vec![
+                &braille_cell("1"),
+                &braille_cell("3456"),
+                &braille_cell("1"),
+                &braille_cell("56"),
+                &braille_cell("12"),
+                &braille_cell("3456"),
+                &braille_cell("12"),
+                &braille_cell("5"),
+                &braille_cell("14"),
+                &braille_cell("123456"),
+            ],
+            cells
+        );
+
+        let mut decoder = Decoder::new(encoding);
+        let events: Vec<DecoderEvent> = cells
+            .into_iter()
+            .flat_map(|cell| decoder.push(cell))
+            .collect();
+        assert_eq!(vec![DecoderEvent::Message(Message::new("A1B2 C")?)], events);
+        Ok(())
+    }
+
+    #[test]
+    fn braille_reads_the_capital_sign() -> Result<()> {
+        let encoding = EncodingPreset::Braille.encoding();
+        let cells = [
+            braille_cell("6"),
+            braille_cell("125"),
+            braille_cell("24"),
+            braille_cell("5"),
+            braille_cell("3456"),
+            braille_cell("1"),
+            braille_cell("6"),
+            braille_cell("1"),
+            braille_cell("123456"),
+        ];
+
+        let mut decoder = Decoder::new(encoding.clone());
+        let events: Vec<DecoderEvent> = cells.iter().flat_map(|cell| decoder.push(cell)).collect();
+        let message = Message::new("HI 1A")?;
+        assert_eq!(vec![DecoderEvent::Message(message.clone())], events);
+
+        let mut decoder = Decoder::new(encoding.clone());
+        let events: Vec<DecoderEvent> = encoding
+            .encode(&message)?
+            .parts()
+            .iter()
+            .flat_map(|part| decoder.push(part.shutter_positions()))
+            .collect();
+        assert_eq!(vec![DecoderEvent::Message(message)], events);
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::domain::time::DateTime;
+use crate::domain::windows::TimeWindows;
+use crate::errors::Result;
+use anyhow::anyhow;
+
+/// Decides what happens to public submissions during quiet hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuietHoursSubmissions {
+    Reject,
+    /// Messages are queued and transmitted once the quiet hours are over.
+    Hold,
+}
+
+/// Quiet hours which are currently in effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuietPeriod {
+    until: DateTime,
+    submissions: QuietHoursSubmissions,
+}
+
+impl QuietPeriod {
+    pub fn until(&self) -> &DateTime {
+        &self.until
+    }
+
+    pub fn submissions(&self) -> QuietHoursSubmissions {
+        self.submissions
+    }
+}
+
+/// Periods during which the clacks doesn't start transmitting messages, e.g. at night.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    windows: TimeWindows,
+    submissions: QuietHoursSubmissions,
+}
+
+impl QuietHours {
+    pub fn new(windows: TimeWindows, submissions: QuietHoursSubmissions) -> Result<Self> {
+        if windows.cover_every_moment()? {
+            return Err(anyhow!("quiet hours must leave some time to transmit").into());
+        }
+        Ok(Self {
+            windows,
+            submissions,
+        })
+    }
+
+    pub fn windows(&self) -> &TimeWindows {
+        &self.windows
+    }
+
+    pub fn submissions(&self) -> QuietHoursSubmissions {
+        self.submissions
+    }
+
+    pub fn is_quiet(&self, time: &DateTime) -> bool {
+        self.windows.contains(time)
+    }
+
+    pub fn period_at(&self, time: &DateTime) -> Option<QuietPeriod> {
+        self.quiet_until(time).map(|until| QuietPeriod {
+            until,
+            submissions: self.submissions,
+        })
+    }
+
+    /// Returns the time at which the quiet hours which are in effect at the given time end.
+    pub fn quiet_until(&self, time: &DateTime) -> Option<DateTime> {
+        self.windows.end_of_period(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::time::TimeZone;
+    use crate::domain::windows::{TimeOfDay, TimeWindow, Weekday};
+
+    #[test]
+    fn quiet_hours_must_not_last_forever() -> Result<()> {
+        let every_day = [
+            Weekday::Monday,
+            Weekday::Tuesday,
+            Weekday::Wednesday,
+            Weekday::Thursday,
+            Weekday::Friday,
+            Weekday::Saturday,
+            Weekday::Sunday,
+        ];
+        let window = TimeWindow::new(&every_day, TimeOfDay::new(8, 0)?, TimeOfDay::new(8, 0)?)?;
+        let windows = TimeWindows::new(TimeZone::UTC, vec![window]);
+        assert!(QuietHours::new(windows, QuietHoursSubmissions::Hold).is_err());
+
+        let window = TimeWindow::new(&every_day, TimeOfDay::new(8, 0)?, TimeOfDay::new(7, 59)?)?;
+        let windows = TimeWindows::new(TimeZone::UTC, vec![window]);
+        QuietHours::new(windows, QuietHoursSubmissions::Hold)?;
+        Ok(())
+    }
+}
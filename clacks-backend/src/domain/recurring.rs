@@ -0,0 +1,638 @@
+use crate::domain::Message;
+use crate::domain::time::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone};
+use crate::errors::{Error, Result};
+use anyhow::anyhow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+// long enough to find the next 29th of February which falls on a specific weekday
+const SEARCH_DAYS: usize = 366 * 28;
+
+// runs which are noticed later than this were missed while the clacks wasn't running
+const MISSED_AFTER_SECONDS: u64 = 60;
+
+// limits the number of runs caught up after a long downtime
+const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// Cron-style schedule consisting of five fields: minute, hour, day of the month, month and day
+/// of the week. Fields accept `*`, values, ranges, lists and steps e.g. `0 9-17/2 * * 1-5`. The
+/// shortcuts `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly` are supported as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // like in cron a day matches if either of them matches when both are restricted
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl Schedule {
+    pub fn new(expression: impl Into<String>) -> Result<Self> {
+        let expression = expression.into();
+        let fields: Vec<&str> = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        }
+        .split_whitespace()
+        .collect();
+        if fields.len() != 5 {
+            return Err(anyhow!("schedule '{}' must have five fields", expression).into());
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        // both 0 and 7 mean Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        let schedule = Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            days_of_month_restricted: !fields[2].starts_with('*'),
+            days_of_week_restricted: !fields[4].starts_with('*'),
+            expression,
+        };
+
+        let start =
+            NaiveDateTime::new_from_ymdhms(2000, 1, 1, 0, 0, 0).attach_timezone(TimeZone::UTC);
+        if schedule.next_after(&start, &TimeZone::UTC).is_none() {
+            return Err(anyhow!("schedule '{}' never matches", schedule.expression).into());
+        }
+        Ok(schedule)
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the first time after the given one at which the schedule matches the clock in
+    /// the time zone. See [`NaiveDateTime::resolve_in_timezone`] for how times affected by
+    /// daylight saving time are handled. Times which occur twice only match once.
+    pub fn next_after(&self, after: &DateTime, time_zone: &TimeZone) -> Option<DateTime> {
+        let local = after.in_timezone(time_zone.clone()).naive_local();
+        let mut date = local.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_date(&date) {
+                for hour in set_bits(self.hours, 24) {
+                    for minute in set_bits(self.minutes, 60) {
+                        let candidate =
+                            NaiveDateTime::new_from_date_and_time(&date, hour, minute).ok()?;
+                        if candidate <= local {
+                            continue;
+                        }
+                        let resolved = candidate.resolve_in_timezone(time_zone);
+                        if &resolved > after {
+                            return Some(resolved);
+                        }
+                    }
+                }
+            }
+            date += Duration::new_from_days(1);
+        }
+        None
+    }
+
+    fn matches_date(&self, date: &NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday()) != 0;
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let invalid = || -> Error { anyhow!("invalid schedule field '{}'", field).into() };
+    let parse = |v: &str| v.parse::<u32>().map_err(|_| invalid());
+
+    let mut bits = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, parse(step)?),
+            None => (item, 1),
+        };
+        let (start, end) = match (range, range.split_once('-')) {
+            ("*", _) => (min, max),
+            (_, Some((start, end))) => (parse(start)?, parse(end)?),
+            // `5/15` means every 15 starting at 5
+            (start, None) if item.contains('/') => (parse(start)?, max),
+            (value, None) => (parse(value)?, parse(value)?),
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn set_bits(bits: u64, len: u32) -> impl Iterator<Item = u32> {
+    (0..len).filter(move |v| bits & (1 << v) != 0)
+}
+
+/// Decides what happens to runs which were missed while the clacks wasn't running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Missed runs are dropped.
+    Skip,
+    /// Only the latest of the missed runs is transmitted.
+    Once,
+    /// All missed runs are transmitted.
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurringMessage {
+    name: String,
+    schedule: Schedule,
+    message: Message,
+    catch_up: CatchUp,
+}
+
+impl RecurringMessage {
+    /// Names identify recurring messages and can only contain letters, digits, `-` and `_`.
+    pub fn new(
+        name: impl Into<String>,
+        schedule: Schedule,
+        message: Message,
+        catch_up: CatchUp,
+    ) -> Result<Self> {
+        let name = name.into();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!("invalid recurring message name: '{}'", name).into());
+        }
+        Ok(Self {
+            name,
+            schedule,
+            message,
+            catch_up,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn catch_up(&self) -> CatchUp {
+        self.catch_up
+    }
+}
+
+/// Recurring message which is due for transmission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurringRun {
+    name: String,
+    message: Message,
+    scheduled_for: DateTime,
+}
+
+impl RecurringRun {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn scheduled_for(&self) -> &DateTime {
+        &self.scheduled_for
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecurringMessageEntry {
+    message: RecurringMessage,
+    configured: bool,
+    next_run: Option<DateTime>,
+}
+
+impl RecurringMessageEntry {
+    pub fn message(&self) -> &RecurringMessage {
+        &self.message
+    }
+
+    /// Configured messages come from the config file and can't be removed using the API.
+    pub fn configured(&self) -> bool {
+        self.configured
+    }
+
+    pub fn next_run(&self) -> Option<&DateTime> {
+        self.next_run.as_ref()
+    }
+}
+
+/// Messages added using the API and the time of the last handled run of every message so that
+/// missed runs can be caught up after a restart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecurringMessagesSnapshot {
+    added: Vec<RecurringMessage>,
+    handled_until: HashMap<String, DateTime>,
+}
+
+impl RecurringMessagesSnapshot {
+    pub fn new(added: Vec<RecurringMessage>, handled_until: HashMap<String, DateTime>) -> Self {
+        Self {
+            added,
+            handled_until,
+        }
+    }
+
+    pub fn added(&self) -> &[RecurringMessage] {
+        &self.added
+    }
+
+    pub fn handled_until(&self) -> &HashMap<String, DateTime> {
+        &self.handled_until
+    }
+}
+
+/// Keeps recurring messages across restarts.
+pub trait RecurringMessagesStorage: Send + Sync {
+    fn load(&self) -> Result<RecurringMessagesSnapshot>;
+    fn save(&self, snapshot: &RecurringMessagesSnapshot) -> Result<()>;
+}
+
+struct Entry {
+    message: RecurringMessage,
+    configured: bool,
+    // all runs up to this time were handled
+    handled_until: DateTime,
+    next_run: Option<DateTime>,
+}
+
+impl Entry {
+    fn new(
+        message: RecurringMessage,
+        configured: bool,
+        handled_until: DateTime,
+        time_zone: &TimeZone,
+    ) -> Self {
+        let next_run = message.schedule.next_after(&handled_until, time_zone);
+        Self {
+            message,
+            configured,
+            handled_until,
+            next_run,
+        }
+    }
+}
+
+/// Evaluates the schedules of recurring messages in the configured time zone.
+#[derive(Clone)]
+pub struct RecurringMessages {
+    entries: Arc<Mutex<Vec<Entry>>>,
+    time_zone: TimeZone,
+    storage: Option<Arc<dyn RecurringMessagesStorage>>,
+}
+
+impl RecurringMessages {
+    pub fn new(
+        time_zone: TimeZone,
+        configured: Vec<RecurringMessage>,
+        now: &DateTime,
+    ) -> Result<Self> {
+        let recurring_messages = Self {
+            entries: Arc::new(Mutex::new(vec![])),
+            time_zone,
+            storage: None,
+        };
+        recurring_messages.restore(configured, RecurringMessagesSnapshot::default(), now)?;
+        Ok(recurring_messages)
+    }
+
+    /// Restores messages added using the API and catches up on runs missed since the last
+    /// snapshot was saved.
+    pub fn new_with_storage(
+        time_zone: TimeZone,
+        configured: Vec<RecurringMessage>,
+        storage: impl RecurringMessagesStorage + 'static,
+        now: &DateTime,
+    ) -> Result<Self> {
+        let snapshot = storage.load()?;
+        let recurring_messages = Self {
+            entries: Arc::new(Mutex::new(vec![])),
+            time_zone,
+            storage: Some(Arc::new(storage)),
+        };
+        recurring_messages.restore(configured, snapshot, now)?;
+        Ok(recurring_messages)
+    }
+
+    fn restore(
+        &self,
+        configured: Vec<RecurringMessage>,
+        snapshot: RecurringMessagesSnapshot,
+        now: &DateTime,
+    ) -> Result<()> {
+        let mut entries: Vec<Entry> = vec![];
+        let messages = configured
+            .into_iter()
+            .map(|v| (v, true))
+            .chain(snapshot.added.into_iter().map(|v| (v, false)));
+        for (message, configured) in messages {
+            if entries.iter().any(|v| v.message.name == message.name) {
+                return Err(
+                    anyhow!("recurring message '{}' is defined twice", message.name).into(),
+                );
+            }
+            let handled_until = snapshot
+                .handled_until
+                .get(&message.name)
+                .cloned()
+                .unwrap_or_else(|| now.clone());
+            entries.push(Entry::new(
+                message,
+                configured,
+                handled_until,
+                &self.time_zone,
+            ));
+        }
+
+        self.save(&entries)?;
+        *self.entries.lock().unwrap() = entries;
+        Ok(())
+    }
+
+    /// Returns the runs which are due and applies the catch up policies to runs which were
+    /// missed. The runs are only saved as handled once they are marked as handled so that they
+    /// are caught up on after a restart if they get lost in between.
+    pub fn take_due(&self, now: &DateTime) -> Result<Vec<RecurringRun>> {
+        let mut entries = self.entries.lock().unwrap();
+        let missed_before = now - Duration::new_from_seconds(MISSED_AFTER_SECONDS);
+
+        let mut runs = vec![];
+        for entry in entries.iter_mut() {
+            let mut due = VecDeque::new();
+            while let Some(next_run) = entry.next_run.clone()
+                && &next_run <= now
+            {
+                if due.len() == MAX_CATCH_UP_RUNS {
+                    due.pop_front();
+                }
+                due.push_back(next_run.clone());
+                entry.next_run = entry
+                    .message
+                    .schedule
+                    .next_after(&next_run, &self.time_zone);
+            }
+
+            let due: Vec<DateTime> = match entry.message.catch_up {
+                CatchUp::Skip => due.into_iter().filter(|v| v >= &missed_before).collect(),
+                CatchUp::Once => due.pop_back().into_iter().collect(),
+                CatchUp::All => due.into(),
+            };
+            runs.extend(due.into_iter().map(|scheduled_for| RecurringRun {
+                name: entry.message.name.clone(),
+                message: entry.message.message.clone(),
+                scheduled_for,
+            }));
+        }
+        Ok(runs)
+    }
+
+    /// Saves that the run and all runs of the message before it were handled.
+    pub fn mark_handled(&self, run: &RecurringRun) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.iter_mut().find(|v| v.message.name == run.name) else {
+            // the message was removed in the meantime
+            return Ok(());
+        };
+        if entry.handled_until >= run.scheduled_for {
+            return Ok(());
+        }
+
+        let handled_until = std::mem::replace(&mut entry.handled_until, run.scheduled_for.clone());
+        let result = self.save(&entries);
+        if result.is_err()
+            && let Some(entry) = entries.iter_mut().find(|v| v.message.name == run.name)
+        {
+            entry.handled_until = handled_until;
+        }
+        result
+    }
+
+    pub fn add(&self, message: RecurringMessage, now: &DateTime) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.iter().any(|v| v.message.name == message.name) {
+            return Err(Error::RecurringMessageAlreadyExists(message.name));
+        }
+
+        entries.push(Entry::new(message, false, now.clone(), &self.time_zone));
+        let result = self.save(&entries);
+        if result.is_err() {
+            entries.pop();
+        }
+        result
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries
+            .iter()
+            .position(|v| v.message.name == name)
+            .ok_or_else(|| Error::RecurringMessageNotFound(name.to_string()))?;
+        if entries[index].configured {
+            return Err(Error::RecurringMessageIsConfigured(name.to_string()));
+        }
+
+        let entry = entries.remove(index);
+        let result = self.save(&entries);
+        if result.is_err() {
+            entries.insert(index, entry);
+        }
+        result
+    }
+
+    /// Returns the recurring messages ordered by their next run.
+    pub fn list(&self) -> Vec<RecurringMessageEntry> {
+        let entries = self.entries.lock().unwrap();
+        let mut list: Vec<RecurringMessageEntry> = entries
+            .iter()
+            .map(|v| RecurringMessageEntry {
+                message: v.message.clone(),
+                configured: v.configured,
+                next_run: v.next_run.clone(),
+            })
+            .collect();
+        list.sort_by(|a, b| match (&a.next_run, &b.next_run) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        list
+    }
+
+    fn save(&self, entries: &[Entry]) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        storage.save(&RecurringMessagesSnapshot {
+            added: entries
+                .iter()
+                .filter(|v| !v.configured)
+                .map(|v| v.message.clone())
+                .collect(),
+            handled_until: entries
+                .iter()
+                .map(|v| (v.message.name.clone(), v.handled_until.clone()))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_finds_next_run_across_daylight_saving_time_changes() -> Result<()> {
+        let time_zone = TimeZone::new_from_name("Europe/Berlin")?;
+        let daily = Schedule::new("30 2 * * *")?;
+
+        // the clocks go forward from 02:00 to 03:00 on the 30th of March 2025
+        let before_spring = DateTime::new_from_str("2025-03-29T12:00:00+01:00", "%+")?;
+        let spring = daily.next_after(&before_spring, &time_zone).unwrap();
+        assert_eq!("2025-03-30T03:30:00+02:00", spring.format("%+"));
+        let after_spring = daily.next_after(&spring, &time_zone).unwrap();
+        assert_eq!("2025-03-31T02:30:00+02:00", after_spring.format("%+"));
+
+        // the clocks go back from 03:00 to 02:00 on the 26th of October 2025
+        let before_autumn = DateTime::new_from_str("2025-10-25T12:00:00+02:00", "%+")?;
+        let autumn = daily.next_after(&before_autumn, &time_zone).unwrap();
+        assert_eq!("2025-10-26T02:30:00+02:00", autumn.format("%+"));
+        let after_autumn = daily.next_after(&autumn, &time_zone).unwrap();
+        assert_eq!("2025-10-27T02:30:00+01:00", after_autumn.format("%+"));
+        Ok(())
+    }
+
+    #[test]
+    fn schedule_matches_either_restricted_day() -> Result<()> {
+        // the 13th of every month and every Friday
+        let schedule = Schedule::new("0 12 13 * 5")?;
+        let after = DateTime::new_from_str("2025-06-07T00:00:00+00:00", "%+")?;
+
+        let first = schedule.next_after(&after, &TimeZone::UTC).unwrap();
+        let second = schedule.next_after(&first, &TimeZone::UTC).unwrap();
+
+        assert_eq!("2025-06-13T12:00:00+00:00", first.format("%+"));
+        assert_eq!("2025-06-20T12:00:00+00:00", second.format("%+"));
+        assert!(Schedule::new("0 0 30 2 *").is_err());
+        assert!(Schedule::new("61 * * * *").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn applies_catch_up_policies_to_missed_runs() -> Result<()> {
+        let start = DateTime::new_from_str("2025-06-07T00:00:30+00:00", "%+")?;
+        let hourly = Schedule::new("@hourly")?;
+        let recurring_messages = RecurringMessages::new(
+            TimeZone::UTC,
+            [
+                ("skip", CatchUp::Skip),
+                ("once", CatchUp::Once),
+                ("all", CatchUp::All),
+            ]
+            .into_iter()
+            .map(|(name, catch_up)| {
+                RecurringMessage::new(name, hourly.clone(), Message::new(name)?, catch_up)
+            })
+            .collect::<Result<Vec<_>>>()?,
+            &start,
+        )?;
+
+        let now = DateTime::new_from_str("2025-06-07T03:00:10+00:00", "%+")?;
+        let runs = recurring_messages.take_due(&now)?;
+
+        let count = |name: &str| runs.iter().filter(|v| v.name() == name).count();
+        assert_eq!(1, count("skip"));
+        assert_eq!(1, count("once"));
+        assert_eq!(3, count("all"));
+        assert!(recurring_messages.take_due(&now)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn catches_up_on_runs_which_were_not_marked_as_handled() -> Result<()> {
+        let storage = MemoryStorage::default();
+        let start = DateTime::new_from_str("2025-06-07T00:00:30+00:00", "%+")?;
+        let now = DateTime::new_from_str("2025-06-07T01:00:10+00:00", "%+")?;
+        let restore = || {
+            RecurringMessages::new_with_storage(
+                TimeZone::UTC,
+                vec![RecurringMessage::new(
+                    "hourly",
+                    Schedule::new("@hourly")?,
+                    Message::new("hourly")?,
+                    CatchUp::Once,
+                )?],
+                storage.clone(),
+                &start,
+            )
+        };
+
+        let runs = restore()?.take_due(&now)?;
+        assert_eq!(1, runs.len());
+
+        // the clacks stopped before the run was queued
+        let recurring_messages = restore()?;
+        let runs = recurring_messages.take_due(&now)?;
+        assert_eq!(1, runs.len());
+        recurring_messages.mark_handled(&runs[0])?;
+
+        assert!(restore()?.take_due(&now)?.is_empty());
+        Ok(())
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStorage {
+        snapshot: Arc<Mutex<RecurringMessagesSnapshot>>,
+    }
+
+    impl RecurringMessagesStorage for MemoryStorage {
+        fn load(&self) -> Result<RecurringMessagesSnapshot> {
+            Ok(self.snapshot.lock().unwrap().clone())
+        }
+
+        fn save(&self, snapshot: &RecurringMessagesSnapshot) -> Result<()> {
+            *self.snapshot.lock().unwrap() = snapshot.clone();
+            Ok(())
+        }
+    }
+}
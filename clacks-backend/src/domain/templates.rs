@@ -0,0 +1,193 @@
+use crate::domain::time::{DateTime, NaiveDateTime, TimeZone};
+use crate::domain::{Encoding, Message};
+use crate::errors::Result;
+use anyhow::anyhow;
+use std::collections::HashSet;
+
+const DEFAULT_TIME_FORMAT: &str = "%H%M";
+
+/// Text with placeholders which are filled in right before the message is transmitted:
+/// `{time}` or `{time:FORMAT}` using a strftime-like format, `{weekday}` and `{queue_len}`.
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    text: String,
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Time(String),
+    Weekday,
+    QueueLen,
+}
+
+impl MessageTemplate {
+    pub fn new(text: impl Into<String>) -> Result<Self> {
+        let text = text.into();
+        if text.is_empty() {
+            return Err(anyhow!("template can't be empty").into());
+        }
+
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => {
+                                return Err(anyhow!("unclosed placeholder in '{}'", text).into());
+                            }
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_placeholder(&placeholder)?);
+                }
+                '}' => return Err(anyhow!("unopened placeholder in '{}'", text).into()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Text(literal));
+        }
+        Ok(Self { text, parts })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn render(&self, values: &TemplateValues) -> Result<Message> {
+        let text: String = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Time(format) => values.time.format(format),
+                TemplatePart::Weekday => values.time.format("%A"),
+                TemplatePart::QueueLen => values.queue_len.to_string(),
+            })
+            .collect();
+        Message::new(text)
+    }
+
+    /// Renders the template for times spread over a whole year and for different queue lengths
+    /// to make sure that whatever it produces can be encoded.
+    pub fn check_encodable(&self, encoding: &Encoding) -> Result<()> {
+        let mut rendered = HashSet::new();
+        let mut queue_len = 0;
+        for month in 1..=12 {
+            // the first seven days of a month fall on every day of the week
+            for day in 1..=7 {
+                for hour in 0..24 {
+                    let minute = (hour * 7 + day) % 60;
+                    let time =
+                        NaiveDateTime::new_from_ymdhms(2024, month, day, hour, minute, minute)
+                            .attach_timezone(TimeZone::UTC);
+                    let message = self
+                        .render(&TemplateValues::new(time, queue_len))
+                        .map_err(|err| anyhow!("template '{}': {}", self.text, err))?;
+                    if rendered.insert(message.text().to_string()) {
+                        encoding.encode(&message).map_err(|err| {
+                            anyhow!(
+                                "template '{}' renders '{}': {}",
+                                self.text,
+                                message.text(),
+                                err
+                            )
+                        })?;
+                    }
+                    queue_len += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<TemplatePart> {
+    match placeholder.split_once(':') {
+        Some(("time", format)) => {
+            DateTime::check_format(format)?;
+            Ok(TemplatePart::Time(format.to_string()))
+        }
+        None if placeholder == "time" => Ok(TemplatePart::Time(DEFAULT_TIME_FORMAT.to_string())),
+        None if placeholder == "weekday" => Ok(TemplatePart::Weekday),
+        None if placeholder == "queue_len" => Ok(TemplatePart::QueueLen),
+        _ => Err(anyhow!("unknown placeholder: {{{}}}", placeholder).into()),
+    }
+}
+
+pub struct TemplateValues {
+    time: DateTime,
+    queue_len: usize,
+}
+
+impl TemplateValues {
+    /// The time should already be in the time zone in which it is supposed to be shown.
+    pub fn new(time: DateTime, queue_len: usize) -> Self {
+        Self { time, queue_len }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_placeholders() -> Result<()> {
+        let time =
+            NaiveDateTime::new_from_ymdhms(2024, 6, 2, 9, 5, 0).attach_timezone(TimeZone::UTC);
+        let values = TemplateValues::new(time, 3);
+
+        for (template, expected) in [
+            ("TIME {time}", "TIME 0905"),
+            ("TIME {time:%H.%M}", "TIME 09.05"),
+            ("{weekday}", "Sunday"),
+            ("QUEUE {queue_len}", "QUEUE 3"),
+            ("{{queue_len}}", "{queue_len}"),
+        ] {
+            let message = MessageTemplate::new(template)?.render(&values)?;
+            assert_eq!(message.text(), expected, "template: {}", template);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_templates() -> Result<()> {
+        for template in ["", "{time", "time}", "{date}", "{time:%Q}"] {
+            assert!(
+                MessageTemplate::new(template).is_err(),
+                "template: {}",
+                template
+            );
+        }
+
+        let encoding = Encoding::default();
+        MessageTemplate::new("{time} {weekday}")?.check_encodable(&encoding)?;
+        MessageTemplate::new("QUEUE {queue_len}")?.check_encodable(&encoding)?;
+        // Wednesday makes it too long
+        let template = MessageTemplate::new("TIME {time} ON {weekday}")?;
+        assert!(template.check_encodable(&encoding).is_err());
+        // the default encoding has no plus sign
+        let template = MessageTemplate::new("TIME {time:%H%M %:z}")?;
+        assert!(template.check_encodable(&encoding).is_err());
+        Ok(())
+    }
+}
@@ -1,15 +1,35 @@
 use crate::errors::Result;
 use anyhow::anyhow;
 use chrono::TimeZone as _;
-use chrono::{Datelike as _, DurationRound};
+use chrono::{Datelike as _, DurationRound, Offset as _, Timelike as _};
 use std::fmt::Display;
-use std::ops::{Add, AddAssign, Sub};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::sync::{Arc, Mutex};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TimeZone {
     UTC,
     Local,
     OffsetEastInSeconds(u64),
     OffsetWestInSeconds(u64),
+    /// Time zone from the IANA database which follows daylight saving time.
+    Named(chrono_tz::Tz),
+}
+
+impl TimeZone {
+    /// Accepts `UTC`, `Local` or a name from the IANA database, e.g. `Europe/London`.
+    pub fn new_from_name(name: &str) -> Result<Self> {
+        match name {
+            "UTC" => Ok(TimeZone::UTC),
+            "Local" => Ok(TimeZone::Local),
+            name => {
+                let tz: chrono_tz::Tz = name
+                    .parse()
+                    .map_err(|_| anyhow!("unknown time zone: {}", name))?;
+                Ok(TimeZone::Named(tz))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Ord)]
@@ -35,6 +55,11 @@ impl NaiveDate {
     pub fn year(&self) -> i32 {
         self.nd.year()
     }
+
+    /// Days since Sunday.
+    pub fn weekday(&self) -> u32 {
+        self.nd.weekday().num_days_from_sunday()
+    }
 }
 
 impl AddAssign<&Duration> for NaiveDate {
@@ -49,6 +74,12 @@ impl AddAssign<Duration> for NaiveDate {
     }
 }
 
+impl SubAssign<Duration> for NaiveDate {
+    fn sub_assign(&mut self, rhs: Duration) {
+        self.nd -= rhs.d;
+    }
+}
+
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Ord)]
 pub struct NaiveDateTime {
     ndt: chrono::NaiveDateTime,
@@ -67,6 +98,47 @@ impl NaiveDateTime {
         Self { ndt }
     }
 
+    pub fn new_from_date_and_time(date: &NaiveDate, hour: u32, min: u32) -> Result<Self> {
+        let time = chrono::NaiveTime::from_hms_opt(hour, min, 0)
+            .ok_or_else(|| anyhow!("error creating naive time"))?;
+        let ndt = chrono::NaiveDateTime::new(date.nd, time);
+        Ok(Self { ndt })
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        NaiveDate {
+            nd: self.ndt.date(),
+        }
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.ndt.hour()
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.ndt.minute()
+    }
+
+    /// Unlike [`NaiveDateTime::attach_timezone`] this handles times which don't exist or are
+    /// ambiguous due to daylight saving time. Times skipped when the clocks go forward are
+    /// moved forward by the length of the gap, ambiguous times resolve to the earlier time.
+    pub fn resolve_in_timezone(&self, timezone: &TimeZone) -> DateTime {
+        let dt = match timezone {
+            TimeZone::UTC => resolve(&chrono::Utc, &self.ndt),
+            TimeZone::Local => resolve(&chrono::Local, &self.ndt),
+            TimeZone::OffsetEastInSeconds(seconds_east) => resolve(
+                &chrono::FixedOffset::east_opt(*seconds_east as i32).unwrap(),
+                &self.ndt,
+            ),
+            TimeZone::OffsetWestInSeconds(seconds_west) => resolve(
+                &chrono::FixedOffset::west_opt(*seconds_west as i32).unwrap(),
+                &self.ndt,
+            ),
+            TimeZone::Named(tz) => resolve(tz, &self.ndt),
+        };
+        DateTime::new(dt)
+    }
+
     pub fn attach_timezone(&self, timezone: TimeZone) -> DateTime {
         match timezone {
             TimeZone::UTC => {
@@ -94,6 +166,27 @@ impl NaiveDateTime {
                     .unwrap();
                 DateTime::new(dt)
             }
+            TimeZone::Named(tz) => {
+                let dt = tz.from_local_datetime(&self.ndt).single().unwrap();
+                DateTime::new(dt.fixed_offset())
+            }
+        }
+    }
+}
+
+fn resolve<Tz: chrono::TimeZone>(
+    tz: &Tz,
+    ndt: &chrono::NaiveDateTime,
+) -> chrono::DateTime<chrono::FixedOffset> {
+    match tz.from_local_datetime(ndt) {
+        chrono::LocalResult::Single(dt) => dt.fixed_offset(),
+        chrono::LocalResult::Ambiguous(earliest, _) => earliest.fixed_offset(),
+        chrono::LocalResult::None => {
+            // use the offset from before the clocks went forward
+            let offset = tz
+                .offset_from_utc_datetime(&(*ndt - chrono::Duration::days(1)))
+                .fix();
+            tz.from_utc_datetime(&(*ndt - offset)).fixed_offset()
         }
     }
 }
@@ -136,10 +229,18 @@ impl DateTime {
             TimeZone::OffsetWestInSeconds(seconds_west) => self
                 .dt
                 .with_timezone(&chrono::FixedOffset::west_opt(seconds_west as i32).unwrap()),
+            TimeZone::Named(tz) => self.dt.with_timezone(&tz).fixed_offset(),
         };
         DateTime::new(dt)
     }
 
+    /// Date and time as shown on a clock in the time zone of this date time.
+    pub fn naive_local(&self) -> NaiveDateTime {
+        NaiveDateTime {
+            ndt: self.dt.naive_local(),
+        }
+    }
+
     pub fn truncate_to_seconds(&self) -> Result<Self> {
         Ok(Self::new(
             self.dt.duration_trunc(chrono::Duration::seconds(1))?,
@@ -161,6 +262,17 @@ impl DateTime {
     pub fn format(&self, format: &str) -> String {
         self.dt.format(format).to_string()
     }
+
+    /// Formatting panics if the format is invalid so formats which aren't known in advance
+    /// should be checked first.
+    pub fn check_format(format: &str) -> Result<()> {
+        let invalid = chrono::format::StrftimeItems::new(format)
+            .any(|item| item == chrono::format::Item::Error);
+        if invalid {
+            return Err(anyhow!("invalid time format: {}", format).into());
+        }
+        Ok(())
+    }
 }
 
 impl Add<&Duration> for DateTime {
@@ -293,3 +405,53 @@ impl Duration {
         self.d.as_seconds_f64()
     }
 }
+
+/// Source of the current time. The clacks reads the time only through its clock so that it can
+/// be tested deterministically and simulated faster than real time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SystemClock {}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::now()
+    }
+}
+
+/// Clock which only moves when it is told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: DateTime) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, duration: &Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = &*now + duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime {
+        self.now.lock().unwrap().clone()
+    }
+}
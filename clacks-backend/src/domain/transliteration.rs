@@ -0,0 +1,228 @@
+use crate::domain::{Encoding, Message};
+use crate::errors::Result;
+use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+const VARIATION_SELECTORS: [char; 2] = ['\u{FE0E}', '\u{FE0F}'];
+
+/// Replaces characters which can't be encoded with similar characters which can be encoded.
+#[derive(Clone)]
+pub struct Transliterator {
+    supported_characters: HashSet<String>,
+    replacements: HashMap<String, String>,
+}
+
+impl Transliterator {
+    /// Replacements are tried before the built-in rules and can replace whole sequences of
+    /// characters, e.g. emoji.
+    pub fn new(encoding: &Encoding, replacements: HashMap<String, String>) -> Result<Self> {
+        let supported_characters: HashSet<String> =
+            encoding.supported_characters().into_iter().collect();
+
+        for (from, to) in &replacements {
+            if from.is_empty() {
+                return Err(anyhow!("replaced text can't be empty").into());
+            }
+
+            if !is_supported(&supported_characters, to) {
+                return Err(anyhow!("replacement '{}' for '{}' can't be encoded", to, from).into());
+            }
+        }
+
+        Ok(Self {
+            supported_characters,
+            replacements,
+        })
+    }
+
+    /// The message is only validated after it was transliterated as transliteration can
+    /// change its length, e.g. emoji are replaced by words.
+    pub fn transliterate(&self, text: &str) -> Result<Transliteration> {
+        let text: String = text
+            .chars()
+            .filter(|c| !VARIATION_SELECTORS.contains(c))
+            .collect();
+
+        let mut result = String::new();
+        let mut substitutions: Vec<Substitution> = vec![];
+        let mut rest = text.as_str();
+        while let Some(c) = rest.chars().next() {
+            let (from, to) = match self.replacement(rest) {
+                Some((from, to)) => (from, to.to_string()),
+                None => {
+                    let from = &rest[..c.len_utf8()];
+                    (from, self.transliterate_character(c))
+                }
+            };
+
+            if from != to {
+                let substitution = Substitution::new(from, to.clone());
+                if !substitutions.contains(&substitution) {
+                    substitutions.push(substitution);
+                }
+            }
+
+            result.push_str(&to);
+            rest = &rest[from.len()..];
+        }
+
+        Ok(Transliteration {
+            message: Message::new(result)?,
+            substitutions,
+        })
+    }
+
+    fn replacement<'a>(&self, text: &'a str) -> Option<(&'a str, &str)> {
+        self.replacements
+            .iter()
+            .filter(|(from, _)| text.starts_with(from.as_str()))
+            .max_by_key(|(from, _)| from.len())
+            .map(|(from, to)| (&text[..from.len()], to.as_str()))
+    }
+
+    fn transliterate_character(&self, c: char) -> String {
+        let original = String::from(c);
+        if is_supported(&self.supported_characters, &original) {
+            return original;
+        }
+
+        let mut candidates = vec![];
+        if let Some(replacement) = builtin_replacement(c) {
+            candidates.push(replacement.to_string());
+        }
+        candidates.push(original.nfkd().filter(|c| !is_combining_mark(*c)).collect());
+
+        candidates
+            .into_iter()
+            .find(|candidate| {
+                !candidate.is_empty() && is_supported(&self.supported_characters, candidate)
+            })
+            .unwrap_or(original)
+    }
+}
+
+fn is_supported(supported_characters: &HashSet<String>, text: &str) -> bool {
+    text.chars()
+        .all(|c| supported_characters.contains(&String::from(c).to_uppercase()))
+}
+
+fn builtin_replacement(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ß' | 'ẞ' => "SS",
+        'æ' | 'Æ' => "AE",
+        'œ' | 'Œ' => "OE",
+        'ø' | 'Ø' => "O",
+        'ł' | 'Ł' => "L",
+        'đ' | 'Đ' => "D",
+        'þ' | 'Þ' => "TH",
+        'ð' | 'Ð' => "D",
+        'ı' => "I",
+        '‘' | '’' | '‚' | '‛' | '′' => "'",
+        '“' | '”' | '„' | '‟' | '″' | '«' | '»' => "\"",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+        '…' => "...",
+        '×' => "X",
+        '&' => "AND",
+        '@' => "AT",
+        '\t' | '\n' | '\r' | '\u{00A0}' => " ",
+        _ => return None,
+    })
+}
+
+pub struct Transliteration {
+    message: Message,
+    substitutions: Vec<Substitution>,
+}
+
+impl Transliteration {
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn substitutions(&self) -> &[Substitution] {
+        &self.substitutions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    from: String,
+    to: String,
+}
+
+impl Substitution {
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::MAX_MESSAGE_LEN_BYTES;
+
+    #[test]
+    fn transliterates_unsupported_characters() -> Result<()> {
+        let transliterator = Transliterator::new(
+            &Encoding::default(),
+            HashMap::from([("🙂".to_string(), "SMILE".to_string())]),
+        )?;
+
+        let transliteration = transliterator.transliterate("Straße café 🙂")?;
+
+        assert_eq!(
+            &Message::new("StraSSe cafe SMILE")?,
+            transliteration.message()
+        );
+        assert_eq!(
+            vec![
+                Substitution::new("ß", "SS"),
+                Substitution::new("é", "e"),
+                Substitution::new("🙂", "SMILE"),
+            ],
+            transliteration.substitutions()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn limits_length_after_transliterating() -> Result<()> {
+        let transliterator = Transliterator::new(&Encoding::default(), HashMap::new())?;
+
+        // each character takes two bytes before it is transliterated
+        let text = "é".repeat(MAX_MESSAGE_LEN_BYTES);
+        let transliteration = transliterator.transliterate(&text)?;
+        assert_eq!(
+            MAX_MESSAGE_LEN_BYTES,
+            transliteration.message().text().len()
+        );
+
+        let text = "e".repeat(MAX_MESSAGE_LEN_BYTES + 1);
+        assert!(transliterator.transliterate(&text).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_characters_which_can_not_be_transliterated() -> Result<()> {
+        let transliterator = Transliterator::new(&Encoding::default(), HashMap::new())?;
+
+        let transliteration = transliterator.transliterate("a?")?;
+
+        assert_eq!(&Message::new("a?")?, transliteration.message());
+        assert!(transliteration.substitutions().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use crate::domain::time::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone};
+use crate::errors::Result;
+use anyhow::anyhow;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    fn days_since_sunday(&self) -> u32 {
+        match self {
+            Weekday::Sunday => 0,
+            Weekday::Monday => 1,
+            Weekday::Tuesday => 2,
+            Weekday::Wednesday => 3,
+            Weekday::Thursday => 4,
+            Weekday::Friday => 5,
+            Weekday::Saturday => 6,
+        }
+    }
+}
+
+/// Wall clock time in the time zone of the windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    hour: u32,
+    minute: u32,
+}
+
+impl TimeOfDay {
+    pub fn new(hour: u32, minute: u32) -> Result<Self> {
+        if hour > 23 || minute > 59 {
+            return Err(anyhow!("invalid time of day {:02}:{:02}", hour, minute).into());
+        }
+        Ok(Self { hour, minute })
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Window which starts on each of the given days. Windows which end before they start, e.g.
+/// 22:00 to 07:00, end on the following day. Windows which end when they start last the whole
+/// day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    days: u8,
+    start: TimeOfDay,
+    end: TimeOfDay,
+}
+
+impl TimeWindow {
+    pub fn new(days: &[Weekday], start: TimeOfDay, end: TimeOfDay) -> Result<Self> {
+        if days.is_empty() {
+            return Err(anyhow!("window from {} to {} has no days", start, end).into());
+        }
+        let days = days
+            .iter()
+            .fold(0, |acc, day| acc | (1 << day.days_since_sunday()));
+        Ok(Self { days, start, end })
+    }
+
+    pub fn start(&self) -> &TimeOfDay {
+        &self.start
+    }
+
+    pub fn end(&self) -> &TimeOfDay {
+        &self.end
+    }
+
+    /// Returns the end of the window if it started on the given date and the time is in it.
+    fn end_if_contains(&self, date: &NaiveDate, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.days & (1 << date.weekday()) == 0 {
+            return None;
+        }
+
+        let start =
+            NaiveDateTime::new_from_date_and_time(date, self.start.hour, self.start.minute).ok()?;
+        let mut end_date = date.clone();
+        if self.end <= self.start {
+            end_date += Duration::new_from_days(1);
+        }
+        let end = NaiveDateTime::new_from_date_and_time(&end_date, self.end.hour, self.end.minute)
+            .ok()?;
+        if &start <= time && time < &end {
+            Some(end)
+        } else {
+            None
+        }
+    }
+}
+
+/// Recurring weekly windows. Windows follow the wall clock in the time zone so that they don't
+/// shift when daylight saving time starts or ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindows {
+    time_zone: TimeZone,
+    windows: Vec<TimeWindow>,
+}
+
+impl TimeWindows {
+    pub fn new(time_zone: TimeZone, windows: Vec<TimeWindow>) -> Self {
+        Self { time_zone, windows }
+    }
+
+    pub fn time_zone(&self) -> &TimeZone {
+        &self.time_zone
+    }
+
+    pub fn windows(&self) -> &[TimeWindow] {
+        &self.windows
+    }
+
+    pub fn contains(&self, time: &DateTime) -> bool {
+        let local = time.in_timezone(self.time_zone.clone()).naive_local();
+        self.containing_window_end(&local).is_some()
+    }
+
+    /// Returns the time at which the windows which contain the given time end. Overlapping and
+    /// adjacent windows are treated as a single period.
+    pub fn end_of_period(&self, time: &DateTime) -> Option<DateTime> {
+        let local = time.in_timezone(self.time_zone.clone()).naive_local();
+        self.local_end(&local)
+            .flatten()
+            .map(|v| v.resolve_in_timezone(&self.time_zone))
+    }
+
+    /// Returns true if the windows cover the whole week so that the period never ends.
+    pub fn cover_every_moment(&self) -> Result<bool> {
+        // a week later everything repeats
+        let mut date = NaiveDate::new_from_ydy(2000, 1)?;
+        for _ in 0..7 {
+            let midnight = NaiveDateTime::new_from_date_and_time(&date, 0, 0)?;
+            if self.local_end(&midnight).is_some_and(|v| v.is_none()) {
+                return Ok(true);
+            }
+            date += Duration::new_from_days(1);
+        }
+        Ok(false)
+    }
+
+    /// Returns `None` if the time isn't in a window and `Some(None)` if the period never ends.
+    fn local_end(&self, time: &NaiveDateTime) -> Option<Option<NaiveDateTime>> {
+        let mut end = self.containing_window_end(time)?;
+        // each iteration moves past at least one window, so this covers more than a week
+        for _ in 0..self.windows.len() * 8 {
+            match self.containing_window_end(&end) {
+                Some(next_end) => end = next_end,
+                None => return Some(Some(end)),
+            }
+        }
+        Some(None)
+    }
+
+    fn containing_window_end(&self, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut previous_day = time.date();
+        previous_day -= Duration::new_from_days(1);
+        [previous_day, time.date()]
+            .iter()
+            .flat_map(|date| {
+                self.windows
+                    .iter()
+                    .filter_map(|window| window.end_if_contains(date, time))
+            })
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_span_midnight_and_merge() -> Result<()> {
+        let weekdays = [
+            Weekday::Monday,
+            Weekday::Tuesday,
+            Weekday::Wednesday,
+            Weekday::Thursday,
+            Weekday::Friday,
+        ];
+        let windows = TimeWindows::new(
+            TimeZone::UTC,
+            vec![
+                TimeWindow::new(&weekdays, TimeOfDay::new(22, 0)?, TimeOfDay::new(7, 0)?)?,
+                TimeWindow::new(
+                    &[Weekday::Saturday],
+                    TimeOfDay::new(0, 0)?,
+                    TimeOfDay::new(10, 0)?,
+                )?,
+            ],
+        );
+
+        let at = |day, hour, min| {
+            NaiveDateTime::new_from_ymdhms(2024, 6, day, hour, min, 0)
+                .attach_timezone(TimeZone::UTC)
+        };
+
+        // 2024-06-03 is a Monday
+        assert_eq!(windows.end_of_period(&at(3, 21, 59)), None);
+        assert_eq!(windows.end_of_period(&at(3, 22, 0)), Some(at(4, 7, 0)));
+        assert_eq!(windows.end_of_period(&at(4, 6, 59)), Some(at(4, 7, 0)));
+        assert_eq!(windows.end_of_period(&at(4, 7, 0)), None);
+        // Friday night runs into Saturday morning
+        assert_eq!(windows.end_of_period(&at(7, 23, 0)), Some(at(8, 10, 0)));
+        // there is no window starting on Sunday
+        assert_eq!(windows.end_of_period(&at(2, 23, 0)), None);
+        assert!(!windows.contains(&at(3, 3, 0)));
+        assert!(windows.contains(&at(4, 3, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn windows_follow_the_wall_clock() -> Result<()> {
+        let time_zone = TimeZone::new_from_name("Europe/Berlin")?;
+        let windows = TimeWindows::new(
+            time_zone.clone(),
+            vec![TimeWindow::new(
+                &[Weekday::Saturday],
+                TimeOfDay::new(23, 0)?,
+                TimeOfDay::new(2, 30)?,
+            )?],
+        );
+
+        // clocks go forward at 02:00 on 2024-03-31, 02:30 doesn't exist that night
+        let start =
+            NaiveDateTime::new_from_ymdhms(2024, 3, 30, 23, 0, 0).resolve_in_timezone(&time_zone);
+        let end =
+            NaiveDateTime::new_from_ymdhms(2024, 3, 31, 3, 30, 0).resolve_in_timezone(&time_zone);
+        assert_eq!(windows.end_of_period(&start), Some(end));
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::domain::time::DateTime;
 use crate::errors::Error::Unknown;
 use anyhow::anyhow;
 use chrono::RoundingError;
@@ -12,6 +13,36 @@ pub enum Error {
     #[error("cannot encode character '{0}'")]
     CannotEncodeCharacter(char),
 
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("unsupported image format: {0}")]
+    UnsupportedImageFormat(String),
+
+    #[error("message not found: {0}")]
+    MessageNotFound(String),
+
+    #[error("no message is being transmitted")]
+    NoMessageIsBeingTransmitted,
+
+    #[error("clacks is paused")]
+    ClacksIsPaused,
+
+    #[error("clacks is not paused")]
+    ClacksIsNotPaused,
+
+    #[error("clacks is quiet until {0}")]
+    ClacksIsQuiet(DateTime),
+
+    #[error("recurring message not found: {0}")]
+    RecurringMessageNotFound(String),
+
+    #[error("recurring message already exists: {0}")]
+    RecurringMessageAlreadyExists(String),
+
+    #[error("recurring message is defined in the config: {0}")]
+    RecurringMessageIsConfigured(String),
+
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
@@ -40,6 +71,18 @@ impl From<toml::de::Error> for Error {
     }
 }
 
+impl From<png::EncodingError> for Error {
+    fn from(value: png::EncodingError) -> Self {
+        Unknown(anyhow!(value))
+    }
+}
+
+impl From<gif::EncodingError> for Error {
+    fn from(value: gif::EncodingError) -> Self {
+        Unknown(anyhow!(value))
+    }
+}
+
 impl From<prometheus::Error> for Error {
     fn from(value: prometheus::Error) -> Self {
         Unknown(anyhow!(value))
@@ -58,6 +101,12 @@ impl From<axum::http::Error> for Error {
     }
 }
 
+impl From<ureq::Error> for Error {
+    fn from(value: ureq::Error) -> Self {
+        Unknown(anyhow!(value))
+    }
+}
+
 impl From<rppal::i2c::Error> for Error {
     fn from(value: rppal::i2c::Error) -> Self {
         Unknown(anyhow!(value))
@@ -1,8 +1,23 @@
-use crate::app::{AddMessageToQueue, Config, GetConfigHandler, GetStateHandler};
-use crate::config::Environment;
+use crate::app::{
+    AbortTransmission, AbortTransmissionHandler, AddMessageToQueue, AddMessageToQueueResult,
+    AddRecurringMessage, AddRecurringMessageHandler, CancelScheduledMessage,
+    CancelScheduledMessageHandler, ClearQueue, ClearQueueHandler, Config, EncodingTable,
+    GetConfigHandler, GetEncodingHandler, GetMessageHandler, GetRecurringMessagesHandler,
+    GetScheduledMessagesHandler, GetStateHandler, Image, ImageFormat, MessageState,
+    MoveMessageInQueue, MoveMessageInQueueHandler, PauseClacks, PauseClacksHandler,
+    PreemptTransmission, PreemptTransmissionHandler, PreemptTransmissionResult,
+    RemoveMessageFromQueue, RemoveMessageFromQueueHandler, RemoveRecurringMessage,
+    RemoveRecurringMessageHandler, RenderChart, RenderFrame, RenderHandler, RenderMessage,
+    ResumeClacks, ResumeClacksHandler,
+};
+use crate::config::{Environment, Operators};
+use crate::domain::recurring::{CatchUp, RecurringMessage, RecurringMessageEntry, Schedule};
+use crate::domain::time::DateTime;
+use crate::domain::transliteration::Substitution;
 use crate::domain::{
-    CurrentMessage, EncodedMessage, EncodedMessagePart, Message, MessageComponent, ShutterLocation,
-    ShutterPositions,
+    CurrentMessage, EncodedMessagePart, EncodingSymbol, InterruptedMessage, Message,
+    MessageComponent, MessageId, MessageStatus, Pause, PreemptMode, Priority, QueuedMessage,
+    ShutterLocation, ShutterPosition, ShutterPositions,
 };
 use crate::errors::{Error, Result};
 use crate::{adapters, app, config};
@@ -10,20 +25,22 @@ use anyhow::anyhow;
 use app::AddMessageToQueueHandler;
 use axum::body::Body;
 use axum::extract::ws::WebSocket;
-use axum::extract::{WebSocketUpgrade, ws};
+use axum::extract::{Path, WebSocketUpgrade, ws};
 use axum::handler::Handler;
-use axum::http::Request;
+use axum::http::{HeaderMap, Request, header};
 use axum::routing::any;
 use axum::{
     Router,
-    routing::{get, post},
+    routing::{delete, get, post},
 };
 use axum::{
     extract::Json,
+    extract::Query,
     extract::State,
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use futures_util::stream::SplitSink;
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use include_dir::File;
 use log::debug;
@@ -75,9 +92,45 @@ impl Server {
 
         let app = Router::new()
             .route("/metrics", get(handle_get_metrics::<D>))
-            .route("/api/queue", post(handle_post_queue::<D>))
+            .route(
+                "/api/queue",
+                post(handle_post_queue::<D>).delete(handle_delete_queue::<D>),
+            )
+            .route("/api/queue/{id}", delete(handle_delete_queued_message::<D>))
+            .route(
+                "/api/queue/{id}/move",
+                post(handle_post_move_queued_message::<D>),
+            )
+            .route("/api/scheduled", get(handle_get_scheduled::<D>))
+            .route(
+                "/api/scheduled/{id}",
+                delete(handle_delete_scheduled_message::<D>),
+            )
+            .route(
+                "/api/recurring",
+                get(handle_get_recurring::<D>).post(handle_post_recurring::<D>),
+            )
+            .route(
+                "/api/recurring/{name}",
+                delete(handle_delete_recurring_message::<D>),
+            )
+            .route("/api/messages/{id}", get(handle_get_message::<D>))
+            .route(
+                "/api/transmission/abort",
+                post(handle_post_abort_transmission::<D>),
+            )
+            .route(
+                "/api/transmission/preempt",
+                post(handle_post_preempt_transmission::<D>),
+            )
+            .route("/api/pause", post(handle_post_pause::<D>))
+            .route("/api/resume", post(handle_post_resume::<D>))
             .route("/api/state-updates", any(handle_state_updates::<D>))
             .route("/api/config", get(handle_get_config::<D>))
+            .route("/api/encoding", get(handle_get_encoding::<D>))
+            .route("/api/render/frame", get(handle_get_render_frame::<D>))
+            .route("/api/render/message", get(handle_get_render_message::<D>))
+            .route("/api/render/chart", get(handle_get_render_chart::<D>))
             .layer(
                 ServiceBuilder::new()
                     .layer(trace.clone())
@@ -151,30 +204,258 @@ where
     Ok(encoder.encode_to_string(&families)?)
 }
 
+/// Only operators can schedule messages as scheduled messages skip the public queue. Messages
+/// submitted by operators are queued ahead of the public ones.
 async fn handle_post_queue<D>(
     State(deps): State<D>,
+    headers: HeaderMap,
     Json(json_body): Json<PostQueueRequest>,
-) -> std::result::Result<(), AppError>
+) -> std::result::Result<Json<TransportAddMessageToQueueResult>, AppError>
+where
+    D: Deps,
+{
+    let priority = match headers.contains_key(header::AUTHORIZATION) {
+        true => {
+            authenticate_operator(&deps, &headers)?;
+            Priority::Operator
+        }
+        false => Priority::Public,
+    };
+    let command = AddMessageToQueue::new(json_body.message, priority);
+    let command = match json_body.not_before {
+        Some(not_before) => {
+            authenticate_operator(&deps, &headers)?;
+            let not_before = DateTime::new_from_str(&not_before, "%+")
+                .map_err(|_| AppError::BadRequest("invalid date".into()))?;
+            command.with_not_before(not_before)
+        }
+        None => command,
+    };
+    let result = deps.add_message_to_queue_handler().handle(command)?;
+    let transport_result: TransportAddMessageToQueueResult = (&result).into();
+    Ok(transport_result.into())
+}
+
+async fn handle_get_scheduled<D>(
+    State(deps): State<D>,
+) -> std::result::Result<Json<Vec<TransportQueuedMessage>>, AppError>
+where
+    D: Deps,
+{
+    let messages = deps
+        .get_scheduled_messages_handler()
+        .get_scheduled_messages()?;
+    Ok(messages.iter().map(|v| v.into()).collect::<Vec<_>>().into())
+}
+
+async fn handle_delete_scheduled_message<D>(
+    State(deps): State<D>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = CancelScheduledMessage::new(message_id(id)?, operator);
+    deps.cancel_scheduled_message_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_get_recurring<D>(
+    State(deps): State<D>,
+) -> std::result::Result<Json<Vec<TransportRecurringMessage>>, AppError>
 where
     D: Deps,
 {
+    let recurring_messages = deps
+        .get_recurring_messages_handler()
+        .get_recurring_messages()?;
+    Ok(recurring_messages
+        .iter()
+        .map(|v| v.into())
+        .collect::<Vec<_>>()
+        .into())
+}
+
+async fn handle_post_recurring<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+    Json(json_body): Json<PostRecurringRequest>,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let schedule = Schedule::new(json_body.schedule)
+        .map_err(|_| AppError::BadRequest("invalid schedule".into()))?;
     let message = Message::new(json_body.message)
         .map_err(|_| AppError::BadRequest("invalid message".into()))?;
-    let command = AddMessageToQueue::new(message);
-    deps.add_message_to_queue_handler().handle(command)?;
-    Ok(())
+    let catch_up = match json_body.catch_up {
+        Some(catch_up) => CatchUp::try_from(catch_up)
+            .map_err(|_| AppError::BadRequest("invalid catch up policy".into()))?,
+        None => CatchUp::Skip,
+    };
+    let recurring_message = RecurringMessage::new(json_body.name, schedule, message, catch_up)
+        .map_err(|_| AppError::BadRequest("invalid name".into()))?;
+    let command = AddRecurringMessage::new(recurring_message, operator);
+    deps.add_recurring_message_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_delete_recurring_message<D>(
+    State(deps): State<D>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = RemoveRecurringMessage::new(name, operator);
+    deps.remove_recurring_message_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_delete_queue<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = ClearQueue::new(operator);
+    deps.clear_queue_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_delete_queued_message<D>(
+    State(deps): State<D>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = RemoveMessageFromQueue::new(message_id(id)?, operator);
+    deps.remove_message_from_queue_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_post_move_queued_message<D>(
+    State(deps): State<D>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(json_body): Json<PostMoveQueuedMessageRequest>,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = MoveMessageInQueue::new(message_id(id)?, json_body.position, operator);
+    deps.move_message_in_queue_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_post_abort_transmission<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = AbortTransmission::new(operator);
+    deps.abort_transmission_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_post_preempt_transmission<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+    Json(json_body): Json<PostPreemptTransmissionRequest>,
+) -> std::result::Result<Json<TransportPreemptTransmissionResult>, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let mode = PreemptMode::try_from(json_body.mode)
+        .map_err(|_| AppError::BadRequest("invalid mode".into()))?;
+    let command = PreemptTransmission::new(json_body.message, mode, operator);
+    let result = deps.preempt_transmission_handler().handle(command)?;
+    let transport_result: TransportPreemptTransmissionResult = (&result).into();
+    Ok(transport_result.into())
+}
+
+async fn handle_post_pause<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+    Json(json_body): Json<PostPauseRequest>,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = PauseClacks::new(json_body.reason, json_body.accept_messages, operator);
+    deps.pause_clacks_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_post_resume<D>(
+    State(deps): State<D>,
+    headers: HeaderMap,
+) -> std::result::Result<StatusCode, AppError>
+where
+    D: Deps,
+{
+    let operator = authenticate_operator(&deps, &headers)?;
+    let command = ResumeClacks::new(operator);
+    deps.resume_clacks_handler().handle(command)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Operators authenticate using the `Authorization: Bearer <token>` header.
+fn authenticate_operator<D>(deps: &D, headers: &HeaderMap) -> std::result::Result<String, AppError>
+where
+    D: Deps,
+{
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| deps.operators().authenticate(token))
+        .map(|name| name.to_string())
+        .ok_or(AppError::Unauthorized)
+}
+
+fn message_id(id: String) -> std::result::Result<MessageId, AppError> {
+    MessageId::new(id).map_err(|_| AppError::BadRequest("invalid message id".into()))
+}
+
+async fn handle_get_message<D>(
+    State(deps): State<D>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<TransportMessageState>, AppError>
+where
+    D: Deps,
+{
+    let message_state = deps.get_message_handler().get_message(&message_id(id)?)?;
+    let transport_message_state: TransportMessageState = (&message_state).into();
+    Ok(transport_message_state.into())
 }
 
 async fn handle_state_updates<D>(ws: WebSocketUpgrade, State(deps): State<D>) -> Response
 where
-    D: Deps + Send + 'static,
+    D: Deps + Sync + Send + 'static,
 {
     ws.on_upgrade(move |websocket| handle_socket(websocket, deps))
 }
 
 async fn handle_socket<D>(websocket: WebSocket, deps: D)
 where
-    D: Deps + Send + 'static,
+    D: Deps + Sync + Send + 'static,
 {
     let (cancel, _) = broadcast::channel(1);
     let mut cancel_1 = cancel.subscribe();
@@ -184,49 +465,23 @@ where
     task::spawn(async move {
         let mut s1 = deps.subscriber().subscribe_to_message_added_to_queue();
         let mut s2 = deps.subscriber().subscribe_to_clacks_updated();
+        let mut s3 = deps.subscriber().subscribe_to_queue_changed();
 
-        let state = deps.get_state_handler().get_state().unwrap();
-        let transport_state: TransportState = (&state).into();
-        let string_state = serde_json::to_string(&transport_state).unwrap();
-        let message = ws::Message::text(string_state);
-        match socket_sender.send(message).await {
-            Ok(_) => {}
-            Err(_) => {
-                return;
-            }
-        };
+        if send_current_state(&deps, &mut socket_sender).await.is_err() {
+            return;
+        }
 
         loop {
-            tokio::select! {
-                _ = s1.recv() => {
-                    let state= deps.get_state_handler().get_state().unwrap();
-                    let transport_state: TransportState = (&state).into();
-                    let string_state = serde_json::to_string(&transport_state).unwrap();
-                    let message = ws::Message::text(string_state);
-                    match socket_sender.send(message).await {
-                        Ok(_) => {
-                        },
-                        Err(_) => {
-                            return;
-                        },
-                    };
-                }
-                _ = s2.recv() => {
-                    let state= deps.get_state_handler().get_state().unwrap();
-                    let transport_state: TransportState = (&state).into();
-                    let string_state = serde_json::to_string(&transport_state).unwrap();
-                    let message = ws::Message::text(string_state);
-                    match socket_sender.send(message).await {
-                        Ok(_) => {
-                        },
-                        Err(_) => {
-                            return;
-                        },
-                    };
-                },
+            let sent = tokio::select! {
+                _ = s1.recv() => send_current_state(&deps, &mut socket_sender).await,
+                _ = s2.recv() => send_current_state(&deps, &mut socket_sender).await,
+                _ = s3.recv() => send_current_state(&deps, &mut socket_sender).await,
                 _ = cancel_1.recv() => {
                     return;
                 }
+            };
+            if sent.is_err() {
+                return;
             }
         }
     });
@@ -241,6 +496,19 @@ where
     cancel.send(()).unwrap();
 }
 
+async fn send_current_state<D>(
+    deps: &D,
+    socket: &mut SplitSink<WebSocket, ws::Message>,
+) -> std::result::Result<(), axum::Error>
+where
+    D: Deps,
+{
+    let state = deps.get_state_handler().get_state().unwrap();
+    let transport_state: TransportState = (&state).into();
+    let string_state = serde_json::to_string(&transport_state).unwrap();
+    socket.send(ws::Message::text(string_state)).await
+}
+
 async fn handle_get_config<D>(
     State(deps): State<D>,
 ) -> std::result::Result<Json<TransportConfig>, AppError>
@@ -256,6 +524,7 @@ where
 #[serde(rename_all = "camelCase")]
 struct TransportConfig {
     supported_characters: Vec<String>,
+    phrases: Vec<String>,
     max_message_len_in_bytes: usize,
 }
 
@@ -263,16 +532,162 @@ impl From<Config> for TransportConfig {
     fn from(value: Config) -> Self {
         Self {
             supported_characters: value.supported_characters().into(),
+            phrases: value.phrases().into(),
             max_message_len_in_bytes: value.max_message_len_in_bytes(),
         }
     }
 }
 
+async fn handle_get_encoding<D>(
+    State(deps): State<D>,
+) -> std::result::Result<Json<TransportEncodingTable>, AppError>
+where
+    D: Deps,
+{
+    let encoding_table = deps.get_encoding_handler().get_encoding()?;
+    let transport_encoding_table: TransportEncodingTable = (&encoding_table).into();
+    Ok(transport_encoding_table.into())
+}
+
+async fn handle_get_render_frame<D>(
+    State(deps): State<D>,
+    Query(query): Query<RenderFrameQuery>,
+) -> std::result::Result<Response<Body>, AppError>
+where
+    D: Deps,
+{
+    let open_shutters = query
+        .open_shutters
+        .unwrap_or_default()
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| ShutterLocation::try_from(v.to_string()))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|_| AppError::BadRequest("invalid shutter location".into()))?;
+    let shutter_positions = ShutterPositions::new(&open_shutters)
+        .map_err(|_| AppError::BadRequest("invalid shutter positions".into()))?;
+    let command = RenderFrame::new(shutter_positions, image_format(query.format)?);
+    let image = deps.render_handler().render_frame(command)?;
+    image_response(image)
+}
+
+async fn handle_get_render_message<D>(
+    State(deps): State<D>,
+    Query(query): Query<RenderMessageQuery>,
+) -> std::result::Result<Response<Body>, AppError>
+where
+    D: Deps,
+{
+    let command = RenderMessage::new(
+        query.message,
+        query.animated.unwrap_or(false),
+        image_format(query.format)?,
+    );
+    let image = deps.render_handler().render_message(command)?;
+    image_response(image)
+}
+
+async fn handle_get_render_chart<D>(
+    State(deps): State<D>,
+    Query(query): Query<RenderChartQuery>,
+) -> std::result::Result<Response<Body>, AppError>
+where
+    D: Deps,
+{
+    let command = RenderChart::new(image_format(query.format)?);
+    let image = deps.render_handler().render_chart(command)?;
+    image_response(image)
+}
+
+fn image_format(format: Option<String>) -> std::result::Result<ImageFormat, AppError> {
+    match format {
+        Some(format) => Ok(ImageFormat::try_from(format.as_str())?),
+        None => Ok(ImageFormat::Svg),
+    }
+}
+
+fn image_response(image: Image) -> std::result::Result<Response<Body>, AppError> {
+    let resp = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", image.format().content_type())
+        .body(Body::from(image.data().to_vec()))?;
+    Ok(resp)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderFrameQuery {
+    open_shutters: Option<String>,
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderMessageQuery {
+    message: String,
+    animated: Option<bool>,
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderChartQuery {
+    format: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportEncodingTable {
+    name: String,
+    version: String,
+    symbols: Vec<TransportEncodingSymbol>,
+}
+
+impl From<&EncodingTable> for TransportEncodingTable {
+    fn from(value: &EncodingTable) -> Self {
+        Self {
+            name: value.name().to_string(),
+            version: value.version().to_string(),
+            symbols: value.symbols().iter().map(|v| v.into()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportEncodingSymbol {
+    kind: String,
+    character: Option<String>,
+    plane: Option<String>,
+    open_shutters: TransportShutterPositions,
+}
+
+impl From<&EncodingSymbol> for TransportEncodingSymbol {
+    fn from(value: &EncodingSymbol) -> Self {
+        let (kind, character) = match value.element() {
+            MessageComponent::Character(ch) => ("CHARACTER", Some(ch.to_string())),
+            MessageComponent::Shift => ("SHIFT", None),
+            MessageComponent::Checksum => ("CHECKSUM", None),
+            MessageComponent::End => ("END", None),
+            MessageComponent::Cancel => ("CANCEL", None),
+        };
+        Self {
+            kind: kind.to_string(),
+            character,
+            plane: value.plane().map(|v| v.to_string()),
+            open_shutters: value.shutter_positions().into(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TransportState {
     current_message: Option<TransportCurrentMessage>,
-    queue: Vec<TransportEncodedMessage>,
+    queue: Vec<TransportQueuedMessage>,
+    paused: Option<TransportPause>,
+    // set during quiet hours
+    next_active_at: Option<String>,
 }
 
 impl From<&app::State> for TransportState {
@@ -280,47 +695,177 @@ impl From<&app::State> for TransportState {
         Self {
             current_message: value.current_message().map(|v| v.into()),
             queue: value.queue().iter().map(|v| v.into()).collect(),
+            paused: value.pause().map(|v| v.into()),
+            next_active_at: value.quiet_period().map(|v| v.until().format("%+")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportPause {
+    reason: String,
+    paused_by: String,
+    paused_at: String,
+    accept_messages: bool,
+}
+
+impl From<&Pause> for TransportPause {
+    fn from(value: &Pause) -> Self {
+        Self {
+            reason: value.reason().to_string(),
+            paused_by: value.paused_by().to_string(),
+            paused_at: value.paused_at().format("%+"),
+            accept_messages: value.accept_messages(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportMessageState {
+    id: String,
+    status: String,
+    position: Option<usize>,
+    submitted_at: String,
+    transmission_started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+impl From<&MessageState> for TransportMessageState {
+    fn from(value: &MessageState) -> Self {
+        let record = value.record();
+        Self {
+            id: value.id().to_string(),
+            status: value.status().into(),
+            position: value.position(),
+            submitted_at: record.submitted_at().format("%+"),
+            transmission_started_at: record.transmission_started_at().map(|v| v.format("%+")),
+            finished_at: record.finished_at().map(|v| v.format("%+")),
+        }
+    }
+}
+
+impl From<MessageStatus> for String {
+    fn from(value: MessageStatus) -> Self {
+        match value {
+            MessageStatus::Queued => "QUEUED",
+            MessageStatus::Transmitting => "TRANSMITTING",
+            MessageStatus::Transmitted => "TRANSMITTED",
+            MessageStatus::Rejected => "REJECTED",
+            MessageStatus::Expired => "EXPIRED",
+            MessageStatus::Aborted => "ABORTED",
         }
+        .into()
     }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TransportCurrentMessage {
+    id: Option<String>,
     before: Vec<TransportEncodedMessagePart>,
     current: Option<TransportEncodedMessagePart>,
     after: Vec<TransportEncodedMessagePart>,
+    interrupted: Option<TransportInterruptedMessage>,
 }
 
 impl From<&CurrentMessage> for TransportCurrentMessage {
     fn from(value: &CurrentMessage) -> Self {
         Self {
+            id: value.id().map(|v| v.to_string()),
             before: value.before().iter().map(|v| v.into()).collect(),
             current: value.current().map(|v| v.into()),
             after: value.after().iter().map(|v| v.into()).collect(),
+            interrupted: value.interrupted().map(|v| v.into()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportInterruptedMessage {
+    id: Option<String>,
+    before: Vec<TransportEncodedMessagePart>,
+    after: Vec<TransportEncodedMessagePart>,
+    mode: String,
+    interrupted: Option<Box<TransportInterruptedMessage>>,
+}
+
+impl From<&InterruptedMessage> for TransportInterruptedMessage {
+    fn from(value: &InterruptedMessage) -> Self {
+        Self {
+            id: value.id().map(|v| v.to_string()),
+            before: value.before().iter().map(|v| v.into()).collect(),
+            after: value.after().iter().map(|v| v.into()).collect(),
+            mode: value.mode().into(),
+            interrupted: value.interrupted().map(|v| Box::new(v.into())),
+        }
+    }
+}
+
+impl From<PreemptMode> for String {
+    fn from(value: PreemptMode) -> Self {
+        match value {
+            PreemptMode::Resume => "RESUME",
+            PreemptMode::Restart => "RESTART",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<String> for PreemptMode {
+    type Error = Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "RESUME" => Ok(PreemptMode::Resume),
+            "RESTART" => Ok(PreemptMode::Restart),
+            other => Err(anyhow!("invalid preempt mode: {}", other).into()),
         }
     }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct TransportEncodedMessage {
+struct TransportQueuedMessage {
+    id: String,
     parts: Vec<TransportEncodedMessagePart>,
+    priority: String,
+    submitted_at: String,
+    not_before: Option<String>,
 }
 
-impl From<&EncodedMessage> for TransportEncodedMessage {
-    fn from(value: &EncodedMessage) -> Self {
+impl From<&QueuedMessage> for TransportQueuedMessage {
+    fn from(value: &QueuedMessage) -> Self {
         Self {
-            parts: value.parts().iter().map(|x| x.into()).collect(),
+            id: value.id().to_string(),
+            parts: value.message().parts().iter().map(|x| x.into()).collect(),
+            priority: value.priority().into(),
+            submitted_at: value.submitted_at().format("%+"),
+            not_before: value.not_before().map(|v| v.format("%+")),
         }
     }
 }
 
+impl From<Priority> for String {
+    fn from(value: Priority) -> Self {
+        match value {
+            Priority::Operator => "OPERATOR",
+            Priority::Scheduled => "SCHEDULED",
+            Priority::Public => "PUBLIC",
+            Priority::Injected => "INJECTED",
+        }
+        .into()
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TransportEncodedMessagePart {
     kind: String,
     character: Option<String>,
+    plane: Option<String>,
     open_shutters: TransportShutterPositions,
 }
 
@@ -330,11 +875,31 @@ impl From<&EncodedMessagePart> for TransportEncodedMessagePart {
             MessageComponent::Character(ch) => Self {
                 kind: "CHARACTER".into(),
                 character: Some(ch.to_string()),
+                plane: value.plane().map(|v| v.to_string()),
+                open_shutters: value.shutter_positions().into(),
+            },
+            MessageComponent::Shift => Self {
+                kind: "SHIFT".into(),
+                character: None,
+                plane: value.plane().map(|v| v.to_string()),
+                open_shutters: value.shutter_positions().into(),
+            },
+            MessageComponent::Checksum => Self {
+                kind: "CHECKSUM".to_string(),
+                character: None,
+                plane: None,
                 open_shutters: value.shutter_positions().into(),
             },
             MessageComponent::End => Self {
                 kind: "END".to_string(),
                 character: None,
+                plane: None,
+                open_shutters: value.shutter_positions().into(),
+            },
+            MessageComponent::Cancel => Self {
+                kind: "CANCEL".to_string(),
+                character: None,
+                plane: None,
                 open_shutters: value.shutter_positions().into(),
             },
         }
@@ -347,7 +912,12 @@ struct TransportShutterPositions(Vec<String>);
 
 impl From<&ShutterPositions> for TransportShutterPositions {
     fn from(value: &ShutterPositions) -> Self {
-        Self(value.open_shutters().map(|v| v.into()).collect())
+        Self(
+            ShutterLocation::iter()
+                .filter(|v| matches!(value.get_position(v), ShutterPosition::Open))
+                .map(|v| v.into())
+                .collect(),
+        )
     }
 }
 
@@ -366,15 +936,136 @@ impl From<&ShutterLocation> for String {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct PostQueueRequest {
     message: String,
+    not_before: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PostMoveQueuedMessageRequest {
+    position: usize,
+}
+
+#[derive(Deserialize)]
+struct PostPreemptTransmissionRequest {
+    message: String,
+    mode: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostRecurringRequest {
+    name: String,
+    schedule: String,
+    message: String,
+    catch_up: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportRecurringMessage {
+    name: String,
+    schedule: String,
+    message: String,
+    catch_up: String,
+    configured: bool,
+    next_run: Option<String>,
+}
+
+impl From<&RecurringMessageEntry> for TransportRecurringMessage {
+    fn from(value: &RecurringMessageEntry) -> Self {
+        let message = value.message();
+        Self {
+            name: message.name().to_string(),
+            schedule: message.schedule().expression().to_string(),
+            message: message.message().text().to_string(),
+            catch_up: message.catch_up().into(),
+            configured: value.configured(),
+            next_run: value.next_run().map(|v| v.format("%+")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostPauseRequest {
+    reason: String,
+    #[serde(default)]
+    accept_messages: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportAddMessageToQueueResult {
+    id: String,
+    substitutions: Vec<TransportSubstitution>,
+    held_until: Option<String>,
+}
+
+impl From<&AddMessageToQueueResult> for TransportAddMessageToQueueResult {
+    fn from(value: &AddMessageToQueueResult) -> Self {
+        Self {
+            id: value.id().to_string(),
+            substitutions: value.substitutions().iter().map(|v| v.into()).collect(),
+            held_until: value.held_until().map(|v| v.format("%+")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportPreemptTransmissionResult {
+    id: String,
+    substitutions: Vec<TransportSubstitution>,
+}
+
+impl From<&PreemptTransmissionResult> for TransportPreemptTransmissionResult {
+    fn from(value: &PreemptTransmissionResult) -> Self {
+        Self {
+            id: value.id().to_string(),
+            substitutions: value.substitutions().iter().map(|v| v.into()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransportSubstitution {
+    from: String,
+    to: String,
+}
+
+impl From<&Substitution> for TransportSubstitution {
+    fn from(value: &Substitution) -> Self {
+        Self {
+            from: value.from().to_string(),
+            to: value.to().to_string(),
+        }
+    }
 }
 
 pub trait Deps {
     fn add_message_to_queue_handler(&self) -> &impl AddMessageToQueueHandler;
     fn get_state_handler(&self) -> &impl GetStateHandler;
+    fn get_message_handler(&self) -> &impl GetMessageHandler;
+    fn get_scheduled_messages_handler(&self) -> &impl GetScheduledMessagesHandler;
+    fn cancel_scheduled_message_handler(&self) -> &impl CancelScheduledMessageHandler;
+    fn remove_message_from_queue_handler(&self) -> &impl RemoveMessageFromQueueHandler;
+    fn move_message_in_queue_handler(&self) -> &impl MoveMessageInQueueHandler;
+    fn clear_queue_handler(&self) -> &impl ClearQueueHandler;
+    fn abort_transmission_handler(&self) -> &impl AbortTransmissionHandler;
+    fn preempt_transmission_handler(&self) -> &impl PreemptTransmissionHandler;
+    fn pause_clacks_handler(&self) -> &impl PauseClacksHandler;
+    fn get_recurring_messages_handler(&self) -> &impl GetRecurringMessagesHandler;
+    fn add_recurring_message_handler(&self) -> &impl AddRecurringMessageHandler;
+    fn remove_recurring_message_handler(&self) -> &impl RemoveRecurringMessageHandler;
+    fn resume_clacks_handler(&self) -> &impl ResumeClacksHandler;
     fn get_config_handler(&self) -> &impl GetConfigHandler;
+    fn get_encoding_handler(&self) -> &impl GetEncodingHandler;
+    fn render_handler(&self) -> &impl RenderHandler;
 
+    fn operators(&self) -> &Operators;
     fn metrics(&self) -> &prometheus::Registry;
     fn subscriber(&self) -> &impl EventSubscriber;
 }
@@ -382,6 +1073,7 @@ pub trait Deps {
 pub trait EventSubscriber {
     fn subscribe_to_clacks_updated(&self) -> Receiver<()>;
     fn subscribe_to_message_added_to_queue(&self) -> Receiver<()>;
+    fn subscribe_to_queue_changed(&self) -> Receiver<()>;
 }
 
 impl EventSubscriber for adapters::PubSub {
@@ -392,10 +1084,17 @@ impl EventSubscriber for adapters::PubSub {
     fn subscribe_to_message_added_to_queue(&self) -> Receiver<()> {
         self.subscribe_to_message_added_to_queue()
     }
+
+    fn subscribe_to_queue_changed(&self) -> Receiver<()> {
+        self.subscribe_to_queue_changed()
+    }
 }
 
 enum AppError {
     BadRequest(String),
+    NotFound(String),
+    Conflict(String),
+    Unauthorized,
     UnknownError,
 }
 
@@ -403,6 +1102,9 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, message),
+            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".into()),
             AppError::UnknownError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".into(),
@@ -419,6 +1121,35 @@ where
     fn from(err: E) -> Self {
         match err.into() {
             Error::QueueIsFull => Self::BadRequest("Queue is full".into()),
+            Error::CannotEncodeCharacter(c) => {
+                Self::BadRequest(format!("Cannot encode character '{}'", c))
+            }
+            Error::InvalidMessage(reason) => {
+                Self::BadRequest(format!("Invalid message: {}", reason))
+            }
+            Error::UnsupportedImageFormat(reason) => {
+                Self::BadRequest(format!("Unsupported image format: {}", reason))
+            }
+            Error::MessageNotFound(id) => Self::NotFound(format!("Message not found: {}", id)),
+            Error::NoMessageIsBeingTransmitted => {
+                Self::Conflict("No message is being transmitted".into())
+            }
+            Error::ClacksIsPaused => Self::Conflict("Clacks is paused".into()),
+            Error::ClacksIsNotPaused => Self::Conflict("Clacks is not paused".into()),
+            Error::ClacksIsQuiet(until) => Self::Conflict(format!(
+                "Quiet hours, messages are accepted again from {}",
+                until.format("%Y-%m-%d %H:%M %:z")
+            )),
+            Error::RecurringMessageNotFound(name) => {
+                Self::NotFound(format!("Recurring message not found: {}", name))
+            }
+            Error::RecurringMessageAlreadyExists(name) => {
+                Self::Conflict(format!("Recurring message already exists: {}", name))
+            }
+            Error::RecurringMessageIsConfigured(name) => Self::Conflict(format!(
+                "Recurring message is defined in the config: {}",
+                name
+            )),
             _ => Self::UnknownError,
         }
     }
@@ -429,3 +1160,31 @@ where
 struct TransportError {
     message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::presets::EncodingPreset;
+
+    #[test]
+    fn encoding_response_includes_the_version_and_shifts() -> Result<()> {
+        let encoding = EncodingPreset::Braille.encoding();
+        let handler =
+            app::get_encoding::GetEncodingHandler::new(encoding.clone(), adapters::Metrics::new()?);
+
+        let encoding_table: TransportEncodingTable = (&handler.get_encoding()?).into();
+        let json = serde_json::to_value(&encoding_table).map_err(anyhow::Error::from)?;
+
+        assert_eq!("braille", json["name"]);
+        assert_eq!(encoding.version(), json["version"]);
+        let shifted_planes: Vec<&str> = json["symbols"]
+            .as_array()
+            .ok_or(anyhow!("symbols should be an array"))?
+            .iter()
+            .filter(|symbol| symbol["kind"] == "SHIFT")
+            .filter_map(|symbol| symbol["plane"].as_str())
+            .collect();
+        assert_eq!(vec!["text", "numbers", "capitals"], shifted_planes);
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
-use crate::app::UpdateClacksHandler;
+use crate::app::{EnqueueRecurringMessagesHandler, UpdateClacksHandler};
 use log::{debug, error};
 use std::time::Duration;
 use tokio::time::sleep;
 
-static UPDATE_CLACKS_EVERY: Duration = Duration::from_millis(100);
+/// Also the step by which simulations advance their clock.
+pub static UPDATE_CLACKS_EVERY: Duration = Duration::from_millis(100);
+static ENQUEUE_RECURRING_MESSAGES_EVERY: Duration = Duration::from_secs(1);
 
 pub struct UpdateClacksTimer<H: UpdateClacksHandler> {
     handler: H,
@@ -31,3 +33,30 @@ where
         }
     }
 }
+
+pub struct EnqueueRecurringMessagesTimer<H: EnqueueRecurringMessagesHandler> {
+    handler: H,
+}
+
+impl<H> EnqueueRecurringMessagesTimer<H>
+where
+    H: EnqueueRecurringMessagesHandler,
+{
+    pub fn new(handler: H) -> Self {
+        Self { handler }
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            match self.handler.handle() {
+                Ok(_) => {
+                    debug!("executed EnqueueRecurringMessages in timer");
+                }
+                Err(err) => {
+                    error!("error executing EnqueueRecurringMessages in timer: {}", err);
+                }
+            }
+            sleep(ENQUEUE_RECURRING_MESSAGES_EVERY).await;
+        }
+    }
+}
@@ -1,13 +1,76 @@
 import axios, { AxiosResponse } from 'axios';
-import { CurrentMessage, Message } from '@/types';
+import {
+  CatchUp, CurrentMessage, MessagePartKind, MessageStatus, Pause, PreemptMode, QueuedMessage,
+  RecurringMessage, ShutterLocation,
+} from '@/types';
 
 export class API {
   getConfig(): Promise<AxiosResponse<ConfigResponse>> {
     return axios.get<ConfigResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/config`);
   }
 
-  addMessageToQueue(request: AddMessageToQueueRequest): Promise<AxiosResponse<ErrorResponse>> {
-    return axios.post<ErrorResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/queue`, request);
+  getEncoding(): Promise<AxiosResponse<EncodingResponse>> {
+    return axios.get<EncodingResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/encoding`);
+  }
+
+  addMessageToQueue(request: AddMessageToQueueRequest): Promise<AxiosResponse<AddMessageToQueueResponse>> {
+    return axios.post<AddMessageToQueueResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/queue`, request);
+  }
+
+  scheduleMessage(request: AddMessageToQueueRequest, token: string): Promise<AxiosResponse<AddMessageToQueueResponse>> {
+    return axios.post<AddMessageToQueueResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/queue`, request, operatorHeaders(token));
+  }
+
+  getScheduledMessages(): Promise<AxiosResponse<QueuedMessage[]>> {
+    return axios.get<QueuedMessage[]>(`${process.env.VUE_APP_BACKEND_URL}/api/scheduled`);
+  }
+
+  cancelScheduledMessage(id: string, token: string): Promise<AxiosResponse<void>> {
+    return axios.delete(`${process.env.VUE_APP_BACKEND_URL}/api/scheduled/${id}`, operatorHeaders(token));
+  }
+
+  getRecurringMessages(): Promise<AxiosResponse<RecurringMessage[]>> {
+    return axios.get<RecurringMessage[]>(`${process.env.VUE_APP_BACKEND_URL}/api/recurring`);
+  }
+
+  addRecurringMessage(request: AddRecurringMessageRequest, token: string): Promise<AxiosResponse<void>> {
+    return axios.post(`${process.env.VUE_APP_BACKEND_URL}/api/recurring`, request, operatorHeaders(token));
+  }
+
+  removeRecurringMessage(name: string, token: string): Promise<AxiosResponse<void>> {
+    return axios.delete(`${process.env.VUE_APP_BACKEND_URL}/api/recurring/${encodeURIComponent(name)}`, operatorHeaders(token));
+  }
+
+  getMessage(id: string): Promise<AxiosResponse<MessageResponse>> {
+    return axios.get<MessageResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/messages/${encodeURIComponent(id)}`);
+  }
+
+  removeMessageFromQueue(id: string, token: string): Promise<AxiosResponse<void>> {
+    return axios.delete(`${process.env.VUE_APP_BACKEND_URL}/api/queue/${encodeURIComponent(id)}`, operatorHeaders(token));
+  }
+
+  moveMessageInQueue(id: string, request: MoveMessageInQueueRequest, token: string): Promise<AxiosResponse<void>> {
+    return axios.post(`${process.env.VUE_APP_BACKEND_URL}/api/queue/${encodeURIComponent(id)}/move`, request, operatorHeaders(token));
+  }
+
+  clearQueue(token: string): Promise<AxiosResponse<void>> {
+    return axios.delete(`${process.env.VUE_APP_BACKEND_URL}/api/queue`, operatorHeaders(token));
+  }
+
+  abortTransmission(token: string): Promise<AxiosResponse<void>> {
+    return axios.post(`${process.env.VUE_APP_BACKEND_URL}/api/transmission/abort`, {}, operatorHeaders(token));
+  }
+
+  preemptTransmission(request: PreemptTransmissionRequest, token: string): Promise<AxiosResponse<PreemptTransmissionResponse>> {
+    return axios.post<PreemptTransmissionResponse>(`${process.env.VUE_APP_BACKEND_URL}/api/transmission/preempt`, request, operatorHeaders(token));
+  }
+
+  pause(request: PauseRequest, token: string): Promise<AxiosResponse<void>> {
+    return axios.post(`${process.env.VUE_APP_BACKEND_URL}/api/pause`, request, operatorHeaders(token));
+  }
+
+  resume(token: string): Promise<AxiosResponse<void>> {
+    return axios.post(`${process.env.VUE_APP_BACKEND_URL}/api/resume`, {}, operatorHeaders(token));
   }
 
   stateUpdatesWS(): WebSocket {
@@ -15,18 +78,85 @@ export class API {
   }
 }
 
+function operatorHeaders(token: string) {
+  return { headers: { Authorization: `Bearer ${token}` } };
+}
+
 export interface ConfigResponse {
     supportedCharacters: string[];
+    phrases: string[];
     maxMessageLenInBytes: number;
 }
 
+export interface EncodingResponse {
+    name: string;
+    version: string;
+    symbols: EncodingSymbol[];
+}
+
+export interface EncodingSymbol {
+    kind: MessagePartKind;
+    character?: string;
+    plane?: string;
+    openShutters: ShutterLocation[];
+}
+
 export interface AddMessageToQueueRequest {
     message: string;
+    notBefore?: string;
+}
+
+export interface AddMessageToQueueResponse {
+    id: string;
+    substitutions: Substitution[];
+    heldUntil?: string;
+}
+
+export interface AddRecurringMessageRequest {
+    name: string;
+    schedule: string;
+    message: string;
+    catchUp?: CatchUp;
+}
+
+export interface MoveMessageInQueueRequest {
+    position: number;
+}
+
+export interface PreemptTransmissionRequest {
+    message: string;
+    mode: PreemptMode;
+}
+
+export interface PreemptTransmissionResponse {
+    id: string;
+    substitutions: Substitution[];
+}
+
+export interface PauseRequest {
+    reason: string;
+    acceptMessages: boolean;
+}
+
+export interface MessageResponse {
+    id: string;
+    status: MessageStatus;
+    position?: number;
+    submittedAt: string;
+    transmissionStartedAt?: string;
+    finishedAt?: string;
+}
+
+export interface Substitution {
+    from: string;
+    to: string;
 }
 
 export interface StateUpdate {
     currentMessage?: CurrentMessage;
-    queue: Message[];
+    queue: QueuedMessage[];
+    paused?: Pause;
+    nextActiveAt?: string;
 }
 
 export interface ErrorResponse {
@@ -13,24 +13,88 @@ export enum ShutterPosition {
 }
 
 export interface CurrentMessage {
+    id?: string;
     before: MessagePart[];
     current?: MessagePart;
     after: MessagePart[];
+    interrupted?: InterruptedMessage;
+}
+
+export interface InterruptedMessage {
+    id?: string;
+    before: MessagePart[];
+    after: MessagePart[];
+    mode: PreemptMode;
+    interrupted?: InterruptedMessage;
+}
+
+export interface Pause {
+    reason: string;
+    pausedBy: string;
+    pausedAt: string;
+    acceptMessages: boolean;
+}
+
+export enum PreemptMode {
+    Resume = 'RESUME',
+    Restart = 'RESTART',
 }
 
 export interface Message {
     parts: MessagePart[];
 }
 
+export interface QueuedMessage extends Message {
+    id: string;
+    priority: Priority;
+    submittedAt: string;
+    notBefore?: string;
+}
+
+export enum Priority {
+    Operator = 'OPERATOR',
+    Scheduled = 'SCHEDULED',
+    Public = 'PUBLIC',
+    Injected = 'INJECTED',
+}
+
+export interface RecurringMessage {
+    name: string;
+    schedule: string;
+    message: string;
+    catchUp: CatchUp;
+    configured: boolean;
+    nextRun?: string;
+}
+
+export enum CatchUp {
+    Skip = 'SKIP',
+    Once = 'ONCE',
+    All = 'ALL',
+}
+
+export enum MessageStatus {
+    Queued = 'QUEUED',
+    Transmitting = 'TRANSMITTING',
+    Transmitted = 'TRANSMITTED',
+    Rejected = 'REJECTED',
+    Expired = 'EXPIRED',
+    Aborted = 'ABORTED',
+}
+
 export interface MessagePart {
     kind: MessagePartKind
     character?: string,
+    plane?: string,
     openShutters: ShutterLocation[],
 }
 
 export enum MessagePartKind {
     Character = 'CHARACTER',
+    Shift = 'SHIFT',
+    Checksum = 'CHECKSUM',
     End = 'END',
+    Cancel = 'CANCEL',
 }
 
 export interface DisplayedError {